@@ -1,26 +1,88 @@
-use crate::engine::rendering::{
-    backend::Backend, camera::Camera, configuration::Configuration, size::Size, Renderer,
+use crate::engine::{
+    error::EngineError,
+    input::Input,
+    rendering::{
+        backend::Backend, camera::Camera, configuration::Configuration, graph::RenderGraph,
+        size::Size, Renderer,
+    },
 };
 use crossbeam::channel::Receiver;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
 use wgpu::Device;
 use winit::event::WindowEvent;
 
-use super::window::{handler::Event, window::Window};
+use super::{
+    schedule::Schedule,
+    time::{FixedTimestep, Time},
+    window::{events::WindowEventLoop, handler::Event, settings::WindowSettings, window::Window},
+};
 
-pub struct Engine<'a, C: Configuration> {
-    renderer: Renderer<'a, C>,
-    exited: AtomicBool,
+/// Settings of `Engine::run`
+#[derive(Clone, Debug)]
+pub struct LoopOptions {
+    tick_rate: f32,
+    max_ticks_per_frame: u32,
+    fps_limit: Option<f32>,
 }
 
-impl<'a, C: Configuration> Engine<'a, C> {
-    pub fn new(configuration: C, backend: Backend<'a>) -> Self {
-        let renderer = Renderer::new(configuration, backend);
+impl LoopOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fixed updates per second
+    pub fn tick_rate(mut self, tick_rate: f32) -> Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// Updates run per frame at most, time beyond that is dropped after a stall
+    pub fn max_ticks_per_frame(mut self, max_ticks_per_frame: u32) -> Self {
+        self.max_ticks_per_frame = max_ticks_per_frame;
+        self
+    }
+
+    /// Sleeps after each frame to render at most `fps_limit` frames per second
+    pub fn fps_limit(mut self, fps_limit: Option<f32>) -> Self {
+        self.fps_limit = fps_limit;
+        self
+    }
+}
 
+impl Default for LoopOptions {
+    fn default() -> Self {
         Self {
+            tick_rate: 60.0,
+            max_ticks_per_frame: 5,
+            fps_limit: None,
+        }
+    }
+}
+
+/// Dropping the engine waits for the GPU and releases the renderer, its resources and the
+/// window, a new engine can be created afterwards
+pub struct Engine<C: Configuration> {
+    renderer: Renderer<C>,
+    input: Mutex<Input>,
+    exited: AtomicBool,
+}
+
+impl<C: Configuration> Engine<C> {
+    /// Fails like `Renderer::new`
+    pub fn new(configuration: C, backend: Backend) -> Result<Self, EngineError> {
+        let renderer = Renderer::new(configuration, backend)?;
+
+        Ok(Self {
             renderer,
+            input: Mutex::new(Input::default()),
             exited: AtomicBool::new(false),
-        }
+        })
     }
 
     pub fn handle_engine_events(&self) {
@@ -34,6 +96,16 @@ impl<'a, C: Configuration> Engine<'a, C> {
                             pixels_per_point: self.window().window().scale_factor() as f32,
                         });
                     }
+                    // Moved to a monitor with another DPI, a resize may follow
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        let (width, height) = self.window().dimension();
+
+                        self.renderer().resize(Size {
+                            width,
+                            height,
+                            pixels_per_point: scale_factor as f32,
+                        });
+                    }
                     WindowEvent::CloseRequested => {
                         self.exited.store(true, Ordering::Relaxed);
                     }
@@ -43,10 +115,107 @@ impl<'a, C: Configuration> Engine<'a, C> {
         }
     }
 
-    pub fn renderer(&self) -> &Renderer<'a, C> {
+    /// Pumps window events, runs fixed updates and renders until the engine exits
+    ///
+    /// Lost surfaces are reconfigured and timed out frames skipped. After the device was lost
+    /// the renderer recovers and rendering continues. Window events are consumed by `Input`,
+    /// `Input::events` has those of the current frame. While `Input` replays a recording, frames
+    /// get the recorded input and deltas, so the fixed updates run as in the recorded session.
+    /// Every frame executes a `RenderGraph::builtin` with the passes the render callbacks add.
+    pub fn run(
+        &mut self,
+        event_loop: &mut WindowEventLoop,
+        options: LoopOptions,
+        mut schedule: Schedule<'_, C>,
+    ) -> Result<(), EngineError> {
+        let mut timestep = FixedTimestep::new(options.tick_rate, options.max_ticks_per_frame);
+        let frame_time = options
+            .fps_limit
+            .map(|fps| Duration::from_secs_f32(1.0 / fps.max(f32::EPSILON)));
+
+        let mut time = Time {
+            tick: timestep.step(),
+            ..Default::default()
+        };
+
+        let mut last = Instant::now();
+
+        while !self.exited() {
+            let start = Instant::now();
+
+            event_loop.pump(Some(Duration::ZERO));
+            self.handle_engine_events();
+            // A replayed frame takes as long as when it was recorded
+            time.delta = self.input().update(self.window().events(), start - last);
+            last = start;
+
+            self.renderer.profiler().record("events", start.elapsed());
+
+            let update = Instant::now();
+
+            for _ in 0..timestep.advance(time.delta) {
+                schedule.update(self, &time);
+
+                time.elapsed += time.tick;
+                time.ticks += 1;
+            }
+
+            self.renderer.profiler().record("update", update.elapsed());
+
+            time.alpha = timestep.alpha();
+
+            match self.renderer.start_frame() {
+                Ok(frame) => {
+                    let render = Instant::now();
+
+                    let mut graph = RenderGraph::builtin(&self.renderer);
+                    schedule.render(self, &mut graph, &time);
+                    graph.execute(&frame)?;
+
+                    self.renderer.profiler().record("render", render.elapsed());
+
+                    self.renderer.finish_frame(frame);
+
+                    time.frames += 1;
+                }
+                Err(EngineError::SurfaceTimeout | EngineError::SurfaceLost) => {}
+                Err(EngineError::DeviceLost(_)) => pollster::block_on(self.renderer.recover())?,
+                Err(e) => return Err(e),
+            }
+
+            if let Some(frame_time) = frame_time {
+                if let Some(remaining) = frame_time.checked_sub(start.elapsed()) {
+                    std::thread::sleep(remaining);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Switches the window mode and present mode
+    pub fn apply_window_settings(&self, settings: &WindowSettings) {
+        self.window().apply_settings(settings);
+        self.renderer.set_present_mode(settings.present_mode);
+    }
+
+    /// Current window settings to persist, e.g. with `WindowSettings::save`
+    pub fn window_settings(&self) -> WindowSettings {
+        WindowSettings {
+            present_mode: self.renderer.backend().present_mode(),
+            ..self.window().settings()
+        }
+    }
+
+    pub fn renderer(&self) -> &Renderer<C> {
         &self.renderer
     }
 
+    /// Call `Input::update` once per frame unless the engine loop is driven by `run`
+    pub fn input(&self) -> MutexGuard<'_, Input> {
+        self.input.lock().unwrap()
+    }
+
     pub fn camera(&self) -> &Camera {
         self.renderer.camera()
     }
@@ -71,3 +240,25 @@ impl<'a, C: Configuration> Engine<'a, C> {
         self.exited.store(true, Ordering::Relaxed);
     }
 }
+
+#[test]
+fn test_engine_recreate() {
+    use crate::engine::rendering::test_backend;
+
+    for _ in 0..2 {
+        let Some(backend) = test_backend(8, 8) else {
+            return;
+        };
+
+        let engine = Engine::new((), backend).unwrap();
+
+        let frame = engine.renderer().start_frame().unwrap();
+        engine.renderer().finish_frame(frame);
+        assert!(engine.renderer().read_output().is_some());
+
+        // Nothing keeps the GPU objects alive once the engine is gone
+        let queue = std::sync::Arc::downgrade(engine.renderer().backend().queue());
+        drop(engine);
+        assert!(queue.upgrade().is_none());
+    }
+}
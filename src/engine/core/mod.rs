@@ -1,2 +1,4 @@
 pub mod engine;
+pub mod schedule;
+pub mod time;
 pub mod window;
@@ -0,0 +1,77 @@
+use super::{engine::Engine, time::Time};
+use crate::engine::rendering::{configuration::Configuration, graph::RenderGraph};
+
+type Update<'s, C> = Box<dyn FnMut(&Engine<C>, &Time) + 's>;
+type Render<'s, C> = Box<dyn FnMut(&Engine<C>, &mut RenderGraph<'s, C>, &Time) + 's>;
+
+/// Callbacks driven by `Engine::run`, each kind runs in the order it was added
+pub struct Schedule<'s, C: Configuration> {
+    updates: Vec<(&'static str, Update<'s, C>)>,
+    renders: Vec<(&'static str, Render<'s, C>)>,
+}
+
+impl<'s, C: Configuration> Schedule<'s, C> {
+    pub fn new() -> Self {
+        Self {
+            updates: Vec::new(),
+            renders: Vec::new(),
+        }
+    }
+
+    /// Runs `update` on every fixed tick, `Time::tick` is its time step
+    pub fn add_update<F: FnMut(&Engine<C>, &Time) + 's>(
+        &mut self,
+        name: &'static str,
+        update: F,
+    ) -> &mut Self {
+        self.updates.push((name, Box::new(update)));
+        self
+    }
+
+    /// Runs `render` once per frame to add passes to the frame's `RenderGraph::builtin`
+    /// Use `Time::alpha` to interpolate between the last two ticks
+    pub fn add_render<F: FnMut(&Engine<C>, &mut RenderGraph<'s, C>, &Time) + 's>(
+        &mut self,
+        name: &'static str,
+        render: F,
+    ) -> &mut Self {
+        self.renders.push((name, Box::new(render)));
+        self
+    }
+
+    /// Removes the update and render callbacks called `name`
+    pub fn remove(&mut self, name: &str) {
+        self.updates.retain(|(n, _)| *n != name);
+        self.renders.retain(|(n, _)| *n != name);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + use<'_, 's, C> {
+        self.updates
+            .iter()
+            .map(|(n, _)| *n)
+            .chain(self.renders.iter().map(|(n, _)| *n))
+    }
+
+    pub(super) fn update(&mut self, engine: &Engine<C>, time: &Time) {
+        for (_, update) in &mut self.updates {
+            update(engine, time);
+        }
+    }
+
+    pub(super) fn render(
+        &mut self,
+        engine: &Engine<C>,
+        graph: &mut RenderGraph<'s, C>,
+        time: &Time,
+    ) {
+        for (_, render) in &mut self.renders {
+            render(engine, graph, time);
+        }
+    }
+}
+
+impl<C: Configuration> Default for Schedule<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
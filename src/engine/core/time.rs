@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+/// Timing passed to `Schedule` callbacks
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Time {
+    /// Length of one fixed update
+    pub tick: Duration,
+    /// Time between the last two rendered frames
+    pub delta: Duration,
+    /// Progress towards the next fixed update in `0.0..1.0`, for interpolating between ticks
+    pub alpha: f32,
+    /// Simulated time, advanced by every fixed update
+    pub elapsed: Duration,
+    pub ticks: u64,
+    pub frames: u64,
+}
+
+/// Accumulates frame time and converts it into a number of fixed updates
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    /// Updates per frame before the remaining time is dropped, avoids a spiral of death
+    max_ticks: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f32, max_ticks: u32) -> Self {
+        Self {
+            step: Duration::from_secs_f32(1.0 / tick_rate.max(f32::EPSILON)),
+            accumulator: Duration::ZERO,
+            max_ticks: max_ticks.max(1),
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds `delta` and returns how many fixed updates are due
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+
+        let mut ticks = 0;
+
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            ticks += 1;
+
+            if ticks == self.max_ticks {
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+        }
+
+        ticks
+    }
+
+    /// Fraction of a step left in the accumulator
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f32() / self.step.as_secs_f32()).min(1.0)
+    }
+}
+
+#[test]
+fn test_fixed_timestep() {
+    let mut timestep = FixedTimestep::new(10.0, 3);
+
+    assert_eq!(timestep.advance(Duration::from_millis(50)), 0);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+
+    assert_eq!(timestep.advance(Duration::from_millis(175)), 2);
+    assert!((timestep.alpha() - 0.25).abs() < 1e-3);
+
+    // A long stall runs at most `max_ticks` updates and drops the rest
+    assert_eq!(timestep.advance(Duration::from_millis(5030)), 3);
+    assert_eq!(timestep.advance(Duration::ZERO), 0);
+    assert!((timestep.alpha() - 0.55).abs() < 1e-3);
+}
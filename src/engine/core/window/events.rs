@@ -1,27 +1,48 @@
 use std::time::Duration;
 
-use super::handler::{Event, WindowEventHandler};
-use crossbeam::channel::Sender;
-use winit::{event_loop::EventLoop, platform::pump_events::EventLoopExtPumpEvents};
+use super::{handler::WindowEventHandler, window::Window};
+use winit::{
+    event_loop::EventLoop, platform::pump_events::EventLoopExtPumpEvents, window::WindowAttributes,
+};
 
+/// Event loop shared by all windows, pumping it routes events to the window they belong to
+///
+/// Only one can be created per process. Keep it when an `Engine` is dropped and open the
+/// window of the next one with `create_window`.
 pub struct WindowEventLoop {
     events: EventLoop<()>,
     handler: WindowEventHandler,
 }
 
 impl WindowEventLoop {
-    pub fn new(
-        events: EventLoop<()>,
-        events_sender: Sender<Event>,
-        engine_events_sender: Sender<Event>,
-    ) -> Self {
+    pub fn new() -> Self {
         WindowEventLoop {
-            events,
-            handler: WindowEventHandler::new(events_sender, engine_events_sender),
+            events: EventLoop::new().unwrap(),
+            handler: WindowEventHandler::new(),
         }
     }
 
+    /// Opens a window that receives its events from this loop
+    pub fn create_window(&mut self, attributes: WindowAttributes) -> Window {
+        #[allow(deprecated)]
+        let window = self.events.create_window(attributes).unwrap();
+
+        let (events, engine_events) = self.handler.add_window(window.id());
+
+        Window::from_raw(window, events, engine_events)
+    }
+
+    pub fn handler(&self) -> &WindowEventHandler {
+        &self.handler
+    }
+
     pub fn pump(&mut self, timeout: Option<Duration>) {
         self.events.pump_app_events(timeout, &mut self.handler);
     }
 }
+
+impl Default for WindowEventLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,8 @@
-use crossbeam::channel::Sender;
+use crossbeam::channel::{unbounded, Receiver, Sender};
 use winit::{
     application::ApplicationHandler,
     event::{DeviceEvent, WindowEvent},
+    window::WindowId,
 };
 
 pub enum Event {
@@ -9,16 +10,95 @@ pub enum Event {
     DeviceEvent(DeviceEvent),
 }
 
-pub struct WindowEventHandler {
+/// Channels of one window
+struct Route {
+    id: WindowId,
     events_sender: Sender<Event>,
     engine_events_sender: Sender<Event>,
 }
 
+/// Routes the events of every window of an event loop to the channels of that window
+///
+/// Device events aren't tied to a window and go to the focused one, or the first window
+/// added while none is focused. Windows are removed once their `Window` was dropped.
+#[derive(Default)]
+pub struct WindowEventHandler {
+    routes: Vec<Route>,
+    focused: Option<WindowId>,
+}
+
 impl WindowEventHandler {
-    pub fn new(events_sender: Sender<Event>, engine_events_sender: Sender<Event>) -> Self {
-        Self {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the channels of window `id`, returns the receivers of all and of engine events
+    pub fn add_window(&mut self, id: WindowId) -> (Receiver<Event>, Receiver<Event>) {
+        let (events_sender, events_receiver) = unbounded();
+        let (engine_events_sender, engine_events_receiver) = unbounded();
+
+        self.routes.push(Route {
+            id,
             events_sender,
             engine_events_sender,
+        });
+
+        (events_receiver, engine_events_receiver)
+    }
+
+    pub fn remove_window(&mut self, id: WindowId) {
+        self.routes.retain(|r| r.id != id);
+
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+
+    pub fn window_ids(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.routes.iter().map(|r| r.id)
+    }
+
+    pub fn handle_window_event(&mut self, id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::Focused(true) => self.focused = Some(id),
+            WindowEvent::Focused(false) if self.focused == Some(id) => self.focused = None,
+            _ => {}
+        }
+
+        let Some(route) = self.routes.iter().find(|r| r.id == id) else {
+            return;
+        };
+
+        let engine_event = matches!(
+            event,
+            WindowEvent::CloseRequested
+                | WindowEvent::Resized(_)
+                | WindowEvent::ScaleFactorChanged { .. }
+        );
+
+        // Sending fails once the `Window` was dropped
+        let sent = (!engine_event
+            || route
+                .engine_events_sender
+                .send(Event::WindowEvent(event.clone()))
+                .is_ok())
+            && route.events_sender.send(Event::WindowEvent(event)).is_ok();
+
+        if !sent {
+            self.remove_window(id);
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: DeviceEvent) {
+        let route = self
+            .focused
+            .and_then(|id| self.routes.iter().find(|r| r.id == id))
+            .or(self.routes.first());
+
+        if let Some(route) = route {
+            if route.events_sender.send(Event::DeviceEvent(event)).is_err() {
+                self.remove_window(route.id);
+            }
         }
     }
 }
@@ -32,29 +112,46 @@ impl ApplicationHandler for WindowEventHandler {
         _device_id: winit::event::DeviceId,
         event: DeviceEvent,
     ) {
-        self.events_sender.send(Event::DeviceEvent(event)).unwrap();
+        self.handle_device_event(event);
     }
 
     fn window_event(
         &mut self,
         _event_loop: &winit::event_loop::ActiveEventLoop,
-        _window_id: winit::window::WindowId,
-        event: winit::event::WindowEvent,
+        window_id: WindowId,
+        event: WindowEvent,
     ) {
-        match event {
-            WindowEvent::CloseRequested => {
-                self.engine_events_sender
-                    .send(Event::WindowEvent(event.clone()))
-                    .unwrap();
-            }
-            WindowEvent::Resized(_) => {
-                self.engine_events_sender
-                    .send(Event::WindowEvent(event.clone()))
-                    .unwrap();
-            }
-            _ => {}
-        }
-
-        self.events_sender.send(Event::WindowEvent(event)).unwrap();
+        self.handle_window_event(window_id, event);
     }
 }
+
+#[test]
+fn test_route_events() {
+    let (main, preview) = (WindowId::from(1), WindowId::from(2));
+
+    let mut handler = WindowEventHandler::new();
+    let (main_events, main_engine_events) = handler.add_window(main);
+    let (preview_events, preview_engine_events) = handler.add_window(preview);
+
+    let motion = || DeviceEvent::MouseMotion { delta: (1.0, 0.0) };
+
+    handler.handle_window_event(preview, WindowEvent::CloseRequested);
+    assert!(main_events.is_empty() && main_engine_events.is_empty());
+    assert_eq!(preview_events.len(), 1);
+    assert_eq!(preview_engine_events.len(), 1);
+
+    // Device events follow the focus
+    handler.handle_device_event(motion());
+    assert_eq!(main_events.len(), 1);
+
+    handler.handle_window_event(preview, WindowEvent::Focused(true));
+    handler.handle_device_event(motion());
+    assert_eq!(preview_events.len(), 3);
+
+    drop((preview_events, preview_engine_events));
+    handler.handle_window_event(preview, WindowEvent::Focused(false));
+    assert_eq!(handler.window_ids().collect::<Vec<_>>(), [main]);
+
+    handler.handle_device_event(motion());
+    assert_eq!(main_events.len(), 2);
+}
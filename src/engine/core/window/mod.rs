@@ -1,4 +1,5 @@
 pub mod events;
 pub mod handler;
+pub mod settings;
 #[allow(clippy::module_inception)]
 pub mod window;
@@ -0,0 +1,218 @@
+use crate::engine::rendering::options::PresentMode;
+use std::{fmt::Display, path::Path, str::FromStr};
+
+/// How a window covers its monitor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// Borderless window covering the monitor, switches instantly
+    Borderless,
+    /// Takes over the monitor with the video mode closest to the settings
+    Exclusive,
+}
+
+impl WindowMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Borderless => "borderless",
+            WindowMode::Exclusive => "exclusive",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "windowed" => Some(WindowMode::Windowed),
+            "borderless" => Some(WindowMode::Borderless),
+            "exclusive" => Some(WindowMode::Exclusive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    /// Line number and content of a line that isn't `key = value`
+    Syntax(usize, String),
+    /// Line number and key of an unknown key or a value it doesn't accept
+    InvalidValue(usize, String),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "failed to access window settings: {}", e),
+            SettingsError::Syntax(line, content) => {
+                write!(
+                    f,
+                    "line {}: expected 'key = value', got '{}'",
+                    line, content
+                )
+            }
+            SettingsError::InvalidValue(line, key) => {
+                write!(f, "line {}: invalid value or unknown key '{}'", line, key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Window mode and presentation to persist between sessions
+///
+/// Apply with `Engine::apply_window_settings`, read the current ones with `Window::settings`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowSettings {
+    pub mode: WindowMode,
+    /// Index into `Window::monitors`, the current monitor if `None` or unplugged
+    pub monitor: Option<usize>,
+    /// Inner size when windowed, resolution in exclusive fullscreen
+    pub width: u32,
+    pub height: u32,
+    /// Of the exclusive video mode, the highest available if `None`
+    pub refresh_rate_millihertz: Option<u32>,
+    pub present_mode: PresentMode,
+}
+
+impl WindowSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses lines of `key = value`, `#` starts a comment, missing keys keep their default
+    pub fn parse(source: &str) -> Result<Self, SettingsError> {
+        let mut settings = Self::default();
+
+        for (n, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| SettingsError::Syntax(n + 1, line.to_string()))?;
+
+            let invalid = || SettingsError::InvalidValue(n + 1, key.to_string());
+
+            match key {
+                "mode" => settings.mode = WindowMode::from_name(value).ok_or_else(invalid)?,
+                "monitor" => settings.monitor = parse_optional(value).ok_or_else(invalid)?,
+                "size" => {
+                    let (width, height) = value
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+                        .ok_or_else(invalid)?;
+
+                    settings.width = width;
+                    settings.height = height;
+                }
+                "refresh_rate" => {
+                    settings.refresh_rate_millihertz = parse_optional(value).ok_or_else(invalid)?
+                }
+                "present_mode" => {
+                    settings.present_mode = match value {
+                        "vsync" => PresentMode::Vsync,
+                        "mailbox" => PresentMode::Mailbox,
+                        "immediate" => PresentMode::Immediate,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(settings)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SettingsError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(SettingsError::Io)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SettingsError> {
+        std::fs::write(path, self.to_string()).map_err(SettingsError::Io)
+    }
+}
+
+/// `auto` or a number
+fn parse_optional<T: FromStr>(value: &str) -> Option<Option<T>> {
+    match value {
+        "auto" => Some(None),
+        value => value.parse().ok().map(Some),
+    }
+}
+
+fn format_optional<T: Display>(value: Option<T>) -> String {
+    value.map_or("auto".to_string(), |v| v.to_string())
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            mode: WindowMode::Windowed,
+            monitor: None,
+            width: 1280,
+            height: 720,
+            refresh_rate_millihertz: None,
+            present_mode: PresentMode::Vsync,
+        }
+    }
+}
+
+impl Display for WindowSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "mode = {}", self.mode.name())?;
+        writeln!(f, "monitor = {}", format_optional(self.monitor))?;
+        writeln!(f, "size = {}x{}", self.width, self.height)?;
+        writeln!(
+            f,
+            "refresh_rate = {}",
+            format_optional(self.refresh_rate_millihertz)
+        )?;
+        writeln!(
+            f,
+            "present_mode = {}",
+            match self.present_mode {
+                PresentMode::Vsync => "vsync",
+                PresentMode::Mailbox => "mailbox",
+                PresentMode::Immediate => "immediate",
+            }
+        )
+    }
+}
+
+#[test]
+fn test_settings_round_trip() {
+    let settings = WindowSettings {
+        mode: WindowMode::Exclusive,
+        monitor: Some(1),
+        width: 2560,
+        height: 1440,
+        refresh_rate_millihertz: Some(143_998),
+        present_mode: PresentMode::Mailbox,
+    };
+
+    let source = settings.to_string();
+    assert_eq!(
+        source,
+        "mode = exclusive\nmonitor = 1\nsize = 2560x1440\nrefresh_rate = 143998\npresent_mode = mailbox\n"
+    );
+    assert_eq!(WindowSettings::parse(&source).unwrap(), settings);
+
+    let parsed = WindowSettings::parse("# saved\nmode = borderless\n").unwrap();
+    assert_eq!(parsed.mode, WindowMode::Borderless);
+    assert_eq!(parsed.width, WindowSettings::default().width);
+
+    assert!(matches!(
+        WindowSettings::parse("size = 10 by 10"),
+        Err(SettingsError::InvalidValue(1, _))
+    ));
+    assert!(matches!(
+        WindowSettings::parse("mode\n"),
+        Err(SettingsError::Syntax(1, _))
+    ));
+}
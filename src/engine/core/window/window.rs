@@ -1,10 +1,15 @@
-use crossbeam::channel::{unbounded, Receiver};
+use crossbeam::channel::Receiver;
 use std::sync::Arc;
-use winit::window::{CursorGrabMode, WindowAttributes};
-use winit::{event_loop::EventLoop, window::WindowId};
+use winit::{
+    dpi::PhysicalSize,
+    error::ExternalError,
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::{CursorGrabMode, CursorIcon, Fullscreen, Icon, WindowAttributes, WindowId},
+};
 
 use super::events::WindowEventLoop;
 use super::handler::Event;
+use super::settings::{WindowMode, WindowSettings};
 
 pub struct Window {
     window: Arc<winit::window::Window>,
@@ -13,29 +18,36 @@ pub struct Window {
 }
 
 impl Window {
+    /// Opens the first window with a new event loop, further windows share it
+    /// There is one event loop per process, later sessions use `WindowEventLoop::create_window`
     pub fn new(attributes: WindowAttributes) -> (Window, WindowEventLoop) {
-        let events = EventLoop::new().unwrap();
+        let mut events = WindowEventLoop::new();
+        let window = events.create_window(attributes);
 
-        #[allow(deprecated)]
-        let window = events.create_window(attributes).unwrap();
-
-        let (events_sender, events_receiver) = unbounded();
-        let (engine_events_sender, engine_events_receiver) = unbounded();
+        (window, events)
+    }
 
-        (
-            Window {
-                window: Arc::new(window),
-                events_receiver,
-                engine_events_receiver,
-            },
-            WindowEventLoop::new(events, events_sender, engine_events_sender),
-        )
+    pub(super) fn from_raw(
+        window: winit::window::Window,
+        events_receiver: Receiver<Event>,
+        engine_events_receiver: Receiver<Event>,
+    ) -> Self {
+        Self {
+            window: Arc::new(window),
+            events_receiver,
+            engine_events_receiver,
+        }
     }
 
     pub fn window(&self) -> &winit::window::Window {
         &self.window
     }
 
+    /// Shared handle for surfaces that outlive a borrow of this window
+    pub fn handle(&self) -> Arc<winit::window::Window> {
+        self.window.clone()
+    }
+
     pub fn dimension(&self) -> (u32, u32) {
         let size = self.window().inner_size();
         (size.width, size.height)
@@ -53,14 +65,136 @@ impl Window {
         self.window().id()
     }
 
-    pub fn set_grab(&self, grab: bool) {
+    /// Confines the cursor to the window, or locks it where confining is unsupported
+    pub fn set_grab(&self, grab: bool) -> Result<(), ExternalError> {
         if grab {
             self.window
                 .set_cursor_grab(CursorGrabMode::Confined)
                 .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Locked))
-                .unwrap();
         } else {
-            self.window.set_cursor_grab(CursorGrabMode::None).unwrap();
+            self.window.set_cursor_grab(CursorGrabMode::None)
+        }
+    }
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.window.set_cursor_visible(visible);
+    }
+
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.window.set_cursor(icon);
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Icon of the title bar and taskbar, e.g. from `Icon::from_rgba`, `None` for the default
+    pub fn set_icon(&self, icon: Option<Icon>) {
+        self.window.set_window_icon(icon);
+    }
+
+    /// Monitors in the order `WindowSettings::monitor` indexes
+    pub fn monitors(&self) -> Vec<MonitorHandle> {
+        self.window.available_monitors().collect()
+    }
+
+    /// Video modes of `monitor` for exclusive fullscreen, largest and fastest first
+    pub fn video_modes(&self, monitor: &MonitorHandle) -> Vec<VideoModeHandle> {
+        let mut modes = monitor.video_modes().collect::<Vec<_>>();
+
+        modes.sort_by_key(|m| {
+            std::cmp::Reverse((
+                m.size().width * m.size().height,
+                m.refresh_rate_millihertz(),
+                m.bit_depth(),
+            ))
+        });
+
+        modes
+    }
+
+    pub fn mode(&self) -> WindowMode {
+        match self.window.fullscreen() {
+            None => WindowMode::Windowed,
+            Some(Fullscreen::Borderless(_)) => WindowMode::Borderless,
+            Some(Fullscreen::Exclusive(_)) => WindowMode::Exclusive,
+        }
+    }
+
+    /// Switches the window mode, exclusive fullscreen falls back to borderless without a
+    /// video mode, see `WindowSettings` for how the monitor and video mode are chosen
+    pub fn apply_settings(&self, settings: &WindowSettings) {
+        let monitor = settings
+            .monitor
+            .and_then(|index| self.window.available_monitors().nth(index))
+            .or_else(|| self.window.current_monitor());
+
+        let fullscreen = match settings.mode {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            WindowMode::Exclusive => {
+                let modes = monitor
+                    .as_ref()
+                    .map(|m| self.video_modes(m))
+                    .unwrap_or_default();
+
+                let size = PhysicalSize::new(settings.width, settings.height);
+
+                let mode = modes
+                    .iter()
+                    .filter(|m| m.size() == size)
+                    .find(|m| {
+                        settings
+                            .refresh_rate_millihertz
+                            .is_none_or(|r| m.refresh_rate_millihertz() == r)
+                    })
+                    .or_else(|| modes.iter().find(|m| m.size() == size))
+                    .or(modes.first());
+
+                Some(match mode {
+                    Some(mode) => Fullscreen::Exclusive(mode.clone()),
+                    None => Fullscreen::Borderless(monitor),
+                })
+            }
+        };
+
+        let windowed = fullscreen.is_none();
+
+        self.window.set_fullscreen(fullscreen);
+
+        if windowed {
+            let _ = self
+                .window
+                .request_inner_size(PhysicalSize::new(settings.width, settings.height));
+        }
+    }
+
+    /// Current mode, monitor and size, `present_mode` is left at its default
+    /// See `Engine::window_settings`
+    pub fn settings(&self) -> WindowSettings {
+        let current = self.window.current_monitor();
+        let monitor =
+            current.and_then(|current| self.window.available_monitors().position(|m| m == current));
+
+        let (width, height, refresh_rate_millihertz) = match self.window.fullscreen() {
+            Some(Fullscreen::Exclusive(mode)) => (
+                mode.size().width,
+                mode.size().height,
+                Some(mode.refresh_rate_millihertz()),
+            ),
+            _ => {
+                let (width, height) = self.dimension();
+                (width, height, None)
+            }
+        };
+
+        WindowSettings {
+            mode: self.mode(),
+            monitor,
+            width,
+            height,
+            refresh_rate_millihertz,
+            ..Default::default()
         }
     }
 }
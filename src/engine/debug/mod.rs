@@ -0,0 +1,286 @@
+pub mod rendering;
+
+use crate::engine::{
+    rendering::camera::Camera,
+    ui::inspector::project,
+    voxel::{chunk::chunk_bounds, object::Object},
+};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform, Vector3};
+use egui::{Align2, Context, FontId, Id, LayerId, Order, Rgba};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+pub const RED: [f32; 4] = [1.0, 0.1, 0.1, 1.0];
+pub const GREEN: [f32; 4] = [0.1, 1.0, 0.1, 1.0];
+pub const BLUE: [f32; 4] = [0.2, 0.3, 1.0, 1.0];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    /// Linear RGBA, blended over the scene
+    pub color: [f32; 4],
+}
+
+/// Line list vertices of one frame, split by whether they are hidden behind geometry
+#[derive(Default)]
+pub struct DebugLines {
+    pub depth_tested: Vec<DebugVertex>,
+    pub overlay: Vec<DebugVertex>,
+}
+
+impl DebugLines {
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.overlay.is_empty()
+    }
+}
+
+struct Label {
+    position: Point3<f32>,
+    text: String,
+    color: [f32; 4],
+}
+
+/// Immediate mode gizmos in world space
+///
+/// Shapes accumulate until a `DebugPass` draws and clears them, labels until `render_labels`.
+/// Everything takes `&self`, so one instance can be shared by all systems of a frame.
+pub struct DebugDraw {
+    lines: Mutex<DebugLines>,
+    labels: Mutex<Vec<Label>>,
+    depth_test: AtomicBool,
+}
+
+impl DebugDraw {
+    /// Line segments per circle of `sphere`
+    const SEGMENTS: usize = 32;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn depth_test(&self) -> bool {
+        self.depth_test.load(Ordering::Relaxed)
+    }
+
+    /// Whether shapes added afterwards are hidden behind geometry or drawn on top of it
+    pub fn set_depth_test(&self, depth_test: bool) {
+        self.depth_test.store(depth_test, Ordering::Relaxed);
+    }
+
+    pub fn line(&self, start: Point3<f32>, end: Point3<f32>, color: [f32; 4]) {
+        self.lines(&[(start, end)], color);
+    }
+
+    /// Axis aligned box between two corners
+    pub fn aabb(&self, min: Point3<f32>, max: Point3<f32>, color: [f32; 4]) {
+        self.oriented_box(&Matrix4::identity(), min, max, color);
+    }
+
+    /// Box between two corners in the space `transform` maps to world space, e.g. a collider
+    pub fn oriented_box(
+        &self,
+        transform: &Matrix4<f32>,
+        min: Point3<f32>,
+        max: Point3<f32>,
+        color: [f32; 4],
+    ) {
+        let corner = |n: usize| {
+            transform.transform_point(Point3::new(
+                if n & 1 == 0 { min.x } else { max.x },
+                if (n >> 1) & 1 == 0 { min.y } else { max.y },
+                if n >> 2 == 0 { min.z } else { max.z },
+            ))
+        };
+
+        // Corners differing in one bit share an edge
+        let edges = (0..8)
+            .flat_map(|a| [1, 2, 4].map(|bit| (a, a | bit)))
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| (corner(a), corner(b)))
+            .collect::<Vec<_>>();
+
+        self.lines(&edges, color);
+    }
+
+    /// Bounds of every chunk of `object`
+    pub fn chunk_bounds(&self, object: &Object, color: [f32; 4]) {
+        for offset in object.chunks().map(|(offset, _)| offset) {
+            let (min, max) = chunk_bounds(*offset);
+            self.oriented_box(object.transform(), min, max, color);
+        }
+    }
+
+    /// Three great circles around the axes
+    pub fn sphere(&self, center: Point3<f32>, radius: f32, color: [f32; 4]) {
+        let axes = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+
+        let mut segments = Vec::with_capacity(3 * Self::SEGMENTS);
+
+        for axis in 0..3 {
+            let (u, v) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+            let point = |n: usize| {
+                let angle = n as f32 / Self::SEGMENTS as f32 * std::f32::consts::TAU;
+                center + (u * angle.cos() + v * angle.sin()) * radius
+            };
+
+            segments
+                .extend((0..Self::SEGMENTS).map(|n| (point(n), point((n + 1) % Self::SEGMENTS))));
+        }
+
+        self.lines(&segments, color);
+    }
+
+    /// Line with a four-sided head at `end`, a fifth of the length long
+    pub fn arrow(&self, start: Point3<f32>, end: Point3<f32>, color: [f32; 4]) {
+        let direction = end - start;
+        let length = direction.magnitude();
+
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let direction = direction / length;
+        let up = if direction.y.abs() < 0.99 {
+            Vector3::unit_y()
+        } else {
+            Vector3::unit_x()
+        };
+        let side = direction.cross(up).normalize();
+        let up = side.cross(direction);
+
+        let head = length * 0.2;
+        let base = end - direction * head;
+
+        let mut segments = vec![(start, end)];
+        segments.extend([side, -side, up, -up].map(|offset| (end, base + offset * head * 0.4)));
+
+        self.lines(&segments, color);
+    }
+
+    /// X, Y and Z axes of `transform` in red, green and blue, e.g. a light or an object origin
+    pub fn axes(&self, transform: &Matrix4<f32>, size: f32) {
+        let origin = transform.transform_point(Point3::origin());
+
+        for (axis, color) in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()]
+            .into_iter()
+            .zip([RED, GREEN, BLUE])
+        {
+            self.arrow(
+                origin,
+                transform.transform_point(Point3::from_vec(axis * size)),
+                color,
+            );
+        }
+    }
+
+    /// Text centered on a world position, drawn by `render_labels` and never depth tested
+    pub fn label(&self, position: Point3<f32>, text: impl Into<String>, color: [f32; 4]) {
+        self.labels.lock().unwrap().push(Label {
+            position,
+            text: text.into(),
+            color,
+        });
+    }
+
+    /// Drops everything accumulated so far
+    pub fn clear(&self) {
+        *self.lines.lock().unwrap() = DebugLines::default();
+        self.labels.lock().unwrap().clear();
+    }
+
+    /// Line vertices accumulated since the last call
+    pub fn take_lines(&self) -> DebugLines {
+        std::mem::take(&mut *self.lines.lock().unwrap())
+    }
+
+    /// Draws and clears the labels behind all windows, call it inside the egui pass
+    pub fn render_labels(&self, context: &Context, camera: &Camera) {
+        let labels = std::mem::take(&mut *self.labels.lock().unwrap());
+
+        let view_proj = camera.build_view_projection_matrix();
+        let screen = context.screen_rect();
+
+        let painter = context.layer_painter(LayerId::new(
+            Order::Background,
+            Id::new("vengine::debug_labels"),
+        ));
+
+        for label in labels {
+            let Some(position) = project(&view_proj, screen, label.position) else {
+                continue;
+            };
+
+            let [r, g, b, a] = label.color;
+
+            painter.text(
+                position,
+                Align2::CENTER_CENTER,
+                label.text,
+                FontId::monospace(12.0),
+                Rgba::from_rgba_unmultiplied(r, g, b, a).into(),
+            );
+        }
+    }
+
+    fn lines(&self, segments: &[(Point3<f32>, Point3<f32>)], color: [f32; 4]) {
+        let mut lines = self.lines.lock().unwrap();
+
+        let vertices = if self.depth_test() {
+            &mut lines.depth_tested
+        } else {
+            &mut lines.overlay
+        };
+
+        vertices.extend(segments.iter().flat_map(|(start, end)| {
+            [start, end].map(|p| DebugVertex {
+                position: [p.x, p.y, p.z],
+                color,
+            })
+        }));
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self {
+            lines: Mutex::new(DebugLines::default()),
+            labels: Mutex::new(Vec::new()),
+            depth_test: AtomicBool::new(true),
+        }
+    }
+}
+
+#[test]
+fn test_debug_draw() {
+    let debug = DebugDraw::new();
+
+    debug.line(Point3::origin(), Point3::new(1.0, 0.0, 0.0), RED);
+    debug.aabb(Point3::origin(), Point3::new(1.0, 2.0, 3.0), GREEN);
+
+    debug.set_depth_test(false);
+    debug.sphere(Point3::origin(), 2.0, BLUE);
+    debug.axes(&Matrix4::from_translation(Vector3::new(0.0, 5.0, 0.0)), 1.0);
+    debug.arrow(Point3::origin(), Point3::origin(), RED);
+
+    let lines = debug.take_lines();
+    assert_eq!(lines.depth_tested.len(), 2 * (1 + 12));
+    assert_eq!(lines.overlay.len(), 2 * (3 * DebugDraw::SEGMENTS + 3 * 5));
+
+    // Sphere circles close and stay on the surface
+    let first = lines.overlay[0].position;
+    assert!((Vector3::from(first).magnitude() - 2.0).abs() < 1e-5);
+    assert_eq!(
+        lines.overlay[2 * DebugDraw::SEGMENTS - 1].position,
+        lines.overlay[0].position
+    );
+
+    // The x axis arrow starts at the translated origin
+    let axis = &lines.overlay[2 * 3 * DebugDraw::SEGMENTS..];
+    assert_eq!(axis[0].position, [0.0, 5.0, 0.0]);
+    assert_eq!(axis[1].position, [1.0, 5.0, 0.0]);
+
+    assert!(debug.take_lines().is_empty());
+}
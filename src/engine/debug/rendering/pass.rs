@@ -0,0 +1,165 @@
+use super::pipeline::DebugPipeline;
+use crate::engine::{
+    debug::{DebugDraw, DebugVertex},
+    profiler::Counters,
+    rendering::{
+        configuration::Configuration, frame::Frame, pass::RenderPass,
+        pipeline::MultisampledPipeline,
+    },
+};
+use std::sync::Arc;
+use wgpu::{util::DeviceExt, Buffer, CommandEncoder, Device};
+
+/// Draws the lines of a `DebugDraw`, start it after the scene passes so depth testing sees them
+pub struct DebugPass {
+    encoder: CommandEncoder,
+    pass: wgpu::RenderPass<'static>,
+    pipeline: Arc<DebugPipeline>,
+    device: Device,
+    sample_count: u32,
+    /// Vertex buffers have to outlive the pass
+    buffers: Vec<Buffer>,
+    counters: Counters,
+}
+
+impl DebugPass {
+    /// Draws and clears the lines accumulated by `debug`
+    pub fn render(&mut self, debug: &DebugDraw) {
+        let lines = debug.take_lines();
+
+        let pipeline = self.pipeline.clone();
+
+        self.draw(pipeline.depth_tested(), &lines.depth_tested);
+        self.draw(pipeline.overlay(), &lines.overlay);
+    }
+
+    fn draw(&mut self, pipeline: &MultisampledPipeline, vertices: &[DebugVertex]) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("vengine::debug_vertex_buffer"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        self.pass.set_pipeline(pipeline.get(self.sample_count));
+        self.pass.set_vertex_buffer(0, buffer.slice(..));
+        self.pass.draw(0..vertices.len() as u32, 0..1);
+
+        self.buffers.push(buffer);
+
+        self.counters += Counters {
+            draw_calls: 1,
+            instances: 1,
+            chunks: 0,
+        };
+    }
+}
+
+impl RenderPass for DebugPass {
+    type RequiredPipeline = DebugPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let device = frame.renderer().backend().device().clone();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("vengine::render_debug_encoder"),
+        });
+
+        let (view, resolve_target) = frame.color_target();
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("vengine::debug_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: frame.color_load_op(),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: frame.depth_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: frame.depth_load_op(),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        pass.set_bind_group(0, frame.renderer().camera().bind_group(), &[]);
+
+        let pass = pass.forget_lifetime();
+
+        Self {
+            encoder,
+            pass,
+            pipeline: frame.renderer().pipeline::<DebugPipeline>(),
+            device,
+            sample_count: frame.sample_count(),
+            buffers: Vec::new(),
+            counters: Counters::default(),
+        }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        drop(self.pass);
+
+        frame.renderer().profiler().count(self.counters);
+        frame.push_encoder(self.encoder);
+    }
+}
+
+#[test]
+fn test_debug_pass() {
+    use crate::engine::{
+        debug::RED,
+        rendering::{test_backend, Renderer},
+    };
+    use cgmath::Point3;
+
+    let Some(backend) = test_backend(32, 32) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.register_pipeline::<DebugPipeline>();
+
+    renderer.camera().set_eye(Point3::new(0.0, 0.0, 5.0));
+    renderer.camera().set_look_at(Point3::new(0.0, 0.0, 0.0));
+
+    let debug = DebugDraw::new();
+    debug.set_depth_test(false);
+    debug.line(
+        Point3::new(-10.0, 0.0, 0.0),
+        Point3::new(10.0, 0.0, 0.0),
+        RED,
+    );
+
+    let frame = renderer.start_frame().unwrap();
+    let mut pass: DebugPass = frame.start_render_pass().unwrap();
+    pass.render(&debug);
+    frame.finish_render_pass(pass);
+    renderer.finish_frame(frame);
+
+    let image = renderer.read_output().unwrap();
+    let pixel = |x: u32, y: u32| {
+        let i = ((y * image.width + x) * 4) as usize;
+        [image.pixels[i], image.pixels[i + 1], image.pixels[i + 2]]
+    };
+
+    // The line crosses the middle rows, the corners keep the clear color
+    assert!((14..18).any(|y| {
+        let [r, g, _] = pixel(8, y);
+        r > 200 && g < 100
+    }));
+    assert!(pixel(0, 0)[0] < 200);
+    assert!(debug.take_lines().is_empty());
+}
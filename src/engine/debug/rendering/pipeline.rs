@@ -0,0 +1,107 @@
+use crate::engine::{
+    debug::DebugVertex,
+    rendering::{
+        backend::Backend,
+        camera::Camera,
+        pipeline::{MultisampledPipeline, Pipeline},
+        texture::Texture,
+    },
+};
+
+pub struct DebugPipeline {
+    depth_tested: MultisampledPipeline,
+    overlay: MultisampledPipeline,
+}
+
+impl DebugPipeline {
+    /// Lines hidden behind geometry, without writing depth
+    pub fn depth_tested(&self) -> &MultisampledPipeline {
+        &self.depth_tested
+    }
+
+    /// Lines drawn on top of everything
+    pub fn overlay(&self) -> &MultisampledPipeline {
+        &self.overlay
+    }
+}
+
+impl Pipeline for DebugPipeline {
+    fn initialize(backend: &Backend, camera: &Camera) -> Self {
+        let device = backend.device();
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("vengine::debug_pipeline_layout"),
+                bind_group_layouts: &[camera.bind_group_layout()],
+                push_constant_ranges: &[],
+            });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vengine::debug_shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../../rendering/shaders/camera.wgsl"),
+                    include_str!("shaders/debug.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let create = |depth_compare: wgpu::CompareFunction| {
+            MultisampledPipeline::new(backend, |sample_count| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("vengine::debug_pipeline"),
+                    layout: Some(&render_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        buffers: &[wgpu::VertexBufferLayout {
+                            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+                            step_mode: wgpu::VertexStepMode::Vertex,
+                            attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4],
+                        }],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: Texture::HDR_FORMAT,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::LineList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: None,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
+                    },
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: Texture::DEPTH_FORMAT,
+                        depth_write_enabled: false,
+                        depth_compare,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: wgpu::MultisampleState {
+                        count: sample_count,
+                        mask: !0,
+                        alpha_to_coverage_enabled: false,
+                    },
+                    multiview: None,
+                    cache: None,
+                })
+            })
+        };
+
+        Self {
+            depth_tested: create(wgpu::CompareFunction::LessEqual),
+            overlay: create(wgpu::CompareFunction::Always),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::engine::voxel::object::Object;
+use cgmath::{Vector3, Zero};
+
+pub use crate::engine::scene::transform::Transform;
+
+/// Change of the `Transform` per second, applied by `systems::movement`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Velocity {
+    pub linear: Vector3<f32>,
+    /// Rotation axis scaled by radians per second
+    pub angular: Vector3<f32>,
+}
+
+impl Default for Velocity {
+    fn default() -> Self {
+        Self {
+            linear: Vector3::zero(),
+            angular: Vector3::zero(),
+        }
+    }
+}
+
+/// Voxel `Object` drawn at the entity's `Transform` by `systems::render_voxels`
+pub struct VoxelModel {
+    pub object: Object,
+    pub visible: bool,
+}
+
+impl VoxelModel {
+    pub fn new(object: Object) -> Self {
+        Self {
+            object,
+            visible: true,
+        }
+    }
+}
+
+/// Marks the entity whose `Transform` drives the renderer camera, see `systems::sync_camera`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub active: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { active: true }
+    }
+}
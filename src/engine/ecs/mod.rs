@@ -0,0 +1,217 @@
+pub mod components;
+pub mod storage;
+pub mod systems;
+
+use ahash::HashMap;
+use components::{Camera, Transform, Velocity, VoxelModel};
+use std::{
+    any::{Any, TypeId},
+    sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+use storage::Storage;
+
+/// Handle of an entity, the generation tells apart entities reusing a despawned index
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Default)]
+struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+}
+
+trait AnyStorage: Send + Sync {
+    fn remove(&self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Send + Sync + 'static> AnyStorage for RwLock<Storage<T>> {
+    fn remove(&self, entity: Entity) {
+        self.write().unwrap().remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Entities and their components, one `Storage` per component type
+///
+/// Storages are locked separately, so a system can read one type while writing another.
+/// Locking the same type twice from one thread deadlocks.
+pub struct World {
+    entities: Mutex<Entities>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    /// A world with the built-in components registered
+    pub fn new() -> Self {
+        let mut world = Self {
+            entities: Mutex::new(Entities::default()),
+            storages: HashMap::default(),
+        };
+
+        world.register::<Transform>();
+        world.register::<Velocity>();
+        world.register::<VoxelModel>();
+        world.register::<Camera>();
+
+        world
+    }
+
+    /// Adds a storage for `T`, components have to be registered before they are used
+    pub fn register<T: Send + Sync + 'static>(&mut self) {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(RwLock::new(Storage::<T>::new())));
+    }
+
+    pub fn spawn(&self) -> Entity {
+        let mut entities = self.entities.lock().unwrap();
+
+        let index = entities.free.pop().unwrap_or_else(|| {
+            entities.generations.push(0);
+            entities.alive.push(false);
+            entities.generations.len() as u32 - 1
+        });
+
+        entities.alive[index as usize] = true;
+
+        Entity {
+            index,
+            generation: entities.generations[index as usize],
+        }
+    }
+
+    /// Removes `entity` with all its components, returns `false` if it was already gone
+    pub fn despawn(&self, entity: Entity) -> bool {
+        let mut entities = self.entities.lock().unwrap();
+
+        if !Self::is_alive_in(&entities, entity) {
+            return false;
+        }
+
+        entities.alive[entity.index as usize] = false;
+        entities.generations[entity.index as usize] += 1;
+        entities.free.push(entity.index);
+
+        drop(entities);
+
+        for storage in self.storages.values() {
+            storage.remove(entity);
+        }
+
+        true
+    }
+
+    fn is_alive_in(entities: &Entities, entity: Entity) -> bool {
+        entities.alive.get(entity.index as usize) == Some(&true)
+            && entities.generations[entity.index as usize] == entity.generation
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        Self::is_alive_in(&self.entities.lock().unwrap(), entity)
+    }
+
+    /// Number of living entities
+    pub fn len(&self) -> usize {
+        let entities = self.entities.lock().unwrap();
+        entities.alive.iter().filter(|a| **a).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock<T: Send + Sync + 'static>(&self) -> &RwLock<Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|s| s.as_any().downcast_ref())
+            .unwrap_or_else(|| panic!("component {} is not registered", std::any::type_name::<T>()))
+    }
+
+    /// Components of type `T`, panics if `T` is not registered
+    pub fn storage<T: Send + Sync + 'static>(&self) -> RwLockReadGuard<'_, Storage<T>> {
+        self.lock().read().unwrap()
+    }
+
+    pub fn storage_mut<T: Send + Sync + 'static>(&self) -> RwLockWriteGuard<'_, Storage<T>> {
+        self.lock().write().unwrap()
+    }
+
+    /// Adds or replaces a component, ignored for despawned entities
+    pub fn insert<T: Send + Sync + 'static>(&self, entity: Entity, component: T) {
+        if self.is_alive(entity) {
+            self.storage_mut().insert(entity, component);
+        }
+    }
+
+    pub fn remove<T: Send + Sync + 'static>(&self, entity: Entity) -> Option<T> {
+        self.storage_mut().remove(entity)
+    }
+
+    pub fn has<T: Send + Sync + 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>().contains(entity)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_world() {
+    use cgmath::Vector3;
+
+    struct Health(u32);
+
+    let mut world = World::new();
+    world.register::<Health>();
+
+    let a = world.spawn();
+    let b = world.spawn();
+
+    world.insert(a, Transform::from_position(Vector3::new(1.0, 0.0, 0.0)));
+    world.insert(a, Health(10));
+    world.insert(b, Health(5));
+
+    assert_eq!(
+        world
+            .storage::<Transform>()
+            .join(&world.storage::<Health>())
+            .map(|(e, _, h)| (e, h.0))
+            .collect::<Vec<_>>(),
+        vec![(a, 10)]
+    );
+
+    assert!(world.despawn(a));
+    assert!(!world.despawn(a));
+    assert!(!world.has::<Health>(a));
+    assert_eq!(world.len(), 1);
+
+    // The index is reused with a new generation
+    let c = world.spawn();
+    assert_eq!(c.index(), a.index());
+    assert_ne!(c, a);
+    assert!(!world.has::<Transform>(c));
+
+    world.insert(a, Health(1));
+    assert_eq!(world.storage::<Health>().len(), 1);
+}
@@ -0,0 +1,160 @@
+use super::Entity;
+
+/// Components of one type in a sparse set, iteration is over a packed array
+pub struct Storage<T> {
+    /// Dense index per entity index
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn index(&self, entity: Entity) -> Option<usize> {
+        self.sparse
+            .get(entity.index as usize)
+            .copied()
+            .flatten()
+            .filter(|i| self.entities[*i] == entity)
+    }
+
+    /// Adds or replaces the component of `entity`, returns the replaced one
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(index) = self.index(entity) {
+            return Some(std::mem::replace(&mut self.components[index], component));
+        }
+
+        let slot = entity.index as usize;
+        if slot >= self.sparse.len() {
+            self.sparse.resize(slot + 1, None);
+        }
+
+        // A stale component of a despawned entity with the same index is dropped
+        if let Some(stale) = self.sparse[slot] {
+            self.remove_at(stale);
+        }
+
+        self.sparse[slot] = Some(self.components.len());
+        self.entities.push(entity);
+        self.components.push(component);
+
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.index(entity).map(|index| self.remove_at(index))
+    }
+
+    fn remove_at(&mut self, index: usize) -> T {
+        let entity = self.entities.swap_remove(index);
+        let component = self.components.swap_remove(index);
+
+        self.sparse[entity.index as usize] = None;
+
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.index as usize] = Some(index);
+        }
+
+        component
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.index(entity).map(|i| &self.components[i])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.index(entity).map(|i| &mut self.components[i])
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.index(entity).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(&self.components)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(&mut self.components)
+    }
+
+    /// Entities that have a component here and in `other`
+    pub fn join<'s, U>(
+        &'s self,
+        other: &'s Storage<U>,
+    ) -> impl Iterator<Item = (Entity, &'s T, &'s U)> {
+        self.iter()
+            .filter_map(|(entity, t)| other.get(entity).map(|u| (entity, t, u)))
+    }
+
+    /// Like `join`, with mutable access to the components of `other`
+    pub fn join_mut<'s, U>(
+        &'s self,
+        other: &'s mut Storage<U>,
+    ) -> impl Iterator<Item = (Entity, &'s T, &'s mut U)> {
+        other
+            .iter_mut()
+            .filter_map(|(entity, u)| self.get(entity).map(|t| (entity, t, u)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_storage() {
+    let a = Entity {
+        index: 0,
+        generation: 0,
+    };
+    let b = Entity {
+        index: 3,
+        generation: 0,
+    };
+    let reused = Entity {
+        index: 0,
+        generation: 1,
+    };
+
+    let mut storage = Storage::new();
+    assert_eq!(storage.insert(a, 1), None);
+    assert_eq!(storage.insert(b, 2), None);
+    assert_eq!(storage.insert(a, 3), Some(1));
+    assert_eq!(storage.len(), 2);
+
+    assert_eq!(storage.get(reused), None);
+    assert_eq!(storage.insert(reused, 4), None);
+    assert_eq!(storage.get(a), None);
+    assert_eq!(storage.len(), 2);
+
+    assert_eq!(storage.remove(b), Some(2));
+    assert_eq!(storage.iter().collect::<Vec<_>>(), vec![(reused, &4)]);
+
+    let mut names = Storage::new();
+    names.insert(reused, "reused");
+
+    for (_, value, name) in storage.join_mut(&mut names) {
+        assert_eq!((*value, *name), (4, "reused"));
+        *name = "renamed";
+    }
+
+    assert_eq!(names.get(reused), Some(&"renamed"));
+}
@@ -0,0 +1,172 @@
+use super::{
+    components::{Camera, Transform, Velocity, VoxelModel},
+    World,
+};
+use crate::engine::{
+    core::{schedule::Schedule, time::Time},
+    rendering::{
+        camera,
+        configuration::Configuration,
+        frame::Frame,
+        graph::{RenderGraph, Resource},
+        pass::RenderPass,
+    },
+    voxel::{
+        chunk_mesh::ChunkMesh,
+        rendering::{pass::VoxelPass, translucent::TranslucentVoxelPass},
+    },
+};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+
+/// Adds the built-in systems to `schedule`: movement and camera on every tick, voxels every frame
+pub fn install<'s, C: Configuration>(world: &'s World, schedule: &mut Schedule<'s, C>) {
+    schedule
+        .add_update("ecs::movement", |_, time| movement(world, time))
+        .add_update("ecs::camera", |engine, _| {
+            sync_camera(world, engine.camera())
+        })
+        .add_render("ecs::voxels", |_, graph, _| add_voxel_passes(world, graph));
+}
+
+/// Applies `Velocity` to `Transform` for one tick
+pub fn movement(world: &World, time: &Time) {
+    let dt = time.tick.as_secs_f32();
+
+    let velocities = world.storage::<Velocity>();
+    let mut transforms = world.storage_mut::<Transform>();
+
+    for (_, velocity, transform) in velocities.join_mut(&mut transforms) {
+        transform.position += velocity.linear * dt;
+
+        let angle = velocity.angular.magnitude() * dt;
+        if angle > 0.0 {
+            transform.rotation =
+                (Quaternion::from_axis_angle(velocity.angular.normalize(), Rad(angle))
+                    * transform.rotation)
+                    .normalize();
+        }
+    }
+}
+
+/// Moves the renderer camera to the first active `Camera` entity with a `Transform`
+pub fn sync_camera(world: &World, camera: &camera::Camera) {
+    let cameras = world.storage::<Camera>();
+    let transforms = world.storage::<Transform>();
+
+    let active = cameras
+        .join(&transforms)
+        .find(|(_, c, _)| c.active)
+        .map(|(_, _, t)| t);
+
+    if let Some(transform) = active {
+        let eye = Point3::from_vec(transform.position);
+
+        camera.set_eye_no_update(eye);
+        camera.set_look_at(eye + transform.forward());
+    }
+}
+
+/// Adds an opaque and a translucent pass drawing every visible `VoxelModel` at its `Transform`,
+/// or at the object's own transform
+pub fn add_voxel_passes<'s, C: Configuration>(world: &'s World, graph: &mut RenderGraph<'s, C>) {
+    graph.add_pass(
+        "voxel",
+        &[],
+        &[Resource::Hdr, Resource::Depth],
+        move |frame| {
+            render_voxels::<VoxelPass, C>(world, frame, |pass, matrix, offset, chunk| {
+                pass.render_chunk(matrix, offset, chunk)
+            })
+        },
+    );
+
+    graph.add_pass(
+        "translucent",
+        &[Resource::Depth],
+        &[Resource::Hdr],
+        move |frame| {
+            render_voxels::<TranslucentVoxelPass, C>(world, frame, |pass, matrix, offset, chunk| {
+                pass.render_chunk(matrix, offset, chunk)
+            })
+        },
+    );
+}
+
+/// Draws the chunks of every visible `VoxelModel` with a `T` pass
+/// Skipped while the pipeline of `T` isn't registered or nothing is visible
+pub fn render_voxels<T, C>(
+    world: &World,
+    frame: &Frame<C>,
+    render: impl Fn(&mut T, Matrix4<f32>, Vector3<i32>, &ChunkMesh),
+) where
+    T: RenderPass,
+    C: Configuration,
+{
+    let models = world.storage::<VoxelModel>();
+    let transforms = world.storage::<Transform>();
+
+    let visible = models
+        .iter()
+        .filter(|(_, model)| model.visible)
+        .map(|(entity, model)| {
+            let matrix = transforms
+                .get(entity)
+                .map(Transform::matrix)
+                .unwrap_or(*model.object.transform());
+
+            (matrix, &model.object)
+        })
+        .collect::<Vec<_>>();
+
+    if visible.is_empty() {
+        return;
+    }
+
+    let Ok(mut pass) = frame.start_render_pass::<T>() else {
+        return;
+    };
+
+    for (matrix, object) in &visible {
+        for (offset, chunk) in object.chunks() {
+            render(&mut pass, *matrix, *offset, chunk);
+        }
+    }
+    frame.finish_render_pass(pass);
+}
+
+#[test]
+fn test_movement() {
+    use cgmath::{Vector3, Zero};
+    use std::time::Duration;
+
+    let world = World::new();
+
+    let moving = world.spawn();
+    world.insert(moving, Transform::default());
+    world.insert(
+        moving,
+        Velocity {
+            linear: Vector3::new(2.0, 0.0, 0.0),
+            angular: Vector3::new(0.0, std::f32::consts::PI, 0.0),
+        },
+    );
+
+    let still = world.spawn();
+    world.insert(still, Transform::default());
+
+    let time = Time {
+        tick: Duration::from_millis(500),
+        ..Default::default()
+    };
+
+    movement(&world, &time);
+
+    let transforms = world.storage::<Transform>();
+
+    let moved = transforms.get(moving).unwrap();
+    assert!((moved.position - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    // Half a turn per second for half a second faces +X
+    assert!((moved.forward() - Vector3::unit_x()).magnitude() < 1e-5);
+
+    assert!(transforms.get(still).unwrap().position.is_zero());
+}
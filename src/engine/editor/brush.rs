@@ -0,0 +1,303 @@
+use ahash::{HashSet, HashSetExt};
+use cgmath::Vector3;
+use std::collections::VecDeque;
+
+/// What a brush does to the voxels it covers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    /// Fills empty voxels with the palette selection
+    #[default]
+    Place,
+    Erase,
+    /// Recolors occupied voxels with the palette selection
+    Paint,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 3] = [Tool::Place, Tool::Erase, Tool::Paint];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Place => "Place",
+            Tool::Erase => "Erase",
+            Tool::Paint => "Paint",
+        }
+    }
+}
+
+/// Voxels a brush covers between where a drag starts and ends
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// The voxel under the cursor, a whole grid cell when snapping
+    #[default]
+    Single,
+    /// Axis aligned box spanned by the drag
+    Box,
+    /// Centered on the drag start, reaching the drag end
+    Sphere,
+    Line,
+    /// Connected face of voxels equal to the one under the cursor, see `fill`
+    Fill,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 5] = [
+        Shape::Single,
+        Shape::Box,
+        Shape::Sphere,
+        Shape::Line,
+        Shape::Fill,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Single => "Single",
+            Shape::Box => "Box",
+            Shape::Sphere => "Sphere",
+            Shape::Line => "Line",
+            Shape::Fill => "Fill",
+        }
+    }
+
+    /// Whether the shape is spanned by dragging instead of applied on click
+    pub fn is_dragged(&self) -> bool {
+        matches!(self, Shape::Box | Shape::Sphere | Shape::Line)
+    }
+}
+
+/// Tool, shape, grid snapping and mirror symmetry of the editor
+#[derive(Clone, Debug, PartialEq)]
+pub struct Brush {
+    pub tool: Tool,
+    pub shape: Shape,
+    /// Grid cell size in voxels, `1` disables snapping
+    pub grid: i32,
+    /// Mirrors edits across the planes `x`, `y` and `z` = `mirror_origin`
+    pub mirror: [bool; 3],
+    /// Voxel coordinates of the mirror planes, a plane lies on the lower face of its voxel
+    pub mirror_origin: Vector3<i32>,
+}
+
+impl Brush {
+    /// Voxels `fill` visits at most
+    pub const FILL_LIMIT: usize = 1 << 16;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lower corner of the grid cell containing `voxel`
+    pub fn snap(&self, voxel: Vector3<i32>) -> Vector3<i32> {
+        let grid = self.grid.max(1);
+        voxel.map(|c| c.div_euclid(grid) * grid)
+    }
+
+    /// Voxels of a shape dragged from `start` to `end` before mirroring
+    /// `Fill` depends on the object and is left to `fill`
+    pub fn shape_voxels(&self, start: Vector3<i32>, end: Vector3<i32>) -> Vec<Vector3<i32>> {
+        let grid = self.grid.max(1);
+        let (start, end) = (self.snap(start), self.snap(end));
+
+        match self.shape {
+            Shape::Single => box_voxels(end, end + Vector3::new(grid - 1, grid - 1, grid - 1)),
+            Shape::Box => {
+                let min = Vector3::new(start.x.min(end.x), start.y.min(end.y), start.z.min(end.z));
+                let max = Vector3::new(start.x.max(end.x), start.y.max(end.y), start.z.max(end.z));
+
+                box_voxels(min, max + Vector3::new(grid - 1, grid - 1, grid - 1))
+            }
+            Shape::Sphere => {
+                let d = (end - start).cast::<f32>().unwrap();
+                sphere_voxels(start, (d.x * d.x + d.y * d.y + d.z * d.z).sqrt())
+            }
+            Shape::Line => line_voxels(start, end),
+            Shape::Fill => Vec::new(),
+        }
+    }
+
+    /// `voxels` with their mirror images, without duplicates
+    pub fn mirrored(&self, voxels: Vec<Vector3<i32>>) -> Vec<Vector3<i32>> {
+        let mut seen = HashSet::with_capacity(voxels.len());
+        let mut out = Vec::with_capacity(voxels.len());
+
+        for voxel in voxels {
+            for n in 0..8usize {
+                // Skips combinations that flip a disabled axis
+                if (0..3).any(|axis| n & (1 << axis) != 0 && !self.mirror[axis]) {
+                    continue;
+                }
+
+                let mut image = voxel;
+                for axis in 0..3 {
+                    if n & (1 << axis) != 0 {
+                        image[axis] = 2 * self.mirror_origin[axis] - 1 - voxel[axis];
+                    }
+                }
+
+                if seen.insert((image.x, image.y, image.z)) {
+                    out.push(image);
+                }
+            }
+        }
+
+        out
+    }
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Place,
+            shape: Shape::Single,
+            grid: 1,
+            mirror: [false; 3],
+            mirror_origin: Vector3::new(0, 0, 0),
+        }
+    }
+}
+
+/// Inclusive box between two corners
+pub fn box_voxels(min: Vector3<i32>, max: Vector3<i32>) -> Vec<Vector3<i32>> {
+    let mut voxels = Vec::new();
+
+    for z in min.z..=max.z {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                voxels.push(Vector3::new(x, y, z));
+            }
+        }
+    }
+
+    voxels
+}
+
+/// Voxels whose centers lie within `radius` of the center of `center`
+pub fn sphere_voxels(center: Vector3<i32>, radius: f32) -> Vec<Vector3<i32>> {
+    let extent = radius.ceil() as i32;
+
+    box_voxels(
+        center - Vector3::new(extent, extent, extent),
+        center + Vector3::new(extent, extent, extent),
+    )
+    .into_iter()
+    .filter(|v| {
+        let d = (v - center).cast::<f32>().unwrap();
+        d.x * d.x + d.y * d.y + d.z * d.z <= radius * radius + 0.5
+    })
+    .collect()
+}
+
+/// Connected line between two voxels, one voxel per step along the longest axis
+pub fn line_voxels(start: Vector3<i32>, end: Vector3<i32>) -> Vec<Vector3<i32>> {
+    let d = end - start;
+    let steps = d.x.abs().max(d.y.abs()).max(d.z.abs());
+
+    if steps == 0 {
+        return vec![start];
+    }
+
+    (0..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            start + (d.cast::<f32>().unwrap() * t).map(|c| c.round() as i32)
+        })
+        .collect()
+}
+
+/// Flood fills the voxels face-connected to `start` for which `matches` holds
+/// Stops after `Brush::FILL_LIMIT` voxels
+pub fn fill(start: Vector3<i32>, matches: impl Fn(Vector3<i32>) -> bool) -> Vec<Vector3<i32>> {
+    if !matches(start) {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([start]);
+    let mut voxels = Vec::new();
+
+    seen.insert((start.x, start.y, start.z));
+
+    while let Some(voxel) = queue.pop_front() {
+        voxels.push(voxel);
+
+        if voxels.len() >= Brush::FILL_LIMIT {
+            break;
+        }
+
+        for axis in 0..3 {
+            for step in [-1, 1] {
+                let mut next = voxel;
+                next[axis] += step;
+
+                if matches(next) && seen.insert((next.x, next.y, next.z)) {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    voxels
+}
+
+#[test]
+fn test_brush_shapes() {
+    let mut brush = Brush::new();
+    let origin = Vector3::new(0, 0, 0);
+
+    assert_eq!(
+        brush.shape_voxels(origin, Vector3::new(2, 3, 4)),
+        vec![Vector3::new(2, 3, 4)]
+    );
+
+    brush.shape = Shape::Box;
+    assert_eq!(
+        brush
+            .shape_voxels(Vector3::new(1, 1, 1), Vector3::new(-1, 0, 2))
+            .len(),
+        3 * 2 * 2
+    );
+
+    // Snapped boxes cover whole grid cells
+    brush.grid = 4;
+    let voxels = brush.shape_voxels(Vector3::new(1, 1, 1), Vector3::new(5, 2, 3));
+    assert_eq!(voxels.len(), 8 * 4 * 4);
+    assert_eq!(voxels[0], origin);
+    brush.grid = 1;
+
+    brush.shape = Shape::Sphere;
+    let sphere = brush.shape_voxels(origin, Vector3::new(0, 2, 0));
+    assert!(sphere.contains(&Vector3::new(0, -2, 0)));
+    assert!(!sphere.contains(&Vector3::new(2, 2, 0)));
+
+    brush.shape = Shape::Line;
+    let line = brush.shape_voxels(origin, Vector3::new(4, 2, -1));
+    assert_eq!(line.len(), 5);
+    assert_eq!(line.first(), Some(&origin));
+    assert_eq!(line.last(), Some(&Vector3::new(4, 2, -1)));
+
+    // Mirroring across x = 0 and z = 2
+    brush.mirror = [true, false, true];
+    brush.mirror_origin = Vector3::new(0, 0, 2);
+    let mirrored = brush.mirrored(vec![Vector3::new(3, 1, 0)]);
+    assert_eq!(
+        mirrored,
+        vec![
+            Vector3::new(3, 1, 0),
+            Vector3::new(-4, 1, 0),
+            Vector3::new(3, 1, 3),
+            Vector3::new(-4, 1, 3)
+        ]
+    );
+
+    // A voxel on the plane and its image are distinct, duplicates are dropped
+    assert_eq!(
+        brush
+            .mirrored(vec![Vector3::new(0, 0, 2), Vector3::new(-1, 0, 2)])
+            .len(),
+        4
+    );
+
+    let floor = |v: Vector3<i32>| v.y == 0 && v.x.abs() <= 2 && v.z.abs() <= 2;
+    assert_eq!(fill(origin, floor).len(), 25);
+    assert!(fill(Vector3::new(0, 1, 0), floor).is_empty());
+}
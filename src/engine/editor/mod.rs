@@ -0,0 +1,400 @@
+pub mod brush;
+pub mod palette;
+pub mod raycast;
+
+use crate::engine::{
+    rendering::camera::Camera,
+    ui::inspector::draw_box,
+    voxel::{
+        chunk::{voxel_min, VOXEL_SIZE},
+        object::Object,
+    },
+};
+use brush::{Brush, Shape, Tool};
+use cgmath::{SquareMatrix, Vector2, Vector3};
+use egui::{
+    Button, Checkbox, Color32, ComboBox, Context, DragValue, Id, LayerId, Order, Stroke, Ui, Window,
+};
+use palette::Palette;
+use raycast::{Hit, Ray};
+
+const ERASE_COLOR: Color32 = Color32::from_rgb(230, 60, 60);
+
+/// Level editing on top of the running game
+///
+/// While enabled, `render` shows the brush and palette panel and edits the object under the
+/// cursor: the primary button applies the brush, dragged shapes span from press to release,
+/// the secondary button picks the color under the cursor. Without a voxel under the cursor,
+/// voxels are placed on the object's `y = 0` plane.
+pub struct Editor {
+    enabled: bool,
+    brush: Brush,
+    palette: Palette,
+    /// Where the current drag of a dragged shape started
+    anchor: Option<Vector3<i32>>,
+    /// File of the save and load buttons
+    path: String,
+    /// Outcome of the last save or load
+    status: Option<String>,
+}
+
+impl Editor {
+    /// How far from the camera the cursor reaches, in object space units
+    pub const MAX_DISTANCE: f32 = 512.0;
+    /// Preview outlines beyond this many voxels collapse into their bounding box
+    const PREVIEW_LIMIT: usize = 512;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling cancels a drag in progress
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.anchor = None;
+    }
+
+    pub fn brush(&self) -> &Brush {
+        &self.brush
+    }
+
+    pub fn brush_mut(&mut self) -> &mut Brush {
+        &mut self.brush
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn palette_mut(&mut self) -> &mut Palette {
+        &mut self.palette
+    }
+
+    /// Shows the panel and applies the brush to `object`, call it inside the egui pass
+    pub fn render(&mut self, context: &Context, camera: &Camera, object: &mut Object) {
+        if !self.enabled {
+            return;
+        }
+
+        Window::new("Editor")
+            .default_width(260.0)
+            .resizable([true, true])
+            .show(context, |ui| self.panel_ui(ui, object));
+
+        let (pressed, released, picked) = context.input(|i| {
+            (
+                i.pointer.primary_pressed(),
+                i.pointer.primary_released(),
+                i.pointer.secondary_pressed(),
+            )
+        });
+
+        let hit = self.hit(context, camera, object);
+
+        if let Some(hit) = hit {
+            let target = self.target(&hit);
+
+            if picked {
+                if let Some(voxel) = object.get_voxel(hit.voxel) {
+                    self.palette.pick(voxel);
+                }
+            }
+
+            if pressed {
+                if self.brush.shape.is_dragged() {
+                    self.anchor = Some(target);
+                } else {
+                    let voxels = self.voxels(object, target, &hit);
+                    self.apply(object, &voxels);
+                }
+            }
+
+            let voxels = self.voxels(object, self.anchor.unwrap_or(target), &hit);
+            self.draw_preview(context, camera, object, &voxels);
+
+            if released && self.anchor.is_some() {
+                self.apply(object, &voxels);
+            }
+        }
+
+        if released {
+            self.anchor = None;
+        }
+    }
+
+    /// Applies the tool to `voxels` with the palette selection and remeshes `object`
+    pub fn apply(&self, object: &mut Object, voxels: &[Vector3<i32>]) {
+        let voxel = self.palette.voxel();
+
+        for &position in voxels {
+            match (self.brush.tool, object.get_voxel(position)) {
+                (Tool::Place, None) => object.set_voxel(position, Some(voxel)),
+                (Tool::Erase, Some(_)) => object.set_voxel(position, None),
+                (Tool::Paint, Some(_)) => object.set_voxel(position, Some(voxel)),
+                _ => {}
+            }
+        }
+
+        object.update_meshes();
+    }
+
+    /// Voxel under the cursor in object voxel coordinates, `None` over a window
+    fn hit(&self, context: &Context, camera: &Camera, object: &Object) -> Option<Hit> {
+        if context.is_pointer_over_area() || context.is_using_pointer() {
+            return None;
+        }
+
+        let position = context.input(|i| i.pointer.hover_pos())?;
+        let screen = context.screen_rect();
+
+        let ndc = Vector2::new(
+            (position.x - screen.min.x) / screen.width() * 2.0 - 1.0,
+            1.0 - (position.y - screen.min.y) / screen.height() * 2.0,
+        );
+
+        let ray = Ray::from_screen(&camera.build_view_projection_matrix(), ndc)?
+            .transform(&object.transform().invert()?);
+
+        ray.cast(Self::MAX_DISTANCE, |v| object.get_voxel(v).is_some())
+            .or_else(|| ray.cast_ground(Self::MAX_DISTANCE))
+    }
+
+    /// Placing targets the empty voxel in front of the hit face, the other tools the hit voxel
+    fn target(&self, hit: &Hit) -> Vector3<i32> {
+        match self.brush.tool {
+            Tool::Place => hit.adjacent(),
+            Tool::Erase | Tool::Paint => hit.voxel,
+        }
+    }
+
+    /// Voxels the brush covers from `start` to the cursor, mirrored
+    fn voxels(&self, object: &Object, start: Vector3<i32>, hit: &Hit) -> Vec<Vector3<i32>> {
+        let voxels = match self.brush.shape {
+            Shape::Fill => {
+                let Some(target) = object.get_voxel(hit.voxel) else {
+                    return Vec::new();
+                };
+
+                // The face of equal voxels the cursor points at
+                let face = brush::fill(hit.voxel, |v| {
+                    object.get_voxel(v) == Some(target)
+                        && object.get_voxel(v + hit.normal).is_none()
+                });
+
+                match self.brush.tool {
+                    Tool::Place => face.into_iter().map(|v| v + hit.normal).collect(),
+                    Tool::Erase | Tool::Paint => face,
+                }
+            }
+            _ => self.brush.shape_voxels(start, self.target(hit)),
+        };
+
+        self.brush.mirrored(voxels)
+    }
+
+    /// Outlines the voxels the brush would change
+    fn draw_preview(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        object: &Object,
+        voxels: &[Vector3<i32>],
+    ) {
+        let Some(first) = voxels.first() else {
+            return;
+        };
+
+        let color = match self.brush.tool {
+            Tool::Erase => ERASE_COLOR,
+            Tool::Place | Tool::Paint => {
+                let [r, g, b, _] = self.palette.voxel().color;
+                Color32::from_rgb(r, g, b)
+            }
+        };
+
+        let stroke = Stroke::new(1.5, color);
+        let view_proj = camera.build_view_projection_matrix() * object.transform();
+        let screen = context.screen_rect();
+        let size = Vector3::new(VOXEL_SIZE, VOXEL_SIZE, VOXEL_SIZE);
+
+        let painter = context.layer_painter(LayerId::new(
+            Order::Background,
+            Id::new("vengine::editor_preview"),
+        ));
+
+        let outline = |min: Vector3<i32>, max: Vector3<i32>| {
+            draw_box(
+                &painter,
+                &view_proj,
+                screen,
+                voxel_min(min),
+                voxel_min(max) + size,
+                stroke,
+            )
+        };
+
+        if voxels.len() <= Self::PREVIEW_LIMIT {
+            for voxel in voxels {
+                outline(*voxel, *voxel);
+            }
+        } else {
+            let (min, max) = voxels.iter().fold((*first, *first), |(min, max), v| {
+                (
+                    Vector3::new(min.x.min(v.x), min.y.min(v.y), min.z.min(v.z)),
+                    Vector3::new(max.x.max(v.x), max.y.max(v.y), max.z.max(v.z)),
+                )
+            });
+
+            outline(min, max);
+        }
+    }
+
+    fn panel_ui(&mut self, ui: &mut Ui, object: &mut Object) {
+        ui.horizontal(|ui| {
+            for tool in Tool::ALL {
+                ui.selectable_value(&mut self.brush.tool, tool, tool.name());
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            for shape in Shape::ALL {
+                if ui
+                    .selectable_value(&mut self.brush.shape, shape, shape.name())
+                    .changed()
+                {
+                    self.anchor = None;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Grid");
+            ui.add(DragValue::new(&mut self.brush.grid).range(1..=32));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Mirror");
+
+            for (axis, name) in ["X", "Y", "Z"].into_iter().enumerate() {
+                ui.checkbox(&mut self.brush.mirror[axis], name);
+            }
+        });
+
+        if self.brush.mirror.contains(&true) {
+            ui.horizontal(|ui| {
+                ui.label("Mirror origin");
+
+                for axis in 0..3 {
+                    ui.add(DragValue::new(&mut self.brush.mirror_origin[axis]));
+                }
+            });
+        }
+
+        ui.separator();
+        self.palette_ui(ui);
+
+        ui.separator();
+        self.file_ui(ui, object);
+    }
+
+    fn palette_ui(&mut self, ui: &mut Ui) {
+        let palette = &mut self.palette;
+
+        ui.horizontal_wrapped(|ui| {
+            for (index, [r, g, b, a]) in palette.colors.iter().copied().enumerate() {
+                let mut swatch = Button::new("")
+                    .fill(Color32::from_rgba_unmultiplied(r, g, b, a))
+                    .min_size(egui::vec2(20.0, 20.0));
+
+                if index == palette.color {
+                    swatch = swatch.stroke(Stroke::new(2.0, ui.visuals().strong_text_color()));
+                }
+
+                if ui.add(swatch).clicked() {
+                    palette.color = index;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if let Some(color) = palette.colors.get_mut(palette.color) {
+                ui.color_edit_button_srgba_unmultiplied(color);
+            }
+
+            if ui.button("+").clicked() {
+                let color = palette.voxel().color;
+                palette.colors.push(color);
+                palette.color = palette.colors.len() - 1;
+            }
+
+            if ui
+                .add_enabled(palette.colors.len() > 1, Button::new("-"))
+                .clicked()
+            {
+                palette.colors.remove(palette.color);
+                palette.color = palette.color.min(palette.colors.len() - 1);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let name = |block: u8| match block {
+                0 => "Flat color".to_string(),
+                n => format!("Block {}", n),
+            };
+
+            ComboBox::from_id_salt("vengine::editor_block")
+                .selected_text(name(palette.block))
+                .show_ui(ui, |ui| {
+                    for block in 0..=palette.blocks {
+                        ui.selectable_value(&mut palette.block, block, name(block));
+                    }
+                });
+
+            ui.add_enabled(palette.block != 0, Checkbox::new(&mut palette.tint, "Tint"));
+        });
+    }
+
+    fn file_ui(&mut self, ui: &mut Ui, object: &mut Object) {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+
+            if ui.button("Save").clicked() {
+                self.status = Some(match object.save(&self.path) {
+                    Ok(()) => format!("Saved {} voxels", object.count()),
+                    Err(e) => e.to_string(),
+                });
+            }
+
+            if ui.button("Load").clicked() {
+                self.status = Some(match Object::load(object.device().clone(), &self.path) {
+                    Ok(loaded) => {
+                        *object = loaded;
+                        format!("Loaded {} voxels", object.count())
+                    }
+                    Err(e) => e.to_string(),
+                });
+            }
+        });
+
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+    }
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brush: Brush::default(),
+            palette: Palette::default(),
+            anchor: None,
+            path: "level.vobj".to_string(),
+            status: None,
+        }
+    }
+}
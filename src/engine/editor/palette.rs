@@ -0,0 +1,71 @@
+use crate::engine::voxel::{object::Voxel, texture::BlockTextures};
+
+/// Colors and block textures to pick from, the selection is what the editor places and paints
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub colors: Vec<[u8; 4]>,
+    /// Index into `colors`
+    pub color: usize,
+    /// Number of block texture ids, see `BlockTextures::blocks`
+    pub blocks: u8,
+    /// Selected block texture id, `0` places the flat color
+    pub block: u8,
+    /// Multiplies the block texture with the color
+    pub tint: bool,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offers the block textures of `textures`
+    pub fn set_blocks(&mut self, textures: &BlockTextures) {
+        self.blocks = textures.blocks().len().min(u8::MAX as usize) as u8;
+        self.block = self.block.min(self.blocks);
+    }
+
+    /// Voxel with the selected color and block texture
+    pub fn voxel(&self) -> Voxel {
+        Voxel {
+            color: self.colors.get(self.color).copied().unwrap_or([255; 4]),
+            texture: (self.block != 0).then_some((self.block, self.tint)),
+        }
+    }
+
+    /// Selects the color and block texture of `voxel`, adding its color if missing
+    pub fn pick(&mut self, voxel: Voxel) {
+        self.color = match self.colors.iter().position(|c| *c == voxel.color) {
+            Some(index) => index,
+            None => {
+                self.colors.push(voxel.color);
+                self.colors.len() - 1
+            }
+        };
+
+        (self.block, self.tint) = voxel.texture.unwrap_or((0, self.tint));
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: vec![
+                [255, 255, 255, 255],
+                [128, 128, 128, 255],
+                [40, 40, 40, 255],
+                [200, 60, 50, 255],
+                [240, 160, 40, 255],
+                [240, 220, 80, 255],
+                [90, 170, 70, 255],
+                [60, 120, 200, 255],
+                [120, 80, 50, 255],
+                [60, 140, 230, 128],
+            ],
+            color: 0,
+            blocks: 0,
+            block: 0,
+            tint: false,
+        }
+    }
+}
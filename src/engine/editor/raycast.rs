@@ -0,0 +1,201 @@
+use crate::engine::voxel::chunk::VOXEL_SIZE;
+use cgmath::{
+    EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector2, Vector3, Vector4,
+};
+
+/// Voxel containing an object space position
+pub fn voxel_at(position: Point3<f32>) -> Vector3<i32> {
+    let grid = to_grid(position);
+
+    Vector3::new(
+        grid.x.floor() as i32,
+        grid.y.floor() as i32,
+        grid.z.floor() as i32,
+    )
+}
+
+/// Object space to a grid with unit cells at integer corners
+fn to_grid(position: Point3<f32>) -> Point3<f32> {
+    Point3::from_vec(position.to_vec() / VOXEL_SIZE + Vector3::unit_z())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+/// Voxel a ray hit and the face it entered through
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub voxel: Vector3<i32>,
+    /// Points out of the hit face, zero when the ray starts inside the voxel
+    pub normal: Vector3<i32>,
+    /// Along the ray in units of its direction
+    pub distance: f32,
+}
+
+impl Hit {
+    /// Empty voxel in front of the hit face
+    pub fn adjacent(&self) -> Vector3<i32> {
+        self.voxel + self.normal
+    }
+}
+
+impl Ray {
+    /// Ray through a point of the screen in normalized device coordinates, `y` pointing up
+    pub fn from_screen(view_proj: &Matrix4<f32>, ndc: Vector2<f32>) -> Option<Ray> {
+        let inverse = view_proj.invert()?;
+
+        let unproject = |depth: f32| {
+            let point = inverse * Vector4::new(ndc.x, ndc.y, depth, 1.0);
+            Point3::from_homogeneous(point)
+        };
+
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+
+        Some(Ray {
+            origin: near,
+            direction: (far - near).normalize(),
+        })
+    }
+
+    /// Ray in the space `transform` maps to, e.g. object space with the inverse object transform
+    pub fn transform(&self, transform: &Matrix4<f32>) -> Ray {
+        Ray {
+            origin: Point3::from_homogeneous(transform * self.origin.to_homogeneous()),
+            direction: (transform * self.direction.extend(0.0)).truncate(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// Walks the voxels along the ray up to `max_distance` and returns the first occupied one
+    pub fn cast(&self, max_distance: f32, occupied: impl Fn(Vector3<i32>) -> bool) -> Option<Hit> {
+        let origin = to_grid(self.origin);
+        let direction = self.direction / VOXEL_SIZE;
+
+        let mut voxel = voxel_at(self.origin);
+        let mut normal = Vector3::new(0, 0, 0);
+        let mut distance = 0.0;
+
+        let mut step = [0i32; 3];
+        let mut next = [f32::INFINITY; 3];
+        let mut delta = [f32::INFINITY; 3];
+
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                next[axis] = (voxel[axis] as f32 + 1.0 - origin[axis]) / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                next[axis] = (voxel[axis] as f32 - origin[axis]) / direction[axis];
+            }
+
+            if step[axis] != 0 {
+                delta[axis] = 1.0 / direction[axis].abs();
+            }
+        }
+
+        loop {
+            if occupied(voxel) {
+                return Some(Hit {
+                    voxel,
+                    normal,
+                    distance,
+                });
+            }
+
+            let axis = (0..3).min_by(|a, b| next[*a].total_cmp(&next[*b])).unwrap();
+
+            if next[axis] > max_distance {
+                return None;
+            }
+
+            distance = next[axis];
+            next[axis] += delta[axis];
+
+            voxel[axis] += step[axis];
+            normal = Vector3::new(0, 0, 0);
+            normal[axis] = -step[axis];
+        }
+    }
+
+    /// Hit on the object space plane `y = 0`, as if the layer below it was filled
+    pub fn cast_ground(&self, max_distance: f32) -> Option<Hit> {
+        if self.direction.y.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let distance = -self.origin.y / self.direction.y;
+
+        if distance < 0.0 || distance > max_distance {
+            return None;
+        }
+
+        let sign = if self.direction.y < 0.0 { 1 } else { -1 };
+        let mut voxel = voxel_at(self.at(distance));
+        voxel.y = if sign > 0 { -1 } else { 0 };
+
+        Some(Hit {
+            voxel,
+            normal: Vector3::new(0, sign, 0),
+            distance,
+        })
+    }
+}
+
+#[test]
+fn test_raycast() {
+    use crate::engine::voxel::chunk::voxel_min;
+
+    let wall = |v: Vector3<i32>| v.x == 5;
+
+    // Straight along +x through the middle of voxel row (y 0, z 0)
+    let ray = Ray {
+        origin: Point3::new(0.5, 0.5, -0.5),
+        direction: Vector3::unit_x(),
+    };
+
+    let hit = ray.cast(100.0, wall).unwrap();
+    assert_eq!(hit.voxel, Vector3::new(5, 0, 0));
+    assert_eq!(hit.normal, Vector3::new(-1, 0, 0));
+    assert_eq!(hit.adjacent(), Vector3::new(4, 0, 0));
+    assert!((hit.distance - 4.5).abs() < 1e-5);
+
+    assert_eq!(ray.cast(3.0, wall), None);
+
+    // Diagonal rays don't skip voxels
+    let ray = Ray {
+        origin: Point3::new(0.5, 0.5, -0.5),
+        direction: Vector3::new(1.0, 1.0, 0.0).normalize(),
+    };
+    let hit = ray.cast(100.0, |v| v == Vector3::new(3, 2, 0)).unwrap();
+    assert_eq!(hit.voxel, Vector3::new(3, 2, 0));
+
+    assert_eq!(
+        voxel_at(voxel_min(Vector3::new(-3, 7, 2))),
+        Vector3::new(-3, 7, 2)
+    );
+
+    // Looking down onto the ground places on layer 0
+    let ray = Ray {
+        origin: Point3::new(2.5, 10.0, -3.5),
+        direction: -Vector3::unit_y(),
+    };
+    let hit = ray.cast_ground(100.0).unwrap();
+    assert_eq!(hit.adjacent(), Vector3::new(2, 0, -3));
+
+    // The ray through the screen center points at the camera target
+    let view = Matrix4::look_at_rh(
+        Point3::new(0.0, 0.0, 10.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::unit_y(),
+    );
+    let proj = cgmath::perspective(cgmath::Deg(60.0), 1.0, 0.1, 100.0);
+    let ray = Ray::from_screen(&(proj * view), Vector2::new(0.0, 0.0)).unwrap();
+    assert!((ray.direction - -Vector3::unit_z()).magnitude() < 1e-4);
+}
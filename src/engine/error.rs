@@ -0,0 +1,93 @@
+use crate::engine::rendering::graph::GraphError;
+use std::fmt::Display;
+
+/// Failures of the GPU backend and the renderer
+#[derive(Debug)]
+pub enum EngineError {
+    /// The window can't be presented to
+    CreateSurface(wgpu::CreateSurfaceError),
+    /// No adapter of the enabled backends, or none that can present to the window
+    NoAdapter,
+    /// `BackendOptions::adapter_index` is out of range
+    InvalidAdapter(usize),
+    /// Features the adapter lacks
+    MissingFeatures(wgpu::Features),
+    /// Names of `BackendOptions::limits` the adapter can't meet
+    UnsupportedLimits(Vec<&'static str>),
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The adapter can't present to the surface in any format
+    IncompatibleSurface,
+    /// The surface kept failing after being reconfigured
+    SurfaceLost,
+    /// Acquiring the next frame timed out, the frame should be skipped
+    SurfaceTimeout,
+    OutOfMemory,
+    /// The device is gone, `Renderer::recover` recreates it and the GPU resources
+    DeviceLost(String),
+    /// The passes of a frame can't be ordered
+    Graph(GraphError),
+}
+
+impl EngineError {
+    /// Whether rendering can continue, possibly after `Renderer::recover`
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            EngineError::SurfaceLost | EngineError::SurfaceTimeout | EngineError::DeviceLost(_)
+        )
+    }
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::CreateSurface(e) => write!(f, "failed to create surface: {}", e),
+            EngineError::NoAdapter => write!(f, "no suitable graphics adapter found"),
+            EngineError::InvalidAdapter(index) => write!(f, "no adapter with index {}", index),
+            EngineError::MissingFeatures(features) => {
+                write!(f, "adapter is missing features {:?}", features)
+            }
+            EngineError::UnsupportedLimits(names) => {
+                write!(f, "adapter doesn't support the limits {}", names.join(", "))
+            }
+            EngineError::RequestDevice(e) => write!(f, "failed to request device: {}", e),
+            EngineError::IncompatibleSurface => {
+                write!(f, "surface is incompatible with the adapter")
+            }
+            EngineError::SurfaceLost => write!(f, "surface lost"),
+            EngineError::SurfaceTimeout => write!(f, "timed out acquiring the next frame"),
+            EngineError::OutOfMemory => write!(f, "out of GPU memory"),
+            EngineError::DeviceLost(reason) => write!(f, "device lost: {}", reason),
+            EngineError::Graph(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::CreateSurface(e) => Some(e),
+            EngineError::RequestDevice(e) => Some(e),
+            EngineError::Graph(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for EngineError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        EngineError::CreateSurface(e)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for EngineError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        EngineError::RequestDevice(e)
+    }
+}
+
+impl From<GraphError> for EngineError {
+    fn from(e: GraphError) -> Self {
+        EngineError::Graph(e)
+    }
+}
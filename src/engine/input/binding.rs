@@ -0,0 +1,265 @@
+use super::{
+    gamepad::{GamepadAxis, GamepadButton},
+    keys::{key_from_name, key_name},
+};
+use std::{fmt::Display, path::Path};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+/// Anything with a pressed and released state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Button {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+/// Input that triggers an action
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Button(Button),
+    /// Held while the axis is past `Input::AXIS_THRESHOLD` in the given direction
+    Axis {
+        axis: GamepadAxis,
+        positive: bool,
+    },
+}
+
+#[derive(Debug)]
+pub enum BindingError {
+    Io(std::io::Error),
+    /// Line number and content of a line that isn't `action = binding, ...`
+    Syntax(usize, String),
+    UnknownBinding(usize, String),
+}
+
+impl Display for BindingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingError::Io(e) => write!(f, "failed to access bindings: {}", e),
+            BindingError::Syntax(line, content) => {
+                write!(
+                    f,
+                    "line {}: expected 'action = binding', got '{}'",
+                    line, content
+                )
+            }
+            BindingError::UnknownBinding(line, binding) => {
+                write!(f, "line {}: unknown binding '{}'", line, binding)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Button(Button::Key(key)) => match key_name(*key) {
+                Some(name) => write!(f, "Key:{}", name),
+                None => write!(f, "Key:{:?}", key),
+            },
+            Binding::Button(Button::Mouse(button)) => match button {
+                MouseButton::Other(n) => write!(f, "Mouse:{}", n),
+                button => write!(f, "Mouse:{:?}", button),
+            },
+            Binding::Button(Button::Gamepad(button)) => write!(f, "Gamepad:{}", button.name()),
+            Binding::Axis { axis, positive } => {
+                write!(
+                    f,
+                    "Axis:{}{}",
+                    axis.name(),
+                    if *positive { '+' } else { '-' }
+                )
+            }
+        }
+    }
+}
+
+impl Binding {
+    /// Parses the `Display` form, e.g. `Key:Space`, `Mouse:Left`, `Gamepad:South`, `Axis:LeftStickY-`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (kind, name) = s.trim().split_once(':')?;
+
+        match kind {
+            "Key" => key_from_name(name).map(|k| Binding::Button(Button::Key(k))),
+            "Mouse" => {
+                let button = match name {
+                    "Left" => MouseButton::Left,
+                    "Right" => MouseButton::Right,
+                    "Middle" => MouseButton::Middle,
+                    "Back" => MouseButton::Back,
+                    "Forward" => MouseButton::Forward,
+                    other => MouseButton::Other(other.parse().ok()?),
+                };
+
+                Some(Binding::Button(Button::Mouse(button)))
+            }
+            "Gamepad" => {
+                GamepadButton::from_name(name).map(|b| Binding::Button(Button::Gamepad(b)))
+            }
+            "Axis" => {
+                let positive = match name.chars().last()? {
+                    '+' => true,
+                    '-' => false,
+                    _ => return None,
+                };
+
+                GamepadAxis::from_name(&name[..name.len() - 1])
+                    .map(|axis| Binding::Axis { axis, positive })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Actions and the bindings that trigger them, in the order they were added
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bindings {
+    actions: Vec<(String, Vec<Binding>)>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `binding` to `action`
+    pub fn bind(&mut self, action: &str, binding: Binding) -> &mut Self {
+        match self.actions.iter_mut().find(|(a, _)| a == action) {
+            Some((_, bindings)) => {
+                if !bindings.contains(&binding) {
+                    bindings.push(binding);
+                }
+            }
+            None => self.actions.push((action.to_string(), vec![binding])),
+        }
+
+        self
+    }
+
+    /// Replaces all bindings of `action`
+    pub fn rebind(&mut self, action: &str, bindings: Vec<Binding>) {
+        match self.actions.iter_mut().find(|(a, _)| a == action) {
+            Some((_, b)) => *b = bindings,
+            None => self.actions.push((action.to_string(), bindings)),
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some((_, bindings)) = self.actions.iter_mut().find(|(a, _)| a == action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    pub fn get(&self, action: &str) -> &[Binding] {
+        self.actions
+            .iter()
+            .find(|(a, _)| a == action)
+            .map(|(_, b)| b.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Binding])> {
+        self.actions.iter().map(|(a, b)| (a.as_str(), b.as_slice()))
+    }
+
+    /// Parses lines of `action = binding, binding`, `#` starts a comment
+    pub fn parse(source: &str) -> Result<Self, BindingError> {
+        let mut bindings = Self::new();
+
+        for (n, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (action, list) = line
+                .split_once('=')
+                .filter(|(a, _)| !a.trim().is_empty())
+                .ok_or_else(|| BindingError::Syntax(n + 1, line.to_string()))?;
+
+            let list = list
+                .split(',')
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(|b| {
+                    Binding::parse(b)
+                        .ok_or_else(|| BindingError::UnknownBinding(n + 1, b.to_string()))
+                })
+                .collect::<Result<Vec<Binding>, BindingError>>()?;
+
+            bindings.rebind(action.trim(), list);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BindingError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(BindingError::Io)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BindingError> {
+        std::fs::write(path, self.to_string()).map_err(BindingError::Io)
+    }
+}
+
+impl Display for Bindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (action, bindings) in &self.actions {
+            let list = bindings
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<String>>();
+
+            writeln!(f, "{} = {}", action, list.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_bindings_round_trip() {
+    let mut bindings = Bindings::new();
+    bindings
+        .bind("jump", Binding::Button(Button::Key(KeyCode::Space)))
+        .bind(
+            "jump",
+            Binding::Button(Button::Gamepad(GamepadButton::South)),
+        )
+        .bind(
+            "fire",
+            Binding::Button(Button::Mouse(MouseButton::Other(4))),
+        )
+        .bind(
+            "back",
+            Binding::Axis {
+                axis: GamepadAxis::LeftStickY,
+                positive: false,
+            },
+        );
+
+    let source = bindings.to_string();
+    assert_eq!(
+        source,
+        "jump = Key:Space, Gamepad:South\nfire = Mouse:4\nback = Axis:LeftStickY-\n"
+    );
+    assert_eq!(Bindings::parse(&source).unwrap(), bindings);
+
+    let parsed = Bindings::parse("# comment\n\njump = Key:KeyW  # rebound\n").unwrap();
+    assert_eq!(
+        parsed.get("jump"),
+        &[Binding::Button(Button::Key(KeyCode::KeyW))]
+    );
+
+    assert!(matches!(
+        Bindings::parse("jump Key:Space"),
+        Err(BindingError::Syntax(1, _))
+    ));
+    assert!(matches!(
+        Bindings::parse("jump = Key:Space\nfire = Key:Nope"),
+        Err(BindingError::UnknownBinding(2, _))
+    ));
+}
@@ -0,0 +1,136 @@
+use super::{
+    binding::{Binding, Button},
+    gamepad::{GamepadAxis, GamepadButton, GamepadEvent},
+    Input,
+};
+use crate::engine::core::window::handler::Event;
+use cgmath::Vector2;
+use std::fmt::Display;
+use winit::{
+    event::{DeviceEvent, ElementState, MouseScrollDelta, WindowEvent},
+    keyboard::PhysicalKey,
+};
+
+/// Input change `Input` understands, independent of winit so it can be recorded and replayed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    /// Keyboard or mouse button, gamepads report through `Gamepad`
+    Button(Button, bool),
+    /// In lines, pixel deltas are converted with `Input::PIXELS_PER_LINE`
+    Scroll(Vector2<f32>),
+    /// Cursor position in physical pixels, `None` when it left the window
+    Cursor(Option<Vector2<f32>>),
+    MouseMotion(Vector2<f32>),
+    Focus(bool),
+    Gamepad(GamepadEvent),
+}
+
+impl InputEvent {
+    /// The input part of a window event, `None` for events `Input` ignores
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::WindowEvent(event) => match event {
+                WindowEvent::KeyboardInput { event, .. } if !event.repeat => {
+                    match event.physical_key {
+                        PhysicalKey::Code(key) => Some(InputEvent::Button(
+                            Button::Key(key),
+                            event.state == ElementState::Pressed,
+                        )),
+                        PhysicalKey::Unidentified(_) => None,
+                    }
+                }
+                WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::Button(
+                    Button::Mouse(*button),
+                    *state == ElementState::Pressed,
+                )),
+                WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::Scroll(match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vector2::new(*x, *y),
+                    MouseScrollDelta::PixelDelta(p) => {
+                        Vector2::new(p.x as f32, p.y as f32) / Input::PIXELS_PER_LINE
+                    }
+                })),
+                WindowEvent::CursorMoved { position, .. } => Some(InputEvent::Cursor(Some(
+                    Vector2::new(position.x as f32, position.y as f32),
+                ))),
+                WindowEvent::CursorLeft { .. } => Some(InputEvent::Cursor(None)),
+                WindowEvent::Focused(focused) => Some(InputEvent::Focus(*focused)),
+                _ => None,
+            },
+            Event::DeviceEvent(DeviceEvent::MouseMotion { delta }) => Some(
+                InputEvent::MouseMotion(Vector2::new(delta.0 as f32, delta.1 as f32)),
+            ),
+            Event::DeviceEvent(_) => None,
+        }
+    }
+
+    /// Parses the `Display` form, one event per line of a recording
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let kind = words.next()?;
+        let mut next = || words.next();
+
+        let state = |word: Option<&str>| match word? {
+            "down" => Some(true),
+            "up" => Some(false),
+            _ => None,
+        };
+        let float = |word: Option<&str>| word?.parse::<f32>().ok();
+        let id = |word: Option<&str>| word?.parse::<usize>().ok();
+
+        Some(match kind {
+            "button" => match Binding::parse(next()?)? {
+                Binding::Button(button) => InputEvent::Button(button, state(next())?),
+                Binding::Axis { .. } => return None,
+            },
+            "scroll" => InputEvent::Scroll(Vector2::new(float(next())?, float(next())?)),
+            "cursor" => InputEvent::Cursor(Some(Vector2::new(float(next())?, float(next())?))),
+            "cursor_left" => InputEvent::Cursor(None),
+            "motion" => InputEvent::MouseMotion(Vector2::new(float(next())?, float(next())?)),
+            "focus" => InputEvent::Focus(next()? == "1"),
+            "pad_connected" => InputEvent::Gamepad(GamepadEvent::Connected(id(next())?)),
+            "pad_disconnected" => InputEvent::Gamepad(GamepadEvent::Disconnected(id(next())?)),
+            "pad_button" => InputEvent::Gamepad(GamepadEvent::Button {
+                id: id(next())?,
+                button: GamepadButton::from_name(next()?)?,
+                pressed: state(next())?,
+            }),
+            "pad_axis" => InputEvent::Gamepad(GamepadEvent::Axis {
+                id: id(next())?,
+                axis: GamepadAxis::from_name(next()?)?,
+                value: float(next())?,
+            }),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for InputEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = |pressed: bool| if pressed { "down" } else { "up" };
+
+        match self {
+            InputEvent::Button(button, pressed) => {
+                write!(f, "button {} {}", Binding::Button(*button), state(*pressed))
+            }
+            InputEvent::Scroll(delta) => write!(f, "scroll {} {}", delta.x, delta.y),
+            InputEvent::Cursor(Some(position)) => {
+                write!(f, "cursor {} {}", position.x, position.y)
+            }
+            InputEvent::Cursor(None) => write!(f, "cursor_left"),
+            InputEvent::MouseMotion(delta) => write!(f, "motion {} {}", delta.x, delta.y),
+            InputEvent::Focus(focused) => write!(f, "focus {}", *focused as u8),
+            InputEvent::Gamepad(GamepadEvent::Connected(id)) => write!(f, "pad_connected {}", id),
+            InputEvent::Gamepad(GamepadEvent::Disconnected(id)) => {
+                write!(f, "pad_disconnected {}", id)
+            }
+            InputEvent::Gamepad(GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            }) => write!(f, "pad_button {} {} {}", id, button.name(), state(*pressed)),
+            InputEvent::Gamepad(GamepadEvent::Axis { id, axis, value }) => {
+                write!(f, "pad_axis {} {} {}", id, axis.name(), value)
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+macro_rules! named_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($variant) => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+named_enum!(
+    /// Buttons by position, `South` is A on Xbox and Cross on PlayStation controllers
+    GamepadButton {
+        South, East, West, North, LeftShoulder, RightShoulder, LeftTrigger, RightTrigger,
+        Select, Start, LeftStick, RightStick, DPadUp, DPadDown, DPadLeft, DPadRight,
+    }
+);
+
+named_enum!(
+    /// Axes in `-1.0..=1.0`, triggers in `0.0..=1.0`
+    GamepadAxis {
+        LeftStickX, LeftStickY, RightStickX, RightStickY, LeftTrigger, RightTrigger,
+    }
+);
+
+/// Identifies a gamepad within its source
+pub type GamepadId = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// Backend reporting gamepad input, e.g. a wrapper around gilrs or SDL
+/// Polled by `Input::update` once per frame
+pub trait GamepadSource: Send {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+}
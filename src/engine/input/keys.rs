@@ -0,0 +1,33 @@
+use winit::keyboard::KeyCode;
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        /// Name of `key` in binding files, the `KeyCode` variant
+        pub(super) fn key_name(key: KeyCode) -> Option<&'static str> {
+            match key {
+                $(KeyCode::$key => Some(stringify!($key)),)*
+                _ => None,
+            }
+        }
+
+        pub(super) fn key_from_name(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+// Keys that can be bound in binding files
+key_names! {
+    Backquote, Backslash, BracketLeft, BracketRight, Comma, Digit0, Digit1, Digit2, Digit3,
+    Digit4, Digit5, Digit6, Digit7, Digit8, Digit9, Equal, KeyA, KeyB, KeyC, KeyD, KeyE, KeyF,
+    KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM, KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU,
+    KeyV, KeyW, KeyX, KeyY, KeyZ, Minus, Period, Quote, Semicolon, Slash, AltLeft, AltRight,
+    Backspace, CapsLock, ControlLeft, ControlRight, Enter, SuperLeft, SuperRight, ShiftLeft,
+    ShiftRight, Space, Tab, Delete, End, Home, Insert, PageDown, PageUp, ArrowDown, ArrowLeft,
+    ArrowRight, ArrowUp, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7,
+    Numpad8, Numpad9, NumpadAdd, NumpadDecimal, NumpadDivide, NumpadEnter, NumpadMultiply,
+    NumpadSubtract, Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+}
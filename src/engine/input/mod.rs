@@ -0,0 +1,453 @@
+pub mod binding;
+pub mod event;
+pub mod gamepad;
+mod keys;
+pub mod record;
+
+use crate::engine::core::window::handler::Event;
+use ahash::{HashMap, HashSet};
+use binding::{Binding, Bindings, Button};
+use cgmath::{Vector2, Zero};
+use crossbeam::channel::Receiver;
+use event::InputEvent;
+use gamepad::{GamepadAxis, GamepadButton, GamepadEvent, GamepadId, GamepadSource};
+use record::{Recording, Replay};
+use std::time::Duration;
+
+/// Keyboard, mouse and gamepad state of the current frame with actions mapped onto it
+///
+/// `pressed` and `released` hold for the frame the change arrived in, so with `Engine::run`
+/// every fixed update of that frame sees them.
+pub struct Input {
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    /// Gamepad buttons per pad, a button is held while any pad holds it
+    gamepad_buttons: HashSet<(GamepadId, GamepadButton)>,
+    axes: HashMap<(GamepadId, GamepadAxis), f32>,
+    mouse_delta: Vector2<f32>,
+    /// In lines, pixel deltas are converted with `PIXELS_PER_LINE`
+    scroll: Vector2<f32>,
+    cursor: Option<Vector2<f32>>,
+    events: Vec<Event>,
+    bindings: Bindings,
+    sources: Vec<Box<dyn GamepadSource>>,
+    gamepad_events: Vec<GamepadEvent>,
+    /// Applied since `begin_frame`, in order
+    frame_events: Vec<InputEvent>,
+    recording: Option<Recording>,
+    replay: Option<Replay>,
+}
+
+impl Input {
+    /// Axis value past which an axis binding counts as held
+    pub const AXIS_THRESHOLD: f32 = 0.5;
+    pub const PIXELS_PER_LINE: f32 = 20.0;
+
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            held: HashSet::default(),
+            pressed: HashSet::default(),
+            released: HashSet::default(),
+            gamepad_buttons: HashSet::default(),
+            axes: HashMap::default(),
+            mouse_delta: Vector2::zero(),
+            scroll: Vector2::zero(),
+            cursor: None,
+            events: Vec::new(),
+            bindings,
+            sources: Vec::new(),
+            gamepad_events: Vec::new(),
+            frame_events: Vec::new(),
+            recording: None,
+            replay: None,
+        }
+    }
+
+    pub fn add_gamepad_source<S: GamepadSource + 'static>(&mut self, source: S) {
+        self.sources.push(Box::new(source));
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// Rebinding takes effect immediately
+    pub fn bindings_mut(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    /// Starts a new frame with the window events in `events` and polls the gamepad sources
+    ///
+    /// `delta` is the time since the last frame. While replaying, live input is discarded, the
+    /// recorded input of the next frame is applied instead and its recorded delta returned.
+    pub fn update(&mut self, events: &Receiver<Event>, delta: Duration) -> Duration {
+        self.begin_frame();
+
+        if let Some(replay) = self.replay.take() {
+            // Events without input, e.g. resizes, still reach `events`
+            self.events.extend(
+                events
+                    .try_iter()
+                    .filter(|e| InputEvent::from_event(e).is_none()),
+            );
+
+            let frame = &replay.recording.frames[replay.frame];
+            for event in &frame.events {
+                self.apply(*event);
+            }
+
+            let delta = replay.recording.delta(replay.frame);
+
+            if replay.frame + 1 < replay.recording.frames.len() {
+                self.replay = Some(Replay {
+                    frame: replay.frame + 1,
+                    ..replay
+                });
+            }
+
+            return delta;
+        }
+
+        for event in events.try_iter() {
+            self.handle_event(&event);
+            self.events.push(event);
+        }
+
+        self.poll_gamepads();
+
+        if let Some(recording) = &mut self.recording {
+            recording.push(delta, self.frame_events.clone());
+        }
+
+        delta
+    }
+
+    /// Records the `InputEvent`s of every following `update` until `stop_recording`
+    /// Other window events aren't recorded, see `Recording`
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replays `recording` from its first frame, live input resumes after its last frame
+    ///
+    /// Held buttons are released first so the replay starts from the state it was recorded in.
+    /// Live `InputEvent`s are dropped meanwhile, other window events still reach `events`.
+    pub fn start_replay(&mut self, recording: Recording) {
+        self.release_all();
+        self.axes.clear();
+        self.cursor = None;
+
+        self.replay = (!recording.frames.is_empty()).then_some(Replay {
+            recording,
+            frame: 0,
+        });
+    }
+
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Clears the state of the previous frame, use with `handle_event` instead of `update`
+    pub fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = Vector2::zero();
+        self.scroll = Vector2::zero();
+        self.events.clear();
+        self.frame_events.clear();
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Some(event) = InputEvent::from_event(event) {
+            self.apply(event);
+        }
+    }
+
+    /// Applies one change of input, e.g. from a recording
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Button(button, pressed) => self.set(button, pressed),
+            InputEvent::Scroll(delta) => self.scroll += delta,
+            InputEvent::Cursor(cursor) => self.cursor = cursor,
+            InputEvent::MouseMotion(delta) => self.mouse_delta += delta,
+            // Keys released while unfocused never arrive
+            InputEvent::Focus(false) => self.release_all(),
+            InputEvent::Focus(true) => {}
+            InputEvent::Gamepad(event) => self.apply_gamepad_event(event),
+        }
+
+        // Keys without a name can't be written to a recording
+        if !matches!(event, InputEvent::Button(Button::Key(key), _) if keys::key_name(key).is_none())
+        {
+            self.frame_events.push(event);
+        }
+    }
+
+    pub fn poll_gamepads(&mut self) {
+        let mut events = std::mem::take(&mut self.gamepad_events);
+
+        for source in &mut self.sources {
+            source.poll(&mut events);
+        }
+
+        for event in events.drain(..) {
+            self.handle_gamepad_event(event);
+        }
+
+        self.gamepad_events = events;
+    }
+
+    pub fn handle_gamepad_event(&mut self, event: GamepadEvent) {
+        self.apply(InputEvent::Gamepad(event));
+    }
+
+    fn apply_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(_) => {}
+            GamepadEvent::Disconnected(id) => {
+                let buttons = self
+                    .gamepad_buttons
+                    .iter()
+                    .filter(|(pad, _)| *pad == id)
+                    .map(|(_, b)| *b)
+                    .collect::<Vec<_>>();
+
+                for button in buttons {
+                    self.set_gamepad_button(id, button, false);
+                }
+
+                self.axes.retain(|(pad, _), _| *pad != id);
+            }
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => self.set_gamepad_button(id, button, pressed),
+            GamepadEvent::Axis { id, axis, value } => {
+                self.axes.insert((id, axis), value);
+            }
+        }
+    }
+
+    fn set_gamepad_button(&mut self, id: GamepadId, button: GamepadButton, pressed: bool) {
+        if pressed {
+            self.gamepad_buttons.insert((id, button));
+        } else {
+            self.gamepad_buttons.remove(&(id, button));
+        }
+
+        let held = self.gamepad_buttons.iter().any(|(_, b)| *b == button);
+        self.set(Button::Gamepad(button), held);
+    }
+
+    fn set(&mut self, button: Button, held: bool) {
+        if held {
+            if self.held.insert(button) {
+                self.pressed.insert(button);
+            }
+        } else if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.held.drain());
+        self.gamepad_buttons.clear();
+    }
+
+    pub fn is_held(&self, button: Button) -> bool {
+        self.held.contains(&button)
+    }
+
+    /// Went down this frame
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Went up this frame
+    pub fn is_released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Value with the largest magnitude over all gamepads
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes
+            .iter()
+            .filter(|((_, a), _)| *a == axis)
+            .map(|(_, v)| *v)
+            .fold(0.0, |max: f32, v| if v.abs() > max.abs() { v } else { max })
+    }
+
+    /// Raw mouse movement since the last frame, unaffected by cursor grab and acceleration
+    pub fn mouse_delta(&self) -> Vector2<f32> {
+        self.mouse_delta
+    }
+
+    pub fn scroll(&self) -> Vector2<f32> {
+        self.scroll
+    }
+
+    /// Cursor position in physical pixels, `None` outside the window
+    pub fn cursor(&self) -> Option<Vector2<f32>> {
+        self.cursor
+    }
+
+    /// Input applied since the last `begin_frame`, recorded or replayed
+    pub fn input_events(&self) -> &[InputEvent] {
+        &self.frame_events
+    }
+
+    /// Events handled by the last `update`, to forward them e.g. to `UiPipeline`
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    fn binding_value(&self, binding: &Binding) -> f32 {
+        match binding {
+            Binding::Button(button) => self.is_held(*button) as u8 as f32,
+            Binding::Axis { axis, positive } => {
+                let value = self.axis(*axis);
+                if *positive {
+                    value.max(0.0)
+                } else {
+                    (-value).max(0.0)
+                }
+            }
+        }
+    }
+
+    fn binding_held(&self, binding: &Binding) -> bool {
+        self.binding_value(binding) > Self::AXIS_THRESHOLD
+    }
+
+    /// Strongest binding of `action` in `0.0..=1.0`, analog for axes
+    pub fn action_value(&self, action: &str) -> f32 {
+        self.bindings
+            .get(action)
+            .iter()
+            .map(|b| self.binding_value(b))
+            .fold(0.0, f32::max)
+    }
+
+    pub fn action_held(&self, action: &str) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|b| self.binding_held(b))
+    }
+
+    /// A button of `action` went down this frame
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|b| matches!(b, Binding::Button(button) if self.is_pressed(*button)))
+    }
+
+    /// A button of `action` went up this frame
+    pub fn action_released(&self, action: &str) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|b| matches!(b, Binding::Button(button) if self.is_released(*button)))
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new(Bindings::new())
+    }
+}
+
+#[test]
+fn test_input_actions() {
+    use winit::keyboard::KeyCode;
+
+    struct Pad(Vec<GamepadEvent>);
+
+    impl GamepadSource for Pad {
+        fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+            events.append(&mut self.0);
+        }
+    }
+
+    let mut bindings = Bindings::new();
+    bindings
+        .bind("jump", Binding::Button(Button::Key(KeyCode::Space)))
+        .bind(
+            "jump",
+            Binding::Button(Button::Gamepad(GamepadButton::South)),
+        )
+        .bind(
+            "forward",
+            Binding::Axis {
+                axis: GamepadAxis::LeftStickY,
+                positive: true,
+            },
+        );
+
+    let mut input = Input::new(bindings);
+
+    input.set(Button::Key(KeyCode::Space), true);
+    assert!(input.action_pressed("jump") && input.action_held("jump"));
+
+    input.begin_frame();
+    assert!(!input.action_pressed("jump") && input.action_held("jump"));
+
+    input.begin_frame();
+    input.set(Button::Key(KeyCode::Space), false);
+    assert!(input.action_released("jump") && !input.action_held("jump"));
+
+    input.add_gamepad_source(Pad(vec![
+        GamepadEvent::Button {
+            id: 0,
+            button: GamepadButton::South,
+            pressed: true,
+        },
+        GamepadEvent::Button {
+            id: 1,
+            button: GamepadButton::South,
+            pressed: true,
+        },
+        GamepadEvent::Button {
+            id: 0,
+            button: GamepadButton::South,
+            pressed: false,
+        },
+        GamepadEvent::Axis {
+            id: 1,
+            axis: GamepadAxis::LeftStickY,
+            value: 0.75,
+        },
+    ]));
+
+    input.begin_frame();
+    input.poll_gamepads();
+    assert!(input.action_held("jump"));
+    assert_eq!(input.action_value("forward"), 0.75);
+
+    // The second pad still holds the button until it disconnects
+    input.begin_frame();
+    input.handle_gamepad_event(GamepadEvent::Disconnected(1));
+    assert!(input.action_released("jump"));
+    assert_eq!(input.action_value("forward"), 0.0);
+
+    input
+        .bindings_mut()
+        .rebind("jump", vec![Binding::Button(Button::Key(KeyCode::KeyJ))]);
+    input.set(Button::Key(KeyCode::KeyJ), true);
+    assert!(input.action_pressed("jump"));
+}
@@ -0,0 +1,231 @@
+use super::event::InputEvent;
+use std::{fmt::Display, path::Path, time::Duration};
+
+/// Input of one frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    /// Since the start of the recording, including the delta of this frame
+    pub time: Duration,
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    /// Line number and content of a line that isn't a frame or an event
+    Syntax(usize, String),
+    /// Line number of an event before the first frame
+    NoFrame(usize),
+}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(e) => write!(f, "failed to access recording: {}", e),
+            RecordingError::Syntax(line, content) => {
+                write!(f, "line {}: unknown frame or event '{}'", line, content)
+            }
+            RecordingError::NoFrame(line) => write!(f, "line {}: event outside of a frame", line),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// Input of a session frame by frame, made by `Input::start_recording`
+///
+/// Replayed with `Input::start_replay`, each frame gets its recorded events and delta, so with a
+/// fixed timestep the session runs the same updates again.
+///
+/// Only `InputEvent`s are recorded: keys, mouse buttons, cursor, scroll, mouse motion, focus and
+/// gamepads. Other window events, e.g. resizes, DPI changes, text input and what egui reads from
+/// `Input::events`, aren't recorded and arrive live during a replay, so window size and UI
+/// interaction aren't reproduced.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a frame that took `delta`
+    pub fn push(&mut self, delta: Duration, events: Vec<InputEvent>) {
+        self.frames.push(RecordedFrame {
+            time: self.duration() + delta,
+            events,
+        });
+    }
+
+    /// Time between the end of the previous frame and the end of `frame`
+    pub fn delta(&self, frame: usize) -> Duration {
+        let previous = match frame {
+            0 => Duration::ZERO,
+            n => self.frames[n - 1].time,
+        };
+
+        self.frames[frame].time.saturating_sub(previous)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |f| f.time)
+    }
+
+    /// Parses lines of `frame <nanoseconds>`, each followed by the events of the frame
+    /// `#` starts a comment
+    pub fn parse(source: &str) -> Result<Self, RecordingError> {
+        let mut recording = Self::new();
+
+        for (n, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(nanos) = line.strip_prefix("frame ") {
+                let nanos = nanos
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| RecordingError::Syntax(n + 1, line.to_string()))?;
+
+                recording.frames.push(RecordedFrame {
+                    time: Duration::from_nanos(nanos),
+                    events: Vec::new(),
+                });
+            } else {
+                let event = InputEvent::parse(line)
+                    .ok_or_else(|| RecordingError::Syntax(n + 1, line.to_string()))?;
+
+                recording
+                    .frames
+                    .last_mut()
+                    .ok_or(RecordingError::NoFrame(n + 1))?
+                    .events
+                    .push(event);
+            }
+        }
+
+        Ok(recording)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(RecordingError::Io)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_string()).map_err(RecordingError::Io)
+    }
+}
+
+impl Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for frame in &self.frames {
+            writeln!(f, "frame {}", frame.time.as_nanos())?;
+
+            for event in &frame.events {
+                writeln!(f, "{}", event)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Position in a recording being replayed
+pub(super) struct Replay {
+    pub recording: Recording,
+    pub frame: usize,
+}
+
+#[test]
+fn test_record_replay() {
+    use super::{
+        binding::Button,
+        gamepad::{GamepadButton, GamepadEvent, GamepadSource},
+        Input,
+    };
+    use crate::engine::core::window::handler::Event;
+    use cgmath::Vector2;
+    use winit::event::DeviceEvent;
+
+    struct Pad(Vec<Vec<GamepadEvent>>);
+
+    impl GamepadSource for Pad {
+        fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+            if !self.0.is_empty() {
+                events.append(&mut self.0.remove(0));
+            }
+        }
+    }
+
+    let button = |pressed| GamepadEvent::Button {
+        id: 0,
+        button: GamepadButton::South,
+        pressed,
+    };
+    let south = Button::Gamepad(GamepadButton::South);
+    let state = |input: &Input| {
+        (
+            input.is_pressed(south),
+            input.is_held(south),
+            input.mouse_delta(),
+        )
+    };
+
+    let (sender, receiver) = crossbeam::channel::unbounded();
+    let mut live = Input::default();
+    live.add_gamepad_source(Pad(vec![vec![button(true)], vec![], vec![button(false)]]));
+    live.start_recording();
+
+    let mut states = Vec::new();
+    for frame in 0..3 {
+        sender
+            .send(Event::DeviceEvent(DeviceEvent::MouseMotion {
+                delta: (frame as f64 * 0.1, -1.5),
+            }))
+            .unwrap();
+
+        live.update(&receiver, Duration::from_millis(16 + frame));
+        states.push(state(&live));
+    }
+
+    let recording = Recording::parse(&live.stop_recording().unwrap().to_string()).unwrap();
+    assert_eq!(recording.duration(), Duration::from_millis(16 + 17 + 18));
+    assert_eq!(
+        recording.frames[2].events[1],
+        InputEvent::Gamepad(button(false))
+    );
+
+    // Live input is ignored during the replay
+    let mut replayed = Input::default();
+    replayed.start_replay(recording);
+    for (frame, expected) in states.iter().enumerate() {
+        sender
+            .send(Event::DeviceEvent(DeviceEvent::MouseMotion {
+                delta: (9.0, 9.0),
+            }))
+            .unwrap();
+
+        let delta = replayed.update(&receiver, Duration::ZERO);
+        assert_eq!(delta, Duration::from_millis(16 + frame as u64));
+        assert_eq!(&state(&replayed), expected);
+    }
+    assert!(!replayed.is_replaying());
+
+    // Then live input resumes
+    sender
+        .send(Event::DeviceEvent(DeviceEvent::MouseMotion {
+            delta: (1.0, 2.0),
+        }))
+        .unwrap();
+    replayed.update(&receiver, Duration::ZERO);
+    assert_eq!(replayed.mouse_delta(), Vector2::new(1.0, 2.0));
+
+    assert!(matches!(
+        Recording::parse("button Key:Space down"),
+        Err(RecordingError::NoFrame(1))
+    ));
+}
@@ -1,4 +1,14 @@
 pub mod core;
+#[cfg(feature = "debug-draw")]
+pub mod debug;
+pub mod ecs;
+pub mod editor;
+pub mod error;
+pub mod input;
+pub mod post;
+pub mod profiler;
 pub mod rendering;
+pub mod scene;
+pub mod sky;
 pub mod ui;
 pub mod voxel;
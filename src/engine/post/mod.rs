@@ -0,0 +1,89 @@
+pub mod rendering;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapper {
+    /// Clamps to the displayable range
+    Clamp = 0,
+    Reinhard = 1,
+    /// Narkowicz ACES filmic fit
+    #[default]
+    Aces = 2,
+}
+
+/// Settings shared by all post-process passes
+#[derive(Clone, Copy, Debug)]
+pub struct PostProcess {
+    pub exposure: f32,
+    pub tone_mapper: ToneMapper,
+    /// Luminance above which pixels start to bloom
+    pub bloom_threshold: f32,
+    /// Width of the soft transition around the threshold
+    pub bloom_knee: f32,
+    pub bloom_intensity: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    /// Color multiplier applied while grading
+    pub tint: [f32; 3],
+    pub gamma: f32,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            tone_mapper: ToneMapper::default(),
+            bloom_threshold: 1.0,
+            bloom_knee: 0.5,
+            bloom_intensity: 0.3,
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: [1.0; 3],
+            gamma: 1.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostProcessUniform {
+    tint: [f32; 4],
+    exposure: f32,
+    tone_mapper: u32,
+    bloom_threshold: f32,
+    bloom_knee: f32,
+    bloom_intensity: f32,
+    contrast: f32,
+    saturation: f32,
+    gamma: f32,
+}
+
+impl From<&PostProcess> for PostProcessUniform {
+    fn from(settings: &PostProcess) -> Self {
+        Self {
+            tint: [settings.tint[0], settings.tint[1], settings.tint[2], 1.0],
+            exposure: settings.exposure,
+            tone_mapper: settings.tone_mapper as u32,
+            bloom_threshold: settings.bloom_threshold,
+            bloom_knee: settings.bloom_knee.max(1e-4),
+            bloom_intensity: settings.bloom_intensity,
+            contrast: settings.contrast,
+            saturation: settings.saturation,
+            gamma: settings.gamma.max(1e-4),
+        }
+    }
+}
+
+#[test]
+fn test_post_process_uniform() {
+    assert_eq!(std::mem::size_of::<PostProcessUniform>() % 16, 0);
+
+    let uniform = PostProcessUniform::from(&PostProcess {
+        tone_mapper: ToneMapper::Reinhard,
+        gamma: 0.0,
+        ..Default::default()
+    });
+
+    assert_eq!(uniform.tone_mapper, 1);
+    assert!(uniform.gamma > 0.0);
+    assert_eq!(uniform.tint, [1.0; 4]);
+}
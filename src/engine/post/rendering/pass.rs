@@ -0,0 +1,150 @@
+use super::pipeline::PostProcessPipeline;
+use crate::engine::rendering::{
+    configuration::Configuration, frame::Frame, pass::RenderPass, texture::Texture,
+};
+use wgpu::{CommandEncoder, Device, TextureView};
+
+/// Runs one post-process step from the latest HDR image into the other HDR target
+fn post_process<C, F>(frame: &Frame<C>, label: &str, f: F) -> CommandEncoder
+where
+    C: Configuration,
+    F: FnOnce(&PostProcessPipeline, &Device, &mut CommandEncoder, &Texture, &TextureView),
+{
+    let device = frame.renderer().backend().device();
+
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some(label) });
+
+    let (source, target) = frame.post_process_targets();
+
+    f(
+        &frame.renderer().pipeline::<PostProcessPipeline>(),
+        device,
+        &mut encoder,
+        source,
+        &target.view,
+    );
+
+    encoder
+}
+
+/// Maps the HDR scene into displayable range, usually the first step of the chain
+pub struct ToneMappingPass {
+    encoder: CommandEncoder,
+}
+
+impl RenderPass for ToneMappingPass {
+    type RequiredPipeline = PostProcessPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let encoder = post_process(
+            frame,
+            "vengine::tone_mapping_encoder",
+            |pipeline, device, encoder, source, target| {
+                pipeline.draw(
+                    device,
+                    encoder,
+                    &pipeline.tone_mapping(),
+                    &source.view,
+                    &source.view,
+                    target,
+                );
+            },
+        );
+
+        Self { encoder }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.push_encoder(self.encoder);
+    }
+}
+
+/// Adds a blurred glow around bright pixels, start it before tone mapping
+pub struct BloomPass {
+    encoder: CommandEncoder,
+}
+
+impl RenderPass for BloomPass {
+    type RequiredPipeline = PostProcessPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let encoder = post_process(
+            frame,
+            "vengine::bloom_encoder",
+            |pipeline, device, encoder, source, target| {
+                pipeline.bloom(device, encoder, source, target);
+            },
+        );
+
+        Self { encoder }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.push_encoder(self.encoder);
+    }
+}
+
+/// Applies tint, saturation and contrast
+pub struct ColorGradingPass {
+    encoder: CommandEncoder,
+}
+
+impl RenderPass for ColorGradingPass {
+    type RequiredPipeline = PostProcessPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let encoder = post_process(
+            frame,
+            "vengine::color_grading_encoder",
+            |pipeline, device, encoder, source, target| {
+                pipeline.draw(
+                    device,
+                    encoder,
+                    &pipeline.color_grading(),
+                    &source.view,
+                    &source.view,
+                    target,
+                );
+            },
+        );
+
+        Self { encoder }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.push_encoder(self.encoder);
+    }
+}
+
+/// Gamma adjustment on top of the sRGB encoding of the surface
+pub struct GammaPass {
+    encoder: CommandEncoder,
+}
+
+impl RenderPass for GammaPass {
+    type RequiredPipeline = PostProcessPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let encoder = post_process(
+            frame,
+            "vengine::gamma_encoder",
+            |pipeline, device, encoder, source, target| {
+                pipeline.draw(
+                    device,
+                    encoder,
+                    &pipeline.gamma(),
+                    &source.view,
+                    &source.view,
+                    target,
+                );
+            },
+        );
+
+        Self { encoder }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.push_encoder(self.encoder);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::engine::{
+    post::{PostProcess, PostProcessUniform},
+    rendering::{
+        backend::Backend,
+        camera::Camera,
+        pipeline::Pipeline,
+        shader::{self, ReloadShader, ShaderError},
+        texture::Texture,
+    },
+};
+use crossbeam::atomic::AtomicCell;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+use wgpu::{
+    util::DeviceExt, BindGroupLayout, Buffer, CommandEncoder, Device, PipelineLayout, Queue,
+    RenderPipeline, Sampler, TextureView,
+};
+
+/// Entry points of the post-process shader, one pipeline each
+struct Pipelines {
+    tone_mapping: RenderPipeline,
+    bloom_prefilter: RenderPipeline,
+    blur_horizontal: RenderPipeline,
+    blur_vertical: RenderPipeline,
+    bloom_composite: RenderPipeline,
+    color_grading: RenderPipeline,
+    gamma: RenderPipeline,
+}
+
+const ENTRY_POINTS: [&str; 8] = [
+    "vs_main",
+    "fs_tone_mapping",
+    "fs_bloom_prefilter",
+    "fs_blur_horizontal",
+    "fs_blur_vertical",
+    "fs_bloom_composite",
+    "fs_color_grading",
+    "fs_gamma",
+];
+
+pub struct PostProcessPipeline {
+    pipelines: RwLock<Pipelines>,
+    layout: PipelineLayout,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    settings: AtomicCell<PostProcess>,
+    settings_buffer: Buffer,
+    /// Half resolution bloom targets, recreated when the frame size changes
+    bloom_textures: Mutex<Option<[Texture; 2]>>,
+    queue: Arc<Queue>,
+}
+
+impl PostProcessPipeline {
+    pub fn settings(&self) -> PostProcess {
+        self.settings.load()
+    }
+
+    pub fn set_settings(&self, settings: PostProcess) {
+        self.settings.store(settings);
+
+        self.queue.write_buffer(
+            &self.settings_buffer,
+            0,
+            bytemuck::cast_slice(&[PostProcessUniform::from(&settings)]),
+        );
+    }
+
+    pub fn tone_mapping(&self) -> RenderPipeline {
+        self.pipelines.read().unwrap().tone_mapping.clone()
+    }
+
+    pub fn color_grading(&self) -> RenderPipeline {
+        self.pipelines.read().unwrap().color_grading.clone()
+    }
+
+    pub fn gamma(&self) -> RenderPipeline {
+        self.pipelines.read().unwrap().gamma.clone()
+    }
+
+    /// Draws a fullscreen triangle reading `source` (and `secondary`) into `target`
+    pub fn draw(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        pipeline: &RenderPipeline,
+        source: &TextureView,
+        secondary: &TextureView,
+        target: &TextureView,
+    ) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("vengine::post_process_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(secondary),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.settings_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("vengine::post_process_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Thresholds `source` into half resolution, blurs it and adds it back into `target`
+    pub fn bloom(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &Texture,
+        target: &TextureView,
+    ) {
+        let width = (source.texture.width() / 2).max(1);
+        let height = (source.texture.height() / 2).max(1);
+
+        let mut lock = self.bloom_textures.lock().unwrap();
+
+        if lock
+            .as_ref()
+            .is_none_or(|t| t[0].texture.width() != width || t[0].texture.height() != height)
+        {
+            *lock = Some(
+                ["vengine::bloom_texture_0", "vengine::bloom_texture_1"].map(|label| {
+                    Texture::create_color_texture(
+                        device,
+                        width,
+                        height,
+                        Texture::HDR_FORMAT,
+                        1,
+                        label,
+                    )
+                }),
+            );
+        }
+
+        let [a, b] = lock.as_ref().unwrap();
+        let pipelines = self.pipelines.read().unwrap();
+
+        self.draw(
+            device,
+            encoder,
+            &pipelines.bloom_prefilter,
+            &source.view,
+            &source.view,
+            &a.view,
+        );
+        self.draw(
+            device,
+            encoder,
+            &pipelines.blur_horizontal,
+            &a.view,
+            &a.view,
+            &b.view,
+        );
+        self.draw(
+            device,
+            encoder,
+            &pipelines.blur_vertical,
+            &b.view,
+            &b.view,
+            &a.view,
+        );
+        self.draw(
+            device,
+            encoder,
+            &pipelines.bloom_composite,
+            &source.view,
+            &a.view,
+            target,
+        );
+    }
+}
+
+impl Pipeline for PostProcessPipeline {
+    fn initialize(backend: &Backend, _camera: &Camera) -> Self {
+        let device = backend.device();
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("vengine::post_process_bind_group_layout"),
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("vengine::post_process_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let settings = PostProcess::default();
+
+        let settings_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vengine::post_process_buffer"),
+            contents: bytemuck::cast_slice(&[PostProcessUniform::from(&settings)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("vengine::post_process_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipelines = create_pipelines(device, &layout, include_str!("shaders/post.wgsl"));
+
+        Self {
+            pipelines: RwLock::new(pipelines),
+            layout,
+            bind_group_layout,
+            sampler,
+            settings: AtomicCell::new(settings),
+            settings_buffer,
+            bloom_textures: Mutex::new(None),
+            queue: backend.queue().clone(),
+        }
+    }
+}
+
+impl ReloadShader for PostProcessPipeline {
+    fn shader_files() -> Vec<PathBuf> {
+        vec![PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/engine/post/rendering/shaders/post.wgsl")]
+    }
+
+    fn reload_shader(&self, backend: &Backend, source: &str) -> Result<(), ShaderError> {
+        shader::validate_wgsl("vengine::post_process_shader", source, &ENTRY_POINTS)?;
+
+        let pipelines =
+            shader::capture_validation(backend, "vengine::post_process_shader", || {
+                create_pipelines(backend.device(), &self.layout, source)
+            })?;
+
+        *self.pipelines.write().unwrap() = pipelines;
+
+        Ok(())
+    }
+}
+
+fn create_pipelines(device: &Device, layout: &PipelineLayout, source: &str) -> Pipelines {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("vengine::post_process_shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    let create = |label, entry_point| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    };
+
+    Pipelines {
+        tone_mapping: create("vengine::tone_mapping_pipeline", "fs_tone_mapping"),
+        bloom_prefilter: create("vengine::bloom_prefilter_pipeline", "fs_bloom_prefilter"),
+        blur_horizontal: create("vengine::blur_horizontal_pipeline", "fs_blur_horizontal"),
+        blur_vertical: create("vengine::blur_vertical_pipeline", "fs_blur_vertical"),
+        bloom_composite: create("vengine::bloom_composite_pipeline", "fs_bloom_composite"),
+        color_grading: create("vengine::color_grading_pipeline", "fs_color_grading"),
+        gamma: create("vengine::gamma_pipeline", "fs_gamma"),
+    }
+}
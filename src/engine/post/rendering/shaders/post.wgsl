@@ -0,0 +1,151 @@
+struct PostProcessUniform {
+    tint: vec4<f32>,
+    exposure: f32,
+    tone_mapper: u32,
+    bloom_threshold: f32,
+    bloom_knee: f32,
+    bloom_intensity: f32,
+    contrast: f32,
+    saturation: f32,
+    gamma: f32,
+};
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+// Second input, only read by the bloom composite
+@group(0) @binding(2)
+var secondary: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> settings: PostProcessUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Fullscreen triangle, no vertex buffer required
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+
+    return out;
+}
+
+const LUMA: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+fn fetch(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(source, source_sampler, uv, 0.0);
+}
+
+// Tone mapping
+
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3(0.0), vec3(1.0));
+}
+
+@fragment
+fn fs_tone_mapping(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fetch(in.uv);
+    let exposed = color.rgb * settings.exposure;
+
+    var mapped: vec3<f32>;
+
+    switch settings.tone_mapper {
+        case 1u: {
+            mapped = exposed / (vec3(1.0) + exposed);
+        }
+        case 2u: {
+            mapped = aces(exposed);
+        }
+        default: {
+            mapped = clamp(exposed, vec3(0.0), vec3(1.0));
+        }
+    }
+
+    return vec4<f32>(mapped, color.a);
+}
+
+// Bloom
+
+@fragment
+fn fs_bloom_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fetch(in.uv).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+
+    // Soft knee around the threshold
+    let knee = settings.bloom_threshold * settings.bloom_knee;
+    var soft = clamp(brightness - settings.bloom_threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+
+    let contribution = max(soft, brightness - settings.bloom_threshold) / max(brightness, 1e-4);
+
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+const WEIGHTS: array<f32, 5> = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let texel = direction / vec2<f32>(textureDimensions(source));
+
+    var color = fetch(uv).rgb * WEIGHTS[0];
+
+    for (var i = 1; i < 5; i++) {
+        let offset = texel * f32(i);
+        color += fetch(uv + offset).rgb * WEIGHTS[i];
+        color += fetch(uv - offset).rgb * WEIGHTS[i];
+    }
+
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_vertical(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+@fragment
+fn fs_bloom_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fetch(in.uv);
+    let bloom = textureSampleLevel(secondary, source_sampler, in.uv, 0.0).rgb;
+
+    return vec4<f32>(color.rgb + bloom * settings.bloom_intensity, color.a);
+}
+
+// Color grading
+
+@fragment
+fn fs_color_grading(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fetch(in.uv);
+
+    var graded = color.rgb * settings.tint.rgb;
+    graded = mix(vec3(dot(graded, LUMA)), graded, settings.saturation);
+    graded = (graded - vec3(0.5)) * settings.contrast + vec3(0.5);
+
+    return vec4<f32>(max(graded, vec3(0.0)), color.a);
+}
+
+// Gamma
+
+@fragment
+fn fs_gamma(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = fetch(in.uv);
+
+    return vec4<f32>(pow(max(color.rgb, vec3(0.0)), vec3(1.0 / settings.gamma)), color.a);
+}
@@ -0,0 +1,199 @@
+use super::Timing;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use wgpu::{Buffer, CommandEncoder, Device, QuerySet, Queue};
+
+/// Timestamps of a submitted frame waiting for their buffer to be mapped
+struct Pending {
+    index: u64,
+    names: Vec<&'static str>,
+    buffer: Buffer,
+    mapped: Arc<AtomicBool>,
+}
+
+/// Timestamp queries written between the command buffers of a frame
+///
+/// Each timestamp is the beginning of an empty compute pass, which only needs
+/// `Features::TIMESTAMP_QUERY` and no writes inside encoders.
+pub struct GpuTimer {
+    query_set: QuerySet,
+    /// Resolved timestamps, copied into a mappable buffer per frame
+    resolve: Buffer,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    pending: Vec<Pending>,
+}
+
+impl GpuTimer {
+    /// Begin and end of every pass, further passes aren't timed
+    pub const MAX_QUERIES: u32 = 128;
+
+    /// `None` if the device was created without `Features::TIMESTAMP_QUERY`
+    pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("vengine::profiler_queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: Self::MAX_QUERIES,
+        });
+
+        let resolve = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vengine::profiler_resolve"),
+            size: Self::MAX_QUERIES as u64 * 8,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve,
+            period: queue.get_timestamp_period(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Encoder writing timestamp `query` when it executes
+    pub fn write(&self, device: &Device, query: u32) -> Option<CommandEncoder> {
+        if query >= Self::MAX_QUERIES {
+            return None;
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("vengine::timestamp_encoder"),
+        });
+
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(query),
+                end_of_pass_write_index: None,
+            }),
+        });
+
+        Some(encoder)
+    }
+
+    /// Encoder copying the first `count` timestamps into the returned buffer
+    pub fn resolve(&self, device: &Device, count: u32) -> (CommandEncoder, Buffer) {
+        let count = count.min(Self::MAX_QUERIES);
+        let size = count as u64 * 8;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vengine::profiler_readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("vengine::profiler_resolve_encoder"),
+        });
+
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &buffer, 0, size);
+
+        (encoder, buffer)
+    }
+
+    /// Maps `buffer` once the frame was submitted, `poll` hands out the timings
+    pub fn read(&mut self, index: u64, names: Vec<&'static str>, buffer: Buffer) {
+        let mapped = Arc::new(AtomicBool::new(false));
+
+        {
+            let mapped = mapped.clone();
+
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    mapped.store(result.is_ok(), Ordering::Release);
+                });
+        }
+
+        self.pending.push(Pending {
+            index,
+            names,
+            buffer,
+            mapped,
+        });
+    }
+
+    /// Pass timings of the frames whose timestamps were mapped, call after polling the device
+    pub fn poll(&mut self) -> Vec<(u64, Vec<Timing>)> {
+        let (mapped, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| p.mapped.load(Ordering::Acquire));
+
+        self.pending = pending;
+
+        mapped
+            .into_iter()
+            .map(|pending| {
+                let timings = {
+                    let data = pending.buffer.slice(..).get_mapped_range();
+                    let ticks: &[u64] = bytemuck::cast_slice(&data);
+
+                    pending
+                        .names
+                        .iter()
+                        .zip(ticks.chunks_exact(2))
+                        .map(|(name, ticks)| Timing {
+                            name,
+                            depth: 0,
+                            duration: Duration::from_nanos(
+                                (ticks[1].saturating_sub(ticks[0]) as f64 * self.period as f64)
+                                    as u64,
+                            ),
+                        })
+                        .collect()
+                };
+
+                pending.buffer.unmap();
+
+                (pending.index, timings)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_gpu_timestamps() {
+    use crate::engine::{
+        rendering::{test_backend, Renderer},
+        sky::rendering::{pass::SkyPass, pipeline::SkyPipeline},
+    };
+
+    let Some(backend) = test_backend(32, 32) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.register_pipeline::<SkyPipeline>();
+
+    let frame = renderer.start_frame().unwrap();
+    let pass: SkyPass = frame.start_render_pass().unwrap();
+    frame.finish_render_pass(pass);
+    renderer.finish_frame(frame);
+
+    // Closes a second frame once the timestamps of the first are mapped
+    renderer.backend().device().poll(wgpu::Maintain::Wait);
+    renderer.profiler().end_frame(u64::MAX);
+
+    let frames = renderer.profiler().frames();
+    assert_eq!(frames[0].counters.draw_calls, 1);
+
+    if renderer.profiler().supports_gpu_timing() {
+        let names = frames[0].gpu.iter().map(|t| t.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["SkyPass"]);
+    } else {
+        assert!(frames[0].gpu.is_empty());
+    }
+}
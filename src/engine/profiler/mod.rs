@@ -0,0 +1,510 @@
+pub mod gpu;
+
+use gpu::GpuTimer;
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    ops::AddAssign,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+/// Time spent in a CPU scope or GPU pass during one frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timing {
+    pub name: &'static str,
+    /// Nesting level of CPU scopes, GPU passes are always `0`
+    pub depth: u32,
+    pub duration: Duration,
+}
+
+/// Work submitted by the engine's passes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub draw_calls: u64,
+    pub instances: u64,
+    pub chunks: u64,
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, other: Self) {
+        self.draw_calls += other.draw_calls;
+        self.instances += other.instances;
+        self.chunks += other.chunks;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameProfile {
+    /// `Renderer::frame_index` of the frame
+    pub index: u64,
+    /// Wall clock time since the previous frame finished
+    pub duration: Duration,
+    /// In the order the scopes were entered
+    pub cpu: Vec<Timing>,
+    /// In the order the passes were started, read back a few frames later
+    pub gpu: Vec<Timing>,
+    pub counters: Counters,
+}
+
+impl FrameProfile {
+    /// Time of the outermost CPU scopes
+    pub fn cpu_time(&self) -> Duration {
+        self.cpu
+            .iter()
+            .filter(|t| t.depth == 0)
+            .map(|t| t.duration)
+            .sum()
+    }
+
+    pub fn gpu_time(&self) -> Duration {
+        self.gpu.iter().map(|t| t.duration).sum()
+    }
+}
+
+/// Frame being recorded
+struct Current {
+    start: Instant,
+    cpu: Vec<Timing>,
+    depth: u32,
+    counters: Counters,
+    /// Invalidates scopes still open when the frame ends
+    generation: u64,
+}
+
+/// Named CPU scopes, GPU pass timestamps and counters per frame
+///
+/// The renderer ends a profiler frame in `finish_frame`, scopes entered in between belong to
+/// the next frame. GPU passes are timed with `Features::TIMESTAMP_QUERY` where the adapter
+/// supports it, around every pass started with `Frame::start_render_pass`.
+pub struct Profiler {
+    enabled: AtomicBool,
+    current: Mutex<Current>,
+    history: Mutex<VecDeque<FrameProfile>>,
+    gpu: Mutex<Option<GpuTimer>>,
+}
+
+impl Profiler {
+    /// Frames kept for the graph and the exports
+    pub const HISTORY: usize = 300;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            current: Mutex::new(Current {
+                start: Instant::now(),
+                cpu: Vec::new(),
+                depth: 0,
+                counters: Counters::default(),
+                generation: 0,
+            }),
+            history: Mutex::new(VecDeque::with_capacity(Self::HISTORY)),
+            gpu: Mutex::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// While disabled scopes, counters and GPU timestamps are skipped, frames are still timed
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether GPU passes are timed
+    pub fn supports_gpu_timing(&self) -> bool {
+        self.gpu.lock().unwrap().is_some()
+    }
+
+    /// Times the enclosing block until the returned guard is dropped
+    pub fn scope(&self, name: &'static str) -> Scope<'_> {
+        let mut current = self.current.lock().unwrap();
+
+        let index = self.is_enabled().then(|| {
+            let depth = current.depth;
+
+            current.cpu.push(Timing {
+                name,
+                depth,
+                duration: Duration::ZERO,
+            });
+            current.depth += 1;
+
+            current.cpu.len() - 1
+        });
+
+        Scope {
+            profiler: self,
+            index,
+            generation: current.generation,
+            start: Instant::now(),
+        }
+    }
+
+    /// Adds a span measured by the caller, at the depth of the innermost open scope
+    pub fn record(&self, name: &'static str, duration: Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut current = self.current.lock().unwrap();
+        let depth = current.depth;
+
+        current.cpu.push(Timing {
+            name,
+            depth,
+            duration,
+        });
+    }
+
+    pub fn count(&self, counters: Counters) {
+        if self.is_enabled() {
+            self.current.lock().unwrap().counters += counters;
+        }
+    }
+
+    /// Closes the frame `index` and attaches GPU timings that were read back meanwhile
+    pub fn end_frame(&self, index: u64) {
+        let now = Instant::now();
+
+        let profile = {
+            let mut current = self.current.lock().unwrap();
+
+            let profile = FrameProfile {
+                index,
+                duration: now - current.start,
+                cpu: std::mem::take(&mut current.cpu),
+                gpu: Vec::new(),
+                counters: std::mem::take(&mut current.counters),
+            };
+
+            current.start = now;
+            current.depth = 0;
+            current.generation += 1;
+
+            profile
+        };
+
+        let mut history = self.history.lock().unwrap();
+
+        if history.len() >= Self::HISTORY {
+            history.pop_front();
+        }
+        history.push_back(profile);
+
+        if let Some(gpu) = self.gpu.lock().unwrap().as_mut() {
+            for (index, timings) in gpu.poll() {
+                if let Some(frame) = history.iter_mut().find(|f| f.index == index) {
+                    frame.gpu = timings;
+                }
+            }
+        }
+    }
+
+    /// Recorded frames, oldest first
+    pub fn frames(&self) -> Vec<FrameProfile> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.history.lock().unwrap().clear();
+    }
+
+    /// Frame time below which `p` percent of the recorded frames stay
+    pub fn percentile(&self, p: f32) -> Duration {
+        let mut durations = self
+            .history
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|f| f.duration)
+            .collect::<Vec<_>>();
+
+        durations.sort();
+        percentile(&durations, p)
+    }
+
+    /// Creates the GPU timer for a new device, pending timestamps of the old one are dropped
+    pub fn set_device(&self, device: &Device, queue: &Queue) {
+        *self.gpu.lock().unwrap() = GpuTimer::new(device, queue);
+    }
+
+    /// Encoder writing timestamp `query` when it executes, `None` without GPU timing
+    pub(crate) fn timestamp(&self, device: &Device, query: u32) -> Option<CommandEncoder> {
+        self.gpu
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|gpu| gpu.write(device, query))
+    }
+
+    /// Copies the first `count` timestamps into a buffer to read back after submitting
+    pub(crate) fn resolve_timestamps(
+        &self,
+        device: &Device,
+        count: u32,
+    ) -> Option<(CommandEncoder, Buffer)> {
+        self.gpu
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|gpu| gpu.resolve(device, count))
+    }
+
+    /// Reads the submitted timestamps of frame `index` back, a begin and end per pass name
+    pub(crate) fn read_timestamps(&self, index: u64, names: Vec<&'static str>, buffer: Buffer) {
+        if let Some(gpu) = self.gpu.lock().unwrap().as_mut() {
+            gpu.read(index, names, buffer);
+        }
+    }
+
+    /// One row per frame with a column for every scope and pass name seen, times in ms
+    pub fn to_csv(&self) -> String {
+        let frames = self.frames();
+
+        let mut cpu_names: Vec<&'static str> = Vec::new();
+        let mut gpu_names: Vec<&'static str> = Vec::new();
+
+        for frame in &frames {
+            for timing in &frame.cpu {
+                if !cpu_names.contains(&timing.name) {
+                    cpu_names.push(timing.name);
+                }
+            }
+
+            for timing in &frame.gpu {
+                if !gpu_names.contains(&timing.name) {
+                    gpu_names.push(timing.name);
+                }
+            }
+        }
+
+        let mut csv = String::from("frame,frame_ms,cpu_ms,gpu_ms,draw_calls,instances,chunks");
+
+        for name in &cpu_names {
+            write!(csv, ",cpu:{}", csv_field(name)).unwrap();
+        }
+        for name in &gpu_names {
+            write!(csv, ",gpu:{}", csv_field(name)).unwrap();
+        }
+        csv.push('\n');
+
+        let sum = |timings: &[Timing], name: &str| -> Option<Duration> {
+            timings
+                .iter()
+                .filter(|t| t.name == name)
+                .map(|t| t.duration)
+                .reduce(|a, b| a + b)
+        };
+
+        for frame in &frames {
+            write!(
+                csv,
+                "{},{:.3},{:.3},{:.3},{},{},{}",
+                frame.index,
+                ms(frame.duration),
+                ms(frame.cpu_time()),
+                ms(frame.gpu_time()),
+                frame.counters.draw_calls,
+                frame.counters.instances,
+                frame.counters.chunks
+            )
+            .unwrap();
+
+            for (timings, names) in [(&frame.cpu, &cpu_names), (&frame.gpu, &gpu_names)] {
+                for name in names {
+                    match sum(timings, name) {
+                        Some(duration) => write!(csv, ",{:.3}", ms(duration)).unwrap(),
+                        None => csv.push(','),
+                    }
+                }
+            }
+
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// `{"frames": [...]}` with the timings of every frame in ms
+    pub fn to_json(&self) -> String {
+        let timings = |timings: &[Timing]| {
+            timings
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{{\"name\":{},\"depth\":{},\"ms\":{:.3}}}",
+                        json_string(t.name),
+                        t.depth,
+                        ms(t.duration)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        let frames = self
+            .frames()
+            .iter()
+            .map(|frame| {
+                format!(
+                    "{{\"index\":{},\"frame_ms\":{:.3},\"cpu\":[{}],\"gpu\":[{}],\
+                     \"counters\":{{\"draw_calls\":{},\"instances\":{},\"chunks\":{}}}}}",
+                    frame.index,
+                    ms(frame.duration),
+                    timings(&frame.cpu),
+                    timings(&frame.gpu),
+                    frame.counters.draw_calls,
+                    frame.counters.instances,
+                    frame.counters.chunks
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!("{{\"frames\":[{}]}}\n", frames.join(",\n"))
+    }
+
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard of `Profiler::scope`, records the elapsed time when dropped
+pub struct Scope<'a> {
+    profiler: &'a Profiler,
+    /// Entry in the current frame, `None` while the profiler is disabled
+    index: Option<usize>,
+    generation: u64,
+    start: Instant,
+}
+
+impl Drop for Scope<'_> {
+    fn drop(&mut self) {
+        let Some(index) = self.index else {
+            return;
+        };
+
+        let duration = self.start.elapsed();
+        let mut current = self.profiler.current.lock().unwrap();
+
+        // The frame ended while the scope was open
+        if current.generation != self.generation {
+            return;
+        }
+
+        current.cpu[index].duration = duration;
+        current.depth = current.depth.saturating_sub(1);
+    }
+}
+
+/// Nearest-rank percentile of sorted durations
+pub fn percentile(sorted: &[Duration], p: f32) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+
+    let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn csv_field(name: &str) -> String {
+    if name.contains([',', '"', '\n']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+#[test]
+fn test_profiler_scopes() {
+    let profiler = Profiler::new();
+
+    {
+        let _update = profiler.scope("update");
+        let _physics = profiler.scope("physics");
+    }
+    profiler.record("render", Duration::from_millis(4));
+    profiler.count(Counters {
+        draw_calls: 2,
+        instances: 300,
+        chunks: 2,
+    });
+    profiler.end_frame(1);
+
+    // Disabled frames are timed without scopes
+    profiler.set_enabled(false);
+    drop(profiler.scope("skipped"));
+    profiler.end_frame(2);
+
+    let frames = profiler.frames();
+    assert_eq!(frames.len(), 2);
+
+    let names = frames[0]
+        .cpu
+        .iter()
+        .map(|t| (t.name, t.depth))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![("update", 0), ("physics", 1), ("render", 0)]);
+    assert!(frames[0].cpu_time() >= Duration::from_millis(4));
+    assert_eq!(frames[0].counters.instances, 300);
+    assert!(frames[1].cpu.is_empty());
+
+    let csv = profiler.to_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("frame,frame_ms,cpu_ms,gpu_ms,draw_calls,instances,chunks,cpu:update,cpu:physics,cpu:render")
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("1,"));
+    assert!(row.contains(",0.000,2,300,2,"));
+    assert!(row.ends_with(",4.000"));
+    assert!(lines.next().unwrap().starts_with("2,"));
+
+    let json = profiler.to_json();
+    assert!(json.starts_with("{\"frames\":[{\"index\":1,"));
+    assert!(json.contains("{\"name\":\"render\",\"depth\":0,\"ms\":4.000}"));
+
+    let sorted = (1..=100).map(Duration::from_millis).collect::<Vec<_>>();
+    assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(50));
+    assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(99));
+    assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(1));
+}
@@ -0,0 +1,18 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AntiAliasing {
+    #[default]
+    None,
+    /// 4x multisampling, resolved into the HDR target after every scene pass, before post-processing
+    Msaa4x,
+    /// Fast approximate anti-aliasing applied to the scene before the UI is drawn
+    Fxaa,
+}
+
+impl AntiAliasing {
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            AntiAliasing::Msaa4x => 4,
+            AntiAliasing::None | AntiAliasing::Fxaa => 1,
+        }
+    }
+}
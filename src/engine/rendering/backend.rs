@@ -1,96 +1,342 @@
+use crossbeam::atomic::AtomicCell;
 use std::sync::{Arc, Mutex};
 use wgpu::{
-    Backends, Device, Instance, InstanceDescriptor, Queue, Surface, SurfaceConfiguration,
-    TextureFormat,
+    Adapter, Device, Instance, InstanceDescriptor, Queue, Surface, SurfaceConfiguration,
+    TextureFormat, TextureFormatFeatureFlags,
 };
 
-use crate::engine::core::window::window::Window;
+use super::{
+    options::{BackendOptions, PresentMode, HDR_FORMATS},
+    texture::Texture,
+};
+use crate::engine::{core::window::window::Window, error::EngineError};
 
-pub struct Backend<'a> {
-    surface: Surface<'a>,
+/// Fields drop in declaration order, the surface before the device and the window last
+pub struct Backend {
+    /// `None` for headless backends, which render into an offscreen texture
+    surface: Option<Surface<'static>>,
     device: Device,
     queue: Arc<Queue>,
+    adapter: Adapter,
+    /// Kept to create the surfaces of further windows, see `Viewport`
+    instance: Instance,
     config: Mutex<SurfaceConfiguration>,
     format: TextureFormat,
-    window: &'a Window,
+    window: Option<Window>,
+    options: BackendOptions,
+    present_mode: AtomicCell<PresentMode>,
+    /// Reason reported by the device lost callback
+    lost: Arc<Mutex<Option<String>>>,
 }
 
-impl<'a> Backend<'a> {
-    pub async fn new(window: Window) -> Self {
+impl Backend {
+    pub async fn new(window: Window) -> Result<Self, EngineError> {
+        Self::with_options(window, BackendOptions::default()).await
+    }
+
+    pub async fn with_options(
+        window: Window,
+        options: BackendOptions,
+    ) -> Result<Self, EngineError> {
         let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
+            backends: options.backends,
             ..Default::default()
         });
 
-        let window: &'a Window = Box::leak(Box::new(window));
+        // The surface keeps its own handle to the window, so it doesn't borrow from `self`
+        let surface = instance.create_surface(window.handle())?;
 
-        let surface = instance.create_surface(window.window()).unwrap();
+        let adapter = Self::request_adapter(&instance, &options, Some(&surface), false).await?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
+        let surface_caps = surface.get_capabilities(&adapter);
+
+        let hdr_format = options
+            .hdr
+            .then(|| {
+                HDR_FORMATS
+                    .into_iter()
+                    .find(|f| surface_caps.formats.contains(f))
             })
-            .await
-            .unwrap();
+            .flatten();
+
+        let surface_format = hdr_format
+            .or(surface_caps.formats.iter().copied().find(|f| f.is_srgb()))
+            .or(surface_caps.formats.first().copied())
+            .ok_or(EngineError::IncompatibleSurface)?;
+
+        let (device, queue, lost) = Self::request_device(&adapter, &options).await?;
+
+        let (width, height) = window.dimension();
+
+        let config = wgpu::SurfaceConfiguration {
+            // Screenshots copy from the swapchain texture where supported
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC),
+            format: surface_format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: options.present_mode.select(&surface_caps.present_modes),
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: options.frame_latency,
+        };
+        surface.configure(&device, &config);
+
+        Ok(Self {
+            instance,
+            device,
+            queue: Arc::new(queue),
+            surface: Some(surface),
+            adapter,
+            config: Mutex::new(config),
+            format: surface_format,
+            window: Some(window),
+            present_mode: AtomicCell::new(options.present_mode),
+            options,
+            lost,
+        })
+    }
+
+    /// Creates a backend without a window that renders into an offscreen texture
+    /// Falls back to a software adapter when there is no GPU
+    pub async fn headless(width: u32, height: u32) -> Result<Self, EngineError> {
+        Self::headless_with_options(
+            width,
+            height,
+            BackendOptions::default().backends(wgpu::Backends::all()),
+        )
+        .await
+    }
+
+    pub async fn headless_with_options(
+        width: u32,
+        height: u32,
+        options: BackendOptions,
+    ) -> Result<Self, EngineError> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: options.backends,
+            ..Default::default()
+        });
+
+        let adapter = match Self::request_adapter(&instance, &options, None, false).await {
+            Err(EngineError::NoAdapter) if options.adapter_index.is_none() => {
+                Self::request_adapter(&instance, &options, None, true).await?
+            }
+            adapter => adapter?,
+        };
+
+        let (device, queue, lost) = Self::request_device(&adapter, &options).await?;
+
+        let format = Texture::HEADLESS_FORMAT;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: options.frame_latency,
+        };
+
+        Ok(Self {
+            instance,
+            device,
+            queue: Arc::new(queue),
+            surface: None,
+            adapter,
+            config: Mutex::new(config),
+            format,
+            window: None,
+            present_mode: AtomicCell::new(options.present_mode),
+            options,
+            lost,
+        })
+    }
+
+    /// The adapter at `options.adapter_index`, otherwise the one matching the power preference
+    async fn request_adapter(
+        instance: &Instance,
+        options: &BackendOptions,
+        surface: Option<&Surface<'_>>,
+        force_fallback_adapter: bool,
+    ) -> Result<Adapter, EngineError> {
+        let Some(index) = options.adapter_index else {
+            return instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: options.power_preference,
+                    compatible_surface: surface,
+                    force_fallback_adapter,
+                })
+                .await
+                .ok_or(EngineError::NoAdapter);
+        };
+
+        let adapter = instance
+            .enumerate_adapters(options.backends)
+            .into_iter()
+            .nth(index)
+            .ok_or(EngineError::InvalidAdapter(index))?;
+
+        match surface {
+            Some(surface) if !adapter.is_surface_supported(surface) => {
+                Err(EngineError::IncompatibleSurface)
+            }
+            _ => Ok(adapter),
+        }
+    }
+
+    async fn request_device(
+        adapter: &Adapter,
+        options: &BackendOptions,
+    ) -> Result<(Device, Queue, Arc<Mutex<Option<String>>>), EngineError> {
+        let missing = options.features - adapter.features();
+        if !missing.is_empty() {
+            return Err(EngineError::MissingFeatures(missing));
+        }
+
+        // Pipelines fall back to uniform buffers without push constants
+        let push_constants = adapter.features().contains(wgpu::Features::PUSH_CONSTANTS);
+
+        let limits = wgpu::Limits {
+            max_push_constant_size: if push_constants {
+                options
+                    .limits
+                    .max_push_constant_size
+                    .max(128)
+                    .min(adapter.limits().max_push_constant_size)
+            } else {
+                0
+            },
+            ..options.limits.clone()
+        }
+        .using_resolution(adapter.limits());
+
+        let mut unsupported = Vec::new();
+        limits.check_limits_with_fail_fn(&adapter.limits(), false, |name, _, _| {
+            unsupported.push(name)
+        });
+        if !unsupported.is_empty() {
+            return Err(EngineError::UnsupportedLimits(unsupported));
+        }
+
+        // The profiler times passes where timestamps are available
+        let optional = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::PUSH_CONSTANTS,
-                    // WebGL doesn't support all of wgpu's features, so if
-                    // we're building for the web, we'll have to disable some.
-                    required_limits: wgpu::Limits {
-                        max_push_constant_size: 128, // Mindestens 12 Bytes, typischerweise 128 oder 256
-                        ..Default::default()
+                    required_features: if push_constants {
+                        options.features | optional | wgpu::Features::PUSH_CONSTANTS
+                    } else {
+                        options.features | optional
                     },
+                    required_limits: limits,
 
                     label: None,
                     memory_hints: Default::default(),
                 },
                 None, // Trace path
             )
-            .await
-            .unwrap();
+            .await?;
 
-        let surface_caps = surface.get_capabilities(&adapter);
+        let lost = Arc::new(Mutex::new(None));
 
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        let reason = lost.clone();
+        device.set_device_lost_callback(move |_, message| {
+            *reason.lock().unwrap() = Some(message);
+        });
 
-        let (width, height) = window.dimension();
+        Ok((device, queue, lost))
+    }
 
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width,
-            height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+    pub fn options(&self) -> &BackendOptions {
+        &self.options
+    }
 
-        let queue = Arc::new(queue);
+    /// Present mode last requested, the surface may use a fallback, see `PresentMode::select`
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode.load()
+    }
 
-        Self {
-            device,
-            queue,
-            surface,
-            config: Mutex::new(config),
-            format: surface_format,
-            window,
+    /// Reconfigures the surface with the closest supported mode and returns it
+    pub fn set_present_mode(&self, present_mode: PresentMode) -> wgpu::PresentMode {
+        self.present_mode.store(present_mode);
+
+        let mut config = self.config.lock().unwrap();
+
+        if let Some(surface) = &self.surface {
+            config.present_mode =
+                present_mode.select(&surface.get_capabilities(&self.adapter).present_modes);
+            surface.configure(&self.device, &config);
+        }
+
+        config.present_mode
+    }
+
+    /// Why the device was lost, `None` while it is usable
+    pub fn device_lost(&self) -> Option<String> {
+        self.lost.lock().unwrap().clone()
+    }
+
+    /// Requests a new device from the adapter and reconfigures the surface with it
+    /// Everything created with the old device has to be recreated, see `Renderer::recover`
+    pub async fn recreate_device(&mut self) -> Result<(), EngineError> {
+        let (device, queue, lost) = Self::request_device(&self.adapter, &self.options).await?;
+
+        if let Some(surface) = &self.surface {
+            surface.configure(&device, &self.config.lock().unwrap());
         }
+
+        self.device = device;
+        self.queue = Arc::new(queue);
+        self.lost = lost;
+
+        Ok(())
+    }
+
+    /// Whether the device was created with `Features::PUSH_CONSTANTS`
+    pub fn supports_push_constants(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::PUSH_CONSTANTS)
     }
 
+    /// Panics for headless backends, the UI and input need a window
     pub fn window(&self) -> &Window {
         self.window
+            .as_ref()
+            .expect("headless backend has no window")
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    /// Whether `samples` can be rendered and resolved with the scene and depth format
+    pub fn supports_sample_count(&self, samples: u32) -> bool {
+        let color = self
+            .adapter
+            .get_texture_format_features(Texture::HDR_FORMAT)
+            .flags;
+        let depth = self
+            .adapter
+            .get_texture_format_features(Texture::DEPTH_FORMAT)
+            .flags;
+
+        samples == 1
+            || (color.sample_count_supported(samples)
+                && color.contains(TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                && depth.sample_count_supported(samples))
     }
 
     pub fn device(&self) -> &Device {
@@ -101,8 +347,8 @@ impl<'a> Backend<'a> {
         &self.queue
     }
 
-    pub fn surface(&self) -> &Surface {
-        &self.surface
+    pub fn surface(&self) -> Option<&Surface<'static>> {
+        self.surface.as_ref()
     }
 
     pub fn surface_configuration(&self) -> &Mutex<SurfaceConfiguration> {
@@ -113,3 +359,56 @@ impl<'a> Backend<'a> {
         &self.format
     }
 }
+
+#[test]
+fn test_headless_options() {
+    let options = BackendOptions::new().backends(wgpu::Backends::all());
+
+    let Some(backend) =
+        super::test_backend_with_options(4, 4, options.clone().present_mode(PresentMode::Mailbox))
+    else {
+        return;
+    };
+
+    assert_eq!(backend.present_mode(), PresentMode::Mailbox);
+    assert_eq!(
+        backend.set_present_mode(PresentMode::Immediate),
+        wgpu::PresentMode::Fifo
+    );
+    assert_eq!(backend.present_mode(), PresentMode::Immediate);
+
+    assert!(matches!(
+        pollster::block_on(Backend::headless_with_options(
+            4,
+            4,
+            options.clone().adapter_index(usize::MAX)
+        )),
+        Err(EngineError::InvalidAdapter(usize::MAX))
+    ));
+
+    // Limits beyond the adapter fail like missing features
+    match pollster::block_on(Backend::headless_with_options(
+        4,
+        4,
+        options.clone().limits(wgpu::Limits {
+            max_bind_groups: u32::MAX,
+            ..backend.adapter().limits()
+        }),
+    )) {
+        Err(EngineError::UnsupportedLimits(names)) => assert_eq!(names, vec!["max_bind_groups"]),
+        _ => panic!("expected UnsupportedLimits"),
+    }
+
+    let missing = wgpu::Features::all() - backend.adapter().features();
+
+    if !missing.is_empty() {
+        assert!(matches!(
+            pollster::block_on(Backend::headless_with_options(
+                4,
+                4,
+                options.features(wgpu::Features::all())
+            )),
+            Err(EngineError::MissingFeatures(_))
+        ));
+    }
+}
@@ -1,8 +1,10 @@
-use cgmath::{Point3, Vector3};
+use cgmath::{Point3, SquareMatrix, Vector3};
 use crossbeam::atomic::AtomicCell;
 use std::sync::Arc;
 use wgpu::{util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, Queue};
 
+use crate::engine::sky::{Sky, SkyUniform};
+
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
@@ -22,6 +24,8 @@ pub struct Camera {
     camera_uniform: AtomicCell<CameraUniform>,
     camera_bind_group: BindGroup,
     camera_buffer: Buffer,
+    sky: AtomicCell<Sky>,
+    sky_buffer: Buffer,
     camera_bind_group_layout: BindGroupLayout,
     queue: Arc<Queue>,
 }
@@ -36,27 +40,53 @@ impl Camera {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let sky = Sky::default();
+
+        let sky_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vengine::sky_buffer"),
+            contents: bytemuck::cast_slice(&[SkyUniform::from(&sky)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                ],
                 label: Some("vengine::camera_bind_group_layout"),
             });
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: sky_buffer.as_entire_binding(),
+                },
+            ],
             label: Some("vengine::camera_bind_group"),
         });
 
@@ -71,6 +101,8 @@ impl Camera {
             camera_uniform: AtomicCell::new(camera_uniform),
             camera_bind_group,
             camera_buffer,
+            sky: AtomicCell::new(sky),
+            sky_buffer,
             camera_bind_group_layout,
             queue,
         };
@@ -80,9 +112,33 @@ impl Camera {
         camera
     }
 
+    /// Camera with the same view, projection and sky whose buffers live on `device`
+    pub fn recreate(&self, device: &Device, queue: Arc<Queue>) -> Self {
+        let mut camera = Camera::new(self.aspect.load(), device, queue);
+
+        camera.eye.store(self.eye.load());
+        camera.target.store(self.target.load());
+        camera.up = self.up;
+        camera.fovy = self.fovy;
+        camera.znear = self.znear;
+        camera.zfar = self.zfar;
+        camera.update();
+        camera.set_sky(self.sky());
+
+        camera
+    }
+
     pub fn update(&self) {
+        let view_proj = self.build_view_projection_matrix();
+        let eye = self.eye.load();
+
         self.camera_uniform.store(CameraUniform {
-            view_proj: self.build_view_projection_matrix().into(),
+            view_proj: view_proj.into(),
+            inverse_view_proj: view_proj
+                .invert()
+                .unwrap_or(cgmath::Matrix4::identity())
+                .into(),
+            eye: [eye.x, eye.y, eye.z, 1.0],
         });
 
         let tmp = self.camera_uniform.load();
@@ -122,6 +178,21 @@ impl Camera {
         self.eye.store(n);
     }
 
+    pub fn sky(&self) -> Sky {
+        self.sky.load()
+    }
+
+    /// Replaces the sky and fog settings shared by every pass bound to the camera bind group
+    pub fn set_sky(&self, sky: Sky) {
+        self.sky.store(sky);
+
+        self.queue.write_buffer(
+            &self.sky_buffer,
+            0,
+            bytemuck::cast_slice(&[SkyUniform::from(&sky)]),
+        );
+    }
+
     pub fn bind_group(&self) -> &BindGroup {
         &self.camera_bind_group
     }
@@ -134,7 +205,17 @@ impl Camera {
         self.up
     }
 
-    fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+    /// Vertical field of view in degrees
+    pub fn fovy(&self) -> f32 {
+        self.fovy
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect.load()
+    }
+
+    /// Matrix from world to clip space, as in the camera uniform
+    pub fn build_view_projection_matrix(&self) -> cgmath::Matrix4<f32> {
         let view = cgmath::Matrix4::look_at_rh(self.eye.load(), self.target.load(), self.up);
         let proj = cgmath::perspective(
             cgmath::Deg(self.fovy),
@@ -155,18 +236,29 @@ pub struct CameraUniform {
     // We can't use cgmath with bytemuck directly, so we'll have
     // to convert the Matrix4 into a 4x4 f32 array
     view_proj: [[f32; 4]; 4],
+    inverse_view_proj: [[f32; 4]; 4],
+    eye: [f32; 4],
 }
 
 impl CameraUniform {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        use cgmath::SquareMatrix;
         Self {
             view_proj: cgmath::Matrix4::identity().into(),
+            inverse_view_proj: cgmath::Matrix4::identity().into(),
+            eye: [0.0, 0.0, 0.0, 1.0],
         }
     }
 
     pub fn update_view_proj(&mut self, camera: &Camera) {
-        self.view_proj = camera.build_view_projection_matrix().into();
+        let view_proj = camera.build_view_projection_matrix();
+        let eye = camera.get_eye();
+
+        self.view_proj = view_proj.into();
+        self.inverse_view_proj = view_proj
+            .invert()
+            .unwrap_or(cgmath::Matrix4::identity())
+            .into();
+        self.eye = [eye.x, eye.y, eye.z, 1.0];
     }
 }
@@ -0,0 +1,364 @@
+use super::readback::{self, Image};
+use crossbeam::channel::{bounded, Sender};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use wgpu::{Buffer, Device, Queue, TextureFormat};
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The frame output lacks `TextureUsages::COPY_SRC`
+    NotCopyable,
+    /// `readback::is_readable` rejects the format of the frame output
+    UnsupportedFormat(TextureFormat),
+    Write {
+        path: PathBuf,
+        error: png::EncodingError,
+    },
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::NotCopyable => write!(f, "frame output can't be copied"),
+            CaptureError::UnsupportedFormat(format) => {
+                write!(f, "frame output format {:?} can't be captured", format)
+            }
+            CaptureError::Write { path, error } => {
+                write!(f, "failed to write {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CaptureError::Write { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Numbered PNG files written for every frame while recording
+struct Sequence {
+    directory: PathBuf,
+    next: u32,
+}
+
+/// Frame copy waiting for its staging buffer to be mapped
+struct Readback {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    /// A screenshot and a sequence frame can share one copy
+    paths: Vec<PathBuf>,
+    mapped: Arc<AtomicBool>,
+}
+
+/// Work for the writer thread, handled in order
+enum Job {
+    Write(Image, PathBuf),
+    /// Acknowledged once every earlier image is written
+    Flush(Sender<()>),
+}
+
+/// Copies finished frames into staging buffers and writes them as PNG on a worker thread
+pub struct Capture {
+    screenshot: Mutex<Option<PathBuf>>,
+    sequence: Mutex<Option<Sequence>>,
+    readbacks: Mutex<Vec<Readback>>,
+    writer: Sender<Job>,
+    /// Failures since the last `flush` or `take_errors`
+    errors: Arc<Mutex<Vec<CaptureError>>>,
+}
+
+impl Capture {
+    /// Images queued for the writer thread, handing out more blocks until it catches up
+    pub const QUEUE: usize = 8;
+
+    pub fn new() -> Self {
+        let (writer, receiver) = bounded::<Job>(Self::QUEUE);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        let thread_errors = errors.clone();
+
+        std::thread::Builder::new()
+            .name("vengine::capture".to_string())
+            .spawn(move || {
+                while let Ok(job) = receiver.recv() {
+                    match job {
+                        Job::Write(image, path) => {
+                            if let Err(error) = image.save_png(&path) {
+                                thread_errors
+                                    .lock()
+                                    .unwrap()
+                                    .push(CaptureError::Write { path, error });
+                            }
+                        }
+                        Job::Flush(ack) => {
+                            let _ = ack.send(());
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        Self {
+            screenshot: Mutex::new(None),
+            sequence: Mutex::new(None),
+            readbacks: Mutex::new(Vec::new()),
+            writer,
+            errors,
+        }
+    }
+
+    /// Captures the next finished frame to `path`
+    pub fn screenshot<P: AsRef<Path>>(&self, path: P) {
+        *self.screenshot.lock().unwrap() = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Captures every following frame to `directory/frame_000000.png` and onwards
+    pub fn start_recording<P: AsRef<Path>>(&self, directory: P) -> std::io::Result<()> {
+        std::fs::create_dir_all(&directory)?;
+
+        *self.sequence.lock().unwrap() = Some(Sequence {
+            directory: directory.as_ref().to_path_buf(),
+            next: 0,
+        });
+
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) {
+        *self.sequence.lock().unwrap() = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.sequence.lock().unwrap().is_some()
+    }
+
+    /// Drops pending copies, their buffers belong to a lost device and never map
+    pub fn discard(&self) {
+        self.readbacks.lock().unwrap().clear();
+    }
+
+    /// Paths the current frame has to be written to
+    fn take_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        if let Some(path) = self.screenshot.lock().unwrap().take() {
+            paths.push(path);
+        }
+
+        if let Some(sequence) = self.sequence.lock().unwrap().as_mut() {
+            paths.push(
+                sequence
+                    .directory
+                    .join(format!("frame_{:06}.png", sequence.next)),
+            );
+            sequence.next += 1;
+        }
+
+        paths
+    }
+
+    /// Copies `texture` if a capture is pending, call it after the frame was submitted
+    /// On failure the pending screenshot and sequence frame are skipped
+    pub fn capture(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        texture: &wgpu::Texture,
+    ) -> Result<(), CaptureError> {
+        let paths = self.take_paths();
+
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(CaptureError::NotCopyable);
+        }
+
+        if !readback::is_readable(texture.format()) {
+            return Err(CaptureError::UnsupportedFormat(texture.format()));
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("vengine::capture_encoder"),
+        });
+
+        let buffer = readback::copy_to_buffer(device, &mut encoder, texture);
+
+        queue.submit([encoder.finish()]);
+
+        let mapped = Arc::new(AtomicBool::new(false));
+
+        {
+            let mapped = mapped.clone();
+
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    mapped.store(result.is_ok(), Ordering::Release);
+                });
+        }
+
+        self.readbacks.lock().unwrap().push(Readback {
+            buffer,
+            width: texture.width(),
+            height: texture.height(),
+            format: texture.format(),
+            paths,
+            mapped,
+        });
+
+        Ok(())
+    }
+
+    /// Hands mapped frames to the writer thread, only blocks while its queue is full
+    pub fn poll(&self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+
+        self.process();
+    }
+
+    /// Waits until every pending frame is written, including those already handed out
+    /// Returns the first failure since the last call, `take_errors` has all of them
+    pub fn flush(&self, device: &Device) -> Result<(), CaptureError> {
+        if !self.readbacks.lock().unwrap().is_empty() {
+            device.poll(wgpu::Maintain::Wait);
+            self.process();
+        }
+
+        let (ack, done) = bounded(1);
+
+        if self.writer.send(Job::Flush(ack)).is_ok() {
+            let _ = done.recv();
+        }
+
+        match self.take_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Failed captures and writes since the last `flush` or call
+    pub fn take_errors(&self) -> Vec<CaptureError> {
+        std::mem::take(&mut *self.errors.lock().unwrap())
+    }
+
+    /// Keeps a failure of `capture` for `flush` and `take_errors`
+    pub(super) fn report(&self, error: CaptureError) {
+        self.errors.lock().unwrap().push(error);
+    }
+
+    fn process(&self) {
+        let mut readbacks = self.readbacks.lock().unwrap();
+
+        let (mapped, pending) = readbacks
+            .drain(..)
+            .partition::<Vec<_>, _>(|r| r.mapped.load(Ordering::Acquire));
+
+        *readbacks = pending;
+        drop(readbacks);
+
+        for readback in mapped {
+            let image = {
+                let data = readback.buffer.slice(..).get_mapped_range();
+
+                readback::unpad(&data, readback.width, readback.height, readback.format)
+            };
+
+            readback.buffer.unmap();
+
+            // `capture` only copies readable formats
+            let Some(image) = image else {
+                continue;
+            };
+
+            for path in readback.paths {
+                let _ = self.writer.send(Job::Write(image.clone(), path));
+            }
+        }
+    }
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_capture_headless() {
+    use super::{test_backend, Renderer};
+
+    let Some(backend) = test_backend(40, 30) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+
+    let directory = std::env::temp_dir().join(format!("vengine_capture_{}", std::process::id()));
+    let screenshot = directory.join("screenshot.png");
+
+    renderer.capture().start_recording(&directory).unwrap();
+    renderer.capture().screenshot(&screenshot);
+
+    for _ in 0..2 {
+        let frame = renderer.start_frame().unwrap();
+        renderer.finish_frame(frame);
+    }
+
+    renderer.capture().stop_recording();
+    renderer
+        .capture()
+        .flush(renderer.backend().device())
+        .unwrap();
+
+    // Frames mapped during `poll` were written by the thread before `flush` returned
+    for path in [&screenshot, &directory.join("frame_000001.png")] {
+        let image = Image::load_png(path).unwrap();
+        assert_eq!((image.width, image.height), (40, 30));
+    }
+
+    assert!(!directory.join("frame_000002.png").exists());
+
+    // Writes into a missing directory fail on the thread and surface in `flush`
+    let missing = directory.join("missing").join("screenshot.png");
+    renderer.capture().screenshot(&missing);
+
+    let frame = renderer.start_frame().unwrap();
+    renderer.finish_frame(frame);
+
+    assert!(matches!(
+        renderer.capture().flush(renderer.backend().device()),
+        Err(CaptureError::Write { path, .. }) if path == missing
+    ));
+    assert!(renderer
+        .capture()
+        .flush(renderer.backend().device())
+        .is_ok());
+
+    // The HDR targets can't be encoded as PNG
+    renderer.capture().screenshot(&screenshot);
+    let hdr = renderer.hdr_textures().lock().unwrap()[0].texture.clone();
+    assert!(matches!(
+        renderer.capture().capture(
+            renderer.backend().device(),
+            renderer.backend().queue(),
+            &hdr
+        ),
+        Err(CaptureError::UnsupportedFormat(_))
+    ));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -1,5 +1,11 @@
-use super::{backend::Backend, camera::Camera};
+use super::{backend::Backend, camera::Camera, registry::PipelineRegistry};
 
 pub trait Configuration {
-    fn initialize(&mut self, backend: &Backend<'_>, camera: &Camera);
+    /// Registers the pipelines the app starts with, more can be added at runtime
+    fn initialize(&mut self, pipelines: &PipelineRegistry, backend: &Backend, camera: &Camera);
+}
+
+/// Starts without pipelines, register them later with `Renderer::register_pipeline`
+impl Configuration for () {
+    fn initialize(&mut self, _pipelines: &PipelineRegistry, _backend: &Backend, _camera: &Camera) {}
 }
@@ -1,48 +1,231 @@
 use super::{
-    configuration::Configuration, pass::RenderPass, pipeline::GetPipeline, size::Size, Renderer,
+    antialiasing::AntiAliasing,
+    backend::Backend,
+    configuration::Configuration,
+    pass::{MissingPipeline, RenderPass},
+    size::Size,
+    texture::Texture,
+    Renderer,
 };
-use std::sync::Mutex;
-use wgpu::{CommandBuffer, CommandEncoder, SurfaceTexture};
+use ahash::HashMap;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Mutex,
+};
+use wgpu::{CommandBuffer, CommandEncoder, LoadOp, SurfaceTexture, TextureView};
+
+/// Final image of a frame, a swapchain texture or the offscreen target of a headless backend
+pub enum FrameOutput {
+    Surface(SurfaceTexture),
+    Offscreen(Texture),
+}
+
+impl FrameOutput {
+    pub fn texture(&self) -> &wgpu::Texture {
+        match self {
+            FrameOutput::Surface(output) => &output.texture,
+            FrameOutput::Offscreen(output) => &output.texture,
+        }
+    }
+
+    /// Presents swapchain textures, offscreen targets are kept for readback
+    pub fn present(self) {
+        if let FrameOutput::Surface(output) = self {
+            output.present();
+        }
+    }
+}
+
+/// Depth, MSAA and HDR targets scene passes draw into, the renderer's or those of a `Viewport`
+#[derive(Clone)]
+pub struct FrameTargets {
+    pub depth: Texture,
+    /// Multisampled color target, resolved into the latest HDR texture after every scene pass
+    pub msaa: Option<Texture>,
+    pub hdr: [Texture; 2],
+    pub antialiasing: AntiAliasing,
+}
+
+impl FrameTargets {
+    pub fn new(backend: &Backend, width: u32, height: u32, antialiasing: AntiAliasing) -> Self {
+        let sample_count = antialiasing.sample_count();
+
+        let depth = Texture::create_depth_target(
+            backend.device(),
+            width,
+            height,
+            Texture::DEPTH_FORMAT,
+            sample_count,
+            "vengine::depth_texture",
+        );
+
+        let msaa = (sample_count > 1).then(|| {
+            Texture::create_color_texture(
+                backend.device(),
+                width,
+                height,
+                Texture::HDR_FORMAT,
+                sample_count,
+                "vengine::msaa_texture",
+            )
+        });
+
+        let hdr = ["vengine::hdr_texture_0", "vengine::hdr_texture_1"].map(|label| {
+            Texture::create_color_texture(
+                backend.device(),
+                width,
+                height,
+                Texture::HDR_FORMAT,
+                1,
+                label,
+            )
+        });
+
+        Self {
+            depth,
+            msaa,
+            hdr,
+            antialiasing,
+        }
+    }
+}
 
 pub struct Frame<'a, C: Configuration> {
-    renderer: &'a Renderer<'a, C>,
-    output: SurfaceTexture,
+    renderer: &'a Renderer<C>,
+    output: FrameOutput,
+    output_view: TextureView,
+    depth_view: TextureView,
+    msaa_view: Option<TextureView>,
+    /// Intermediate HDR targets, `current` holds the latest scene image
+    hdr: [Texture; 2],
+    current: AtomicUsize,
+    antialiasing: AntiAliasing,
+    sample_count: u32,
     encoders: Mutex<Vec<CommandEncoder>>,
+    index: u64,
+    /// Transient textures of the `RenderGraph` executing this frame
+    transients: Mutex<HashMap<&'static str, Texture>>,
     size: Size,
+    /// Pass names timed on the GPU, a begin and end timestamp each and whether it ended
+    timestamps: Mutex<Vec<(&'static str, bool)>>,
+    color_cleared: AtomicBool,
+    depth_cleared: AtomicBool,
+    resolved: AtomicBool,
 }
 
 impl<'a, C: Configuration> Frame<'a, C> {
-    pub fn new(renderer: &'a Renderer<C>, output: SurfaceTexture) -> Self {
-        let size = renderer.size();
+    pub fn new(renderer: &'a Renderer<C>, output: FrameOutput) -> Self {
+        let targets = FrameTargets {
+            depth: renderer.depth_texture().lock().unwrap().clone(),
+            msaa: renderer.msaa_texture().lock().unwrap().clone(),
+            hdr: renderer.hdr_textures().lock().unwrap().clone(),
+            antialiasing: renderer.antialiasing(),
+        };
+
+        Self::with_targets(renderer, output, &targets, renderer.size())
+    }
+
+    /// Frame drawing into `targets` of `size` instead of the renderer's, e.g. for a `Viewport`
+    pub fn with_targets(
+        renderer: &'a Renderer<C>,
+        output: FrameOutput,
+        targets: &FrameTargets,
+        size: Size,
+    ) -> Self {
+        let output_view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
 
         Self {
             renderer,
             output,
+            output_view,
+            depth_view: targets.depth.view.clone(),
+            msaa_view: targets.msaa.as_ref().map(|t| t.view.clone()),
+            sample_count: targets
+                .msaa
+                .as_ref()
+                .map_or(1, |t| t.texture.sample_count()),
+            hdr: targets.hdr.clone(),
+            current: AtomicUsize::new(0),
+            antialiasing: targets.antialiasing,
             encoders: Mutex::new(Vec::with_capacity(32)),
+            index: renderer.frame_index(),
+            transients: Mutex::new(HashMap::default()),
             size,
+            timestamps: Mutex::new(Vec::new()),
+            color_cleared: AtomicBool::new(false),
+            depth_cleared: AtomicBool::new(false),
+            resolved: AtomicBool::new(false),
         }
     }
 
-    pub fn start_render_pass<T>(&self) -> T
+    /// Starts a pass, timed on the GPU until `finish_render_pass` while the profiler is enabled
+    pub fn start_render_pass<T>(&self) -> Result<T, MissingPipeline>
     where
         T: RenderPass,
-        C: GetPipeline<T::RequiredPipeline>,
     {
-        T::start(self)
+        if !self.renderer.pipelines().contains::<T::RequiredPipeline>() {
+            return Err(MissingPipeline {
+                pass: pass_name::<T>(),
+                pipeline: pass_name::<T::RequiredPipeline>(),
+            });
+        }
+
+        self.begin_timestamp(pass_name::<T>());
+        Ok(T::start(self))
     }
     pub fn finish_render_pass<T>(&self, pass: T)
     where
         T: RenderPass,
-        C: GetPipeline<T::RequiredPipeline>,
     {
         pass.finish(self);
+        self.end_timestamp(pass_name::<T>());
+    }
+
+    fn begin_timestamp(&self, name: &'static str) {
+        let profiler = self.renderer.profiler();
+
+        if !profiler.is_enabled() {
+            return;
+        }
+
+        let mut timestamps = self.timestamps.lock().unwrap();
+        let query = timestamps.len() as u32 * 2;
+
+        if let Some(encoder) = profiler.timestamp(self.renderer.backend().device(), query) {
+            self.push_encoder(encoder);
+            timestamps.push((name, false));
+        }
+    }
+
+    fn end_timestamp(&self, name: &'static str) {
+        let mut timestamps = self.timestamps.lock().unwrap();
+
+        let Some(index) = timestamps
+            .iter()
+            .rposition(|(n, ended)| *n == name && !ended)
+        else {
+            return;
+        };
+
+        let query = index as u32 * 2 + 1;
+
+        if let Some(encoder) = self
+            .renderer
+            .profiler()
+            .timestamp(self.renderer.backend().device(), query)
+        {
+            self.push_encoder(encoder);
+            timestamps[index].1 = true;
+        }
     }
 
     pub fn renderer(&self) -> &Renderer<C> {
         self.renderer
     }
 
-    pub fn output(&self) -> &SurfaceTexture {
+    pub fn output(&self) -> &FrameOutput {
         &self.output
     }
 
@@ -50,13 +233,145 @@ impl<'a, C: Configuration> Frame<'a, C> {
         self.size
     }
 
+    /// Unique per frame, used to recycle per-frame buffers
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// View of the final image, UI and other overlays draw here
+    pub fn output_view(&self) -> &TextureView {
+        &self.output_view
+    }
+
+    pub fn depth_view(&self) -> &TextureView {
+        &self.depth_view
+    }
+
+    /// Sample count scene pipelines have to match
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// HDR texture holding the latest scene image
+    pub fn hdr_texture(&self) -> &Texture {
+        &self.hdr[self.current.load(Ordering::Relaxed)]
+    }
+
+    /// Texture of a `Resource::Transient` declared by the executing `RenderGraph`
+    pub fn transient(&self, name: &str) -> Option<Texture> {
+        self.transients.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn set_transients(&self, transients: HashMap<&'static str, Texture>) {
+        *self.transients.lock().unwrap() = transients;
+    }
+
+    /// Color attachment and resolve target for scene passes
+    pub fn color_target(&self) -> (&TextureView, Option<&TextureView>) {
+        let hdr = &self.hdr_texture().view;
+
+        match &self.msaa_view {
+            Some(msaa) => (msaa, Some(hdr)),
+            None => (hdr, None),
+        }
+    }
+
+    /// Source and target of the next post-process step, the target becomes the latest image
+    pub fn post_process_targets(&self) -> (&Texture, &Texture) {
+        let source = self.current.fetch_xor(1, Ordering::Relaxed);
+
+        (&self.hdr[source], &self.hdr[source ^ 1])
+    }
+
+    /// Copies the HDR scene into the frame output, called before the UI is drawn
+    /// Scene and post-process passes started afterwards are not visible
+    pub fn resolve(&self) {
+        if self.resolved.swap(true, Ordering::Relaxed)
+            || !self.color_cleared.load(Ordering::Relaxed)
+        {
+            return;
+        }
+
+        let Some(pipeline) = self.renderer.resolve_pipeline() else {
+            return;
+        };
+
+        let device = self.renderer.backend().device();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("vengine::resolve_encoder"),
+        });
+
+        pipeline.render(
+            device,
+            &mut encoder,
+            self.hdr_texture(),
+            &self.output_view,
+            self.antialiasing,
+        );
+
+        self.push_encoder(encoder);
+    }
+
+    /// Clears the color target in the first pass of the frame and loads it in every later pass
+    pub fn color_load_op(&self) -> LoadOp<wgpu::Color> {
+        if self.color_cleared.swap(true, Ordering::Relaxed) {
+            LoadOp::Load
+        } else {
+            LoadOp::Clear(self.renderer.camera().sky().clear_color())
+        }
+    }
+
+    /// Clears the depth target in the first pass of the frame and loads it in every later pass
+    pub fn depth_load_op(&self) -> LoadOp<f32> {
+        if self.depth_cleared.swap(true, Ordering::Relaxed) {
+            LoadOp::Load
+        } else {
+            LoadOp::Clear(1.0)
+        }
+    }
+
     pub fn push_encoder(&self, encoder: CommandEncoder) {
         let mut lock = self.encoders.lock().unwrap();
 
         lock.push(encoder);
     }
 
-    pub fn finish(self) -> SurfaceTexture {
+    pub fn finish(self) -> FrameOutput {
+        self.resolve();
+
+        // Passes that were never finished are timed up to here
+        let open = self
+            .timestamps
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, ended)| !ended)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+
+        for name in open {
+            self.end_timestamp(name);
+        }
+
+        let names = std::mem::take(&mut *self.timestamps.lock().unwrap())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        let profiler = self.renderer.profiler();
+
+        let readback = (!names.is_empty())
+            .then(|| {
+                profiler
+                    .resolve_timestamps(self.renderer.backend().device(), names.len() as u32 * 2)
+            })
+            .flatten()
+            .map(|(encoder, buffer)| {
+                self.push_encoder(encoder);
+                buffer
+            });
+
         let buffers = self
             .encoders
             .into_inner()
@@ -67,6 +382,89 @@ impl<'a, C: Configuration> Frame<'a, C> {
 
         self.renderer.backend().queue().submit(buffers);
 
+        if let Some(buffer) = readback {
+            profiler.read_timestamps(self.index, names, buffer);
+        }
+
         self.output
     }
 }
+
+/// Type name of a pass or pipeline without its module path
+fn pass_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let end = name.find('<').unwrap_or(name.len());
+    let start = name[..end].rfind("::").map_or(0, |i| i + 2);
+
+    &name[start..]
+}
+
+#[test]
+fn test_frame_with_targets() {
+    use super::{readback, test_backend};
+    use crate::engine::{
+        post::rendering::{pass::ToneMappingPass, pipeline::PostProcessPipeline},
+        sky::rendering::{pass::SkyPass, pipeline::SkyPipeline},
+        voxel::rendering::{pass::VoxelPass, pipeline::VoxelPipeline},
+    };
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.register_pipeline::<SkyPipeline>();
+    renderer.register_pipeline::<VoxelPipeline>();
+    renderer.register_pipeline::<PostProcessPipeline>();
+
+    // Another size than the renderer's, like a second window
+    let size = Size {
+        width: 12,
+        height: 6,
+        pixels_per_point: 1.0,
+    };
+
+    let render = |antialiasing: AntiAliasing| {
+        let targets = FrameTargets::new(renderer.backend(), 12, 6, antialiasing);
+        let output = Texture::create_color_texture(
+            renderer.backend().device(),
+            12,
+            6,
+            *renderer.backend().surface_format(),
+            1,
+            "vengine::test_output",
+        );
+
+        let frame = Frame::with_targets(
+            &renderer,
+            FrameOutput::Offscreen(output.clone()),
+            &targets,
+            size,
+        );
+        assert_eq!(frame.sample_count(), antialiasing.sample_count());
+
+        let sky: SkyPass = frame.start_render_pass().unwrap();
+        frame.finish_render_pass(sky);
+        let voxels: VoxelPass = frame.start_render_pass().unwrap();
+        frame.finish_render_pass(voxels);
+        let tone_mapping: ToneMappingPass = frame.start_render_pass().unwrap();
+        frame.finish_render_pass(tone_mapping);
+        frame.finish();
+
+        readback::read_texture(
+            renderer.backend().device(),
+            renderer.backend().queue(),
+            &output.texture,
+        )
+        .unwrap()
+    };
+
+    let image = render(AntiAliasing::None);
+    assert_eq!((image.width, image.height), (12, 6));
+    assert!(image.pixels.chunks_exact(4).any(|p| p[..3] != [0, 0, 0]));
+
+    // Multisampled scene pipelines match multisampled targets
+    if renderer.backend().supports_sample_count(4) {
+        render(AntiAliasing::Msaa4x);
+    }
+}
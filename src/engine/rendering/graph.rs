@@ -0,0 +1,558 @@
+use super::{
+    configuration::Configuration,
+    frame::Frame,
+    pass::{MissingPipeline, RenderPass},
+    size::Size,
+    texture::Texture,
+    Renderer,
+};
+use crate::engine::{
+    post::rendering::{
+        pass::{BloomPass, ColorGradingPass, GammaPass, ToneMappingPass},
+        pipeline::PostProcessPipeline,
+    },
+    sky::rendering::{pass::SkyPass, pipeline::SkyPipeline},
+};
+use ahash::HashMap;
+use std::{fmt::Display, sync::Mutex};
+use wgpu::{Device, TextureFormat};
+
+/// Something a graph pass reads or writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// Final image of the frame, see `Frame::output_view`
+    Output,
+    /// Depth target shared by the scene passes
+    Depth,
+    /// Intermediate HDR targets, see `Frame::color_target`
+    Hdr,
+    /// Image of the post-process chain, its passes read the scene from `Hdr`
+    PostProcess,
+    /// Texture declared with `RenderGraph::transient`, allocated by the graph
+    Transient(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransientSize {
+    /// Follows the frame size
+    Frame,
+    /// Frame size multiplied by a factor, e.g. `0.5` for half resolution
+    Scaled(f32),
+    Fixed(u32, u32),
+}
+
+impl TransientSize {
+    fn resolve(&self, size: Size) -> (u32, u32) {
+        match *self {
+            TransientSize::Frame => (size.width, size.height),
+            TransientSize::Scaled(factor) => (
+                ((size.width as f32 * factor) as u32).max(1),
+                ((size.height as f32 * factor) as u32).max(1),
+            ),
+            TransientSize::Fixed(width, height) => (width, height),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransientDescriptor {
+    pub size: TransientSize,
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// Passes that depend on each other
+    Cycle(Vec<&'static str>),
+    UnknownTransient(&'static str),
+    MissingPipeline(MissingPipeline),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Cycle(passes) => {
+                write!(f, "render graph has a cycle between {}", passes.join(", "))
+            }
+            GraphError::UnknownTransient(name) => write!(f, "unknown transient '{}'", name),
+            GraphError::MissingPipeline(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl From<MissingPipeline> for GraphError {
+    fn from(e: MissingPipeline) -> Self {
+        GraphError::MissingPipeline(e)
+    }
+}
+
+type Execute<'f, C> = Box<dyn FnOnce(&Frame<C>) -> Result<(), GraphError> + 'f>;
+
+struct Node<'f, C: Configuration> {
+    name: &'static str,
+    reads: Vec<Resource>,
+    writes: Vec<Resource>,
+    execute: Execute<'f, C>,
+}
+
+/// Passes of one frame with the resources they access
+///
+/// Writers of a resource run in the order they were added, passes that only read it run after
+/// all of its writers. Passes writing only transients nobody reads are culled.
+pub struct RenderGraph<'f, C: Configuration> {
+    nodes: Vec<Node<'f, C>>,
+    transients: Vec<(&'static str, TransientDescriptor)>,
+}
+
+impl<'f, C: Configuration> RenderGraph<'f, C> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            transients: Vec::new(),
+        }
+    }
+
+    /// Graph with the built-in passes of the registered pipelines
+    ///
+    /// The sky is drawn first, the post-process chain and the resolve into the output run after
+    /// every pass writing `Hdr` or `Depth`. Passes writing `Output` afterwards, like the UI, draw
+    /// on top of the resolved image.
+    pub fn builtin(renderer: &Renderer<C>) -> Self {
+        let mut graph = Self::new();
+
+        if renderer.pipelines().contains::<SkyPipeline>() {
+            graph.add_render_pass::<SkyPass, _>(
+                "sky",
+                &[],
+                &[Resource::Hdr, Resource::Depth],
+                |_| {},
+            );
+        }
+
+        if renderer.pipelines().contains::<PostProcessPipeline>() {
+            let reads = &[Resource::Hdr];
+            let writes = &[Resource::PostProcess];
+
+            graph.add_render_pass::<BloomPass, _>("bloom", reads, writes, |_| {});
+            graph.add_render_pass::<ToneMappingPass, _>("tone_mapping", reads, writes, |_| {});
+            graph.add_render_pass::<ColorGradingPass, _>("color_grading", reads, writes, |_| {});
+            graph.add_render_pass::<GammaPass, _>("gamma", reads, writes, |_| {});
+        }
+
+        graph.add_pass(
+            "resolve",
+            &[Resource::Hdr, Resource::PostProcess],
+            &[Resource::Output],
+            |frame| frame.resolve(),
+        );
+
+        graph
+    }
+
+    /// Declares a texture that only lives for the frame, like a shadow map
+    pub fn transient(&mut self, name: &'static str, descriptor: TransientDescriptor) -> Resource {
+        self.transients.retain(|(n, _)| *n != name);
+        self.transients.push((name, descriptor));
+
+        Resource::Transient(name)
+    }
+
+    pub fn add_pass<F: FnOnce(&Frame<C>) + 'f>(
+        &mut self,
+        name: &'static str,
+        reads: &[Resource],
+        writes: &[Resource],
+        execute: F,
+    ) {
+        self.push(name, reads, writes, move |frame| {
+            execute(frame);
+            Ok(())
+        });
+    }
+
+    /// Adds a `RenderPass`, `record` runs between its start and finish
+    /// Executing fails with `GraphError::MissingPipeline` while its pipeline isn't registered
+    pub fn add_render_pass<T, F>(
+        &mut self,
+        name: &'static str,
+        reads: &[Resource],
+        writes: &[Resource],
+        record: F,
+    ) where
+        T: RenderPass,
+        F: FnOnce(&mut T) + 'f,
+    {
+        self.push(name, reads, writes, move |frame| {
+            let mut pass = frame.start_render_pass::<T>()?;
+            record(&mut pass);
+            frame.finish_render_pass(pass);
+
+            Ok(())
+        });
+    }
+
+    fn push<F: FnOnce(&Frame<C>) -> Result<(), GraphError> + 'f>(
+        &mut self,
+        name: &'static str,
+        reads: &[Resource],
+        writes: &[Resource],
+        execute: F,
+    ) {
+        self.nodes.push(Node {
+            name,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            execute: Box::new(execute),
+        });
+    }
+
+    /// Pass names in execution order, without culled passes
+    pub fn order(&self) -> Result<Vec<&'static str>, GraphError> {
+        Ok(self
+            .schedule()?
+            .into_iter()
+            .map(|n| self.nodes[n].name)
+            .collect())
+    }
+
+    fn schedule(&self) -> Result<Vec<usize>, GraphError> {
+        for node in &self.nodes {
+            for resource in node.reads.iter().chain(&node.writes) {
+                if let Resource::Transient(name) = resource {
+                    if !self.transients.iter().any(|(n, _)| n == name) {
+                        return Err(GraphError::UnknownTransient(name));
+                    }
+                }
+            }
+        }
+
+        let live = self.live();
+
+        // Edges from the last writer of a resource to its next writer and to all its readers
+        let mut dependencies = vec![Vec::new(); self.nodes.len()];
+        let mut writers: HashMap<Resource, Vec<usize>> = HashMap::default();
+
+        for (n, node) in self.nodes.iter().enumerate().filter(|(n, _)| live[*n]) {
+            for resource in &node.writes {
+                let chain = writers.entry(*resource).or_default();
+
+                if let Some(last) = chain.last() {
+                    dependencies[n].push(*last);
+                }
+
+                chain.push(n);
+            }
+        }
+
+        for (n, node) in self.nodes.iter().enumerate().filter(|(n, _)| live[*n]) {
+            for resource in node.reads.iter().filter(|r| !node.writes.contains(r)) {
+                if let Some(last) = writers.get(resource).and_then(|w| w.last()) {
+                    dependencies[n].push(*last);
+                }
+            }
+        }
+
+        // Kahn's algorithm, ties are broken by insertion order
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut scheduled = vec![false; self.nodes.len()];
+
+        while order.len() < live.iter().filter(|l| **l).count() {
+            let next = (0..self.nodes.len()).find(|n| {
+                live[*n] && !scheduled[*n] && dependencies[*n].iter().all(|d| scheduled[*d])
+            });
+
+            match next {
+                Some(n) => {
+                    scheduled[n] = true;
+                    order.push(n);
+                }
+                None => {
+                    return Err(GraphError::Cycle(
+                        (0..self.nodes.len())
+                            .filter(|n| live[*n] && !scheduled[*n])
+                            .map(|n| self.nodes[n].name)
+                            .collect(),
+                    ));
+                }
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Passes that write frame targets or transients read by other live passes
+    fn live(&self) -> Vec<bool> {
+        let mut live = self
+            .nodes
+            .iter()
+            .map(|node| {
+                node.writes.is_empty()
+                    || node
+                        .writes
+                        .iter()
+                        .any(|r| !matches!(r, Resource::Transient(_)))
+            })
+            .collect::<Vec<bool>>();
+
+        loop {
+            let mut changed = false;
+
+            for n in 0..self.nodes.len() {
+                if live[n] {
+                    continue;
+                }
+
+                let read = self.nodes.iter().enumerate().any(|(m, other)| {
+                    live[m]
+                        && m != n
+                        && self.nodes[n].writes.iter().any(|w| other.reads.contains(w))
+                });
+
+                if read {
+                    live[n] = true;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return live;
+            }
+        }
+    }
+
+    /// Allocates transients and runs the passes in dependency order, stopping at the first failure
+    pub fn execute(self, frame: &Frame<C>) -> Result<(), GraphError> {
+        let order = self.schedule()?;
+
+        let transients = frame.renderer().transients().acquire(
+            frame.renderer().backend().device(),
+            frame.size(),
+            &self.transients,
+        );
+        frame.set_transients(transients);
+
+        let mut nodes = self.nodes.into_iter().map(Some).collect::<Vec<_>>();
+
+        for n in order {
+            let node = nodes[n].take().unwrap();
+
+            (node.execute)(frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: Configuration> Default for RenderGraph<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct PooledTexture {
+    descriptor: TransientDescriptor,
+    extent: (u32, u32),
+    texture: Texture,
+}
+
+/// Transient textures kept across frames, recreated when their descriptor or the frame size changes
+#[derive(Default)]
+pub struct TransientPool {
+    textures: Mutex<HashMap<&'static str, PooledTexture>>,
+}
+
+impl TransientPool {
+    /// Textures for `transients` at `size`, textures no longer declared are released
+    pub fn acquire(
+        &self,
+        device: &Device,
+        size: Size,
+        transients: &[(&'static str, TransientDescriptor)],
+    ) -> HashMap<&'static str, Texture> {
+        let mut textures = self.textures.lock().unwrap();
+
+        textures.retain(|name, _| transients.iter().any(|(n, _)| n == name));
+
+        transients
+            .iter()
+            .map(|(name, descriptor)| {
+                let extent = descriptor.size.resolve(size);
+
+                let entry = textures
+                    .get(name)
+                    .filter(|p| p.descriptor == *descriptor && p.extent == extent);
+
+                let texture = match entry {
+                    Some(pooled) => pooled.texture.clone(),
+                    None => {
+                        let create = if descriptor.format.is_depth_stencil_format() {
+                            Texture::create_depth_target
+                        } else {
+                            Texture::create_color_texture
+                        };
+
+                        let texture = create(
+                            device,
+                            extent.0,
+                            extent.1,
+                            descriptor.format,
+                            descriptor.sample_count,
+                            name,
+                        );
+
+                        textures.insert(
+                            name,
+                            PooledTexture {
+                                descriptor: *descriptor,
+                                extent,
+                                texture: texture.clone(),
+                            },
+                        );
+                        texture
+                    }
+                };
+
+                (*name, texture)
+            })
+            .collect()
+    }
+
+    /// Releases all textures
+    pub fn clear(&self) {
+        self.textures.lock().unwrap().clear();
+    }
+}
+
+#[test]
+fn test_graph_order() {
+    struct TestConfiguration;
+
+    impl Configuration for TestConfiguration {
+        fn initialize(
+            &mut self,
+            _pipelines: &super::registry::PipelineRegistry,
+            _backend: &super::backend::Backend,
+            _camera: &super::camera::Camera,
+        ) {
+        }
+    }
+
+    let mut graph = RenderGraph::<TestConfiguration>::new();
+
+    let shadow_map = graph.transient(
+        "shadow_map",
+        TransientDescriptor {
+            size: TransientSize::Fixed(1024, 1024),
+            format: Texture::DEPTH_FORMAT,
+            sample_count: 1,
+        },
+    );
+
+    let unused = graph.transient(
+        "unused",
+        TransientDescriptor {
+            size: TransientSize::Scaled(0.5),
+            format: Texture::HDR_FORMAT,
+            sample_count: 1,
+        },
+    );
+
+    graph.add_pass("ui", &[Resource::Hdr], &[Resource::Output], |_| {});
+    graph.add_pass("sky", &[], &[Resource::Hdr, Resource::Depth], |_| {});
+    graph.add_pass(
+        "voxel",
+        &[shadow_map],
+        &[Resource::Hdr, Resource::Depth],
+        |_| {},
+    );
+    graph.add_pass("shadow", &[], &[shadow_map], |_| {});
+    graph.add_pass("unused", &[], &[unused], |_| {});
+    graph.add_pass("tone_mapping", &[], &[Resource::Hdr], |_| {});
+
+    assert_eq!(
+        graph.order().unwrap(),
+        vec!["sky", "shadow", "voxel", "tone_mapping", "ui"]
+    );
+
+    let mut graph = RenderGraph::<TestConfiguration>::new();
+
+    graph.add_pass("missing", &[Resource::Transient("missing")], &[], |_| {});
+
+    assert_eq!(graph.order(), Err(GraphError::UnknownTransient("missing")));
+
+    let mut graph = RenderGraph::<TestConfiguration>::new();
+
+    graph.add_pass("a", &[Resource::Depth], &[Resource::Hdr], |_| {});
+    graph.add_pass("b", &[Resource::Hdr], &[Resource::Depth], |_| {});
+
+    assert_eq!(graph.order(), Err(GraphError::Cycle(vec!["a", "b"])));
+}
+
+#[test]
+fn test_builtin_graph() {
+    use super::test_backend;
+    use crate::engine::{
+        ecs::{systems::add_voxel_passes, World},
+        voxel::rendering::pipeline::VoxelPipeline,
+    };
+
+    let Some(backend) = test_backend(16, 16) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.register_pipeline::<SkyPipeline>();
+    renderer.register_pipeline::<VoxelPipeline>();
+    renderer.register_pipeline::<PostProcessPipeline>();
+
+    let world = World::new();
+
+    // Scene passes added after the built-in ones still run before post-processing
+    let mut graph = RenderGraph::builtin(&renderer);
+    add_voxel_passes(&world, &mut graph);
+    graph.add_pass("ui", &[], &[Resource::Output], |_| {});
+
+    assert_eq!(
+        graph.order().unwrap(),
+        vec![
+            "sky",
+            "voxel",
+            "translucent",
+            "bloom",
+            "tone_mapping",
+            "color_grading",
+            "gamma",
+            "resolve",
+            "ui"
+        ]
+    );
+
+    let frame = renderer.start_frame().unwrap();
+    graph.execute(&frame).unwrap();
+    renderer.finish_frame(frame);
+
+    // The sky reached the output through the post-process chain
+    let image = renderer.read_output().unwrap();
+    assert!(image.pixels.chunks_exact(4).any(|p| p[..3] != [0, 0, 0]));
+
+    // Depth transients can be attached as depth targets
+    let transients = renderer.transients().acquire(
+        renderer.backend().device(),
+        renderer.size(),
+        &[(
+            "shadow_map",
+            TransientDescriptor {
+                size: TransientSize::Fixed(8, 8),
+                format: Texture::DEPTH_FORMAT,
+                sample_count: 1,
+            },
+        )],
+    );
+    assert_eq!(
+        transients["shadow_map"].texture.usage(),
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+    );
+}
@@ -0,0 +1,205 @@
+use super::{
+    backend::Backend,
+    configuration::Configuration,
+    registry::PipelineRegistry,
+    shader::{ReloadShader, ShaderError},
+    Renderer,
+};
+use ahash::HashSet;
+use crossbeam::channel::{unbounded, Receiver};
+use egui::{Color32, Context, RichText, Window};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    any::type_name,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+type Reload = Box<dyn Fn(&PipelineRegistry, &Backend, &str) -> Result<(), ShaderError> + Send>;
+
+struct WatchedShader {
+    label: &'static str,
+    files: Vec<PathBuf>,
+    reload: Reload,
+}
+
+/// Dev mode that rebuilds pipelines when their WGSL files change on disk
+///
+/// Call `poll` once per frame and `render` inside a `UiPass` to see shader errors.
+pub struct ShaderReloader {
+    watcher: RecommendedWatcher,
+    changes: Receiver<PathBuf>,
+    directories: HashSet<PathBuf>,
+    shaders: Vec<WatchedShader>,
+    /// Latest error per shader label, cleared by a successful reload
+    errors: Mutex<Vec<ShaderError>>,
+}
+
+impl ShaderReloader {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, changes) = unbounded();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                if event.kind.is_modify() || event.kind.is_create() {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            watcher,
+            changes,
+            directories: HashSet::default(),
+            shaders: Vec::new(),
+            errors: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Watches the shader files of `T`, `label` names it in error reports
+    pub fn watch<T: ReloadShader + Send + Sync + 'static>(
+        &mut self,
+        label: &'static str,
+    ) -> notify::Result<()> {
+        let files = T::shader_files();
+
+        // Editors often replace files on save, so the directories are watched
+        for file in &files {
+            let directory = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+            if self.directories.insert(directory.clone()) {
+                self.watcher
+                    .watch(&directory, RecursiveMode::NonRecursive)?;
+            }
+        }
+
+        self.shaders.push(WatchedShader {
+            label,
+            files,
+            reload: Box::new(
+                move |pipelines, backend, source| match pipelines.get::<T>() {
+                    Some(pipeline) => pipeline.reload_shader(backend, source),
+                    None => Err(ShaderError {
+                        label: label.to_string(),
+                        message: format!("pipeline {} is not registered", type_name::<T>()),
+                    }),
+                },
+            ),
+        });
+
+        Ok(())
+    }
+
+    /// Reloads shaders whose files changed since the last call, returns whether any was swapped
+    pub fn poll<C: Configuration>(&self, renderer: &Renderer<C>) -> bool {
+        let changed = self.changes.try_iter().collect::<Vec<PathBuf>>();
+
+        if changed.is_empty() {
+            return false;
+        }
+
+        let mut reloaded = false;
+
+        for shader in &self.shaders {
+            if !shader
+                .files
+                .iter()
+                .any(|file| changed.iter().any(|c| same_file(c, file)))
+            {
+                continue;
+            }
+
+            let result = Self::reload(shader, renderer);
+
+            let mut errors = self.errors.lock().unwrap();
+            errors.retain(|e| e.label != shader.label);
+
+            match result {
+                Ok(()) => reloaded = true,
+                Err(mut e) => {
+                    e.label = shader.label.to_string();
+                    errors.push(e);
+                }
+            }
+        }
+
+        reloaded
+    }
+
+    /// Reads the files of `shader` and rebuilds its pipeline
+    fn reload<C: Configuration>(
+        shader: &WatchedShader,
+        renderer: &Renderer<C>,
+    ) -> Result<(), ShaderError> {
+        let source = shader
+            .files
+            .iter()
+            .map(std::fs::read_to_string)
+            .collect::<std::io::Result<String>>()
+            .map_err(|e| ShaderError {
+                label: shader.label.to_string(),
+                message: e.to_string(),
+            })?;
+
+        (shader.reload)(renderer.pipelines(), renderer.backend(), &source)
+    }
+
+    pub fn errors(&self) -> Vec<ShaderError> {
+        self.errors.lock().unwrap().clone()
+    }
+
+    /// Shows the current shader errors in an egui window
+    pub fn render(&self, context: &Context) {
+        let errors = self.errors.lock().unwrap();
+
+        if errors.is_empty() {
+            return;
+        }
+
+        Window::new("Shader errors")
+            .default_width(500.0)
+            .show(context, |ui| {
+                for error in errors.iter() {
+                    ui.label(RichText::new(&error.label).strong());
+                    ui.label(
+                        RichText::new(&error.message)
+                            .monospace()
+                            .color(Color32::LIGHT_RED),
+                    );
+                    ui.separator();
+                }
+            });
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+}
+
+#[test]
+fn test_unregistered_pipeline() {
+    use super::{resolve::ResolvePipeline, test_backend};
+    use crate::engine::sky::rendering::pipeline::SkyPipeline;
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+
+    let mut reloader = ShaderReloader::new().unwrap();
+    reloader.watch::<SkyPipeline>("sky").unwrap();
+    reloader.watch::<ResolvePipeline>("resolve").unwrap();
+
+    let error = ShaderReloader::reload(&reloader.shaders[0], &renderer).unwrap_err();
+    assert_eq!(error.label, "sky");
+    assert!(error.message.contains("not registered"));
+
+    assert!(ShaderReloader::reload(&reloader.shaders[1], &renderer).is_ok());
+}
@@ -1,42 +1,98 @@
+pub mod antialiasing;
 pub mod backend;
 pub mod camera;
+pub mod capture;
 pub mod configuration;
 pub mod frame;
+pub mod graph;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod options;
 pub mod pass;
 pub mod pipeline;
+pub mod readback;
+pub mod registry;
+pub mod resolve;
+pub mod shader;
 pub mod size;
 pub mod texture;
+pub mod viewport;
 
-use super::core::window::window::Window;
+use super::{core::window::window::Window, error::EngineError, profiler::Profiler};
+use antialiasing::AntiAliasing;
 use backend::Backend;
 use camera::Camera;
+use capture::Capture;
 use configuration::Configuration;
 use crossbeam::atomic::AtomicCell;
-use frame::Frame;
+use frame::{Frame, FrameOutput, FrameTargets};
+use graph::TransientPool;
+use options::PresentMode;
+use pipeline::Pipeline;
+use readback::Image;
+use registry::PipelineRegistry;
+use resolve::ResolvePipeline;
 use size::Size;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
 };
 use texture::Texture;
-use wgpu::SurfaceTexture;
+use viewport::Viewport;
+use wgpu::SurfaceConfiguration;
 
-pub struct Renderer<'a, C: Configuration> {
+pub struct Renderer<C: Configuration> {
     current_size: AtomicCell<Size>,
     new_size: AtomicCell<Size>,
     resized: AtomicBool,
+    /// Incremented by every `start_frame`
+    frame_index: AtomicU64,
     configuration: C,
+    pipelines: PipelineRegistry,
     camera: Camera,
     depth_texture: Mutex<Texture>,
-    backend: Backend<'a>,
+    /// Multisampled color target, only present with `AntiAliasing::Msaa4x`
+    msaa_texture: Mutex<Option<Texture>>,
+    /// Intermediate HDR color targets, post-processing ping-pongs between both
+    hdr_textures: Mutex<[Texture; 2]>,
+    /// Frame output of headless backends
+    offscreen_texture: Mutex<Option<Texture>>,
+    /// Textures of `RenderGraph` transients, kept alive across frames
+    transients: TransientPool,
+    antialiasing: AtomicCell<AntiAliasing>,
+    capture: Capture,
+    profiler: Profiler,
+    backend: Backend,
 }
 
-impl<'a, C: Configuration> Renderer<'a, C> {
-    pub fn new(mut configuration: C, backend: Backend<'a>) -> Self {
+impl<C: Configuration> Renderer<C> {
+    /// Attempts to acquire a frame before `start_frame` gives up
+    const SURFACE_RETRIES: usize = 3;
+
+    /// Fails when the device is lost or runs out of memory while the render targets and the
+    /// pipelines of `configuration` are created
+    pub fn new(mut configuration: C, backend: Backend) -> Result<Self, EngineError> {
+        if let Some(reason) = backend.device_lost() {
+            return Err(EngineError::DeviceLost(reason));
+        }
+
+        backend
+            .device()
+            .push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+
+        let (width, height) = {
+            let config = backend.surface_configuration().lock().unwrap();
+            (config.width, config.height)
+        };
+
         let size = Size {
-            width: backend.window().dimension().0,
-            height: backend.window().dimension().1,
-            pixels_per_point: backend.window().window().scale_factor() as f32,
+            width,
+            height,
+            pixels_per_point: if backend.is_headless() {
+                1.0
+            } else {
+                backend.window().window().scale_factor() as f32
+            },
         };
 
         let camera = Camera::new(
@@ -45,24 +101,43 @@ impl<'a, C: Configuration> Renderer<'a, C> {
             backend.queue().clone(),
         );
 
-        configuration.initialize(&backend, &camera);
+        let pipelines = PipelineRegistry::new();
+        pipelines.register::<ResolvePipeline>(&backend, &camera);
+
+        configuration.initialize(&pipelines, &backend, &camera);
 
         let lock = backend.surface_configuration().lock().unwrap();
 
-        let depth_texture =
-            Texture::create_depth_texture(backend.device(), &lock, "engine::depth_texture");
+        let (depth_texture, msaa_texture, hdr_textures, offscreen_texture) =
+            Self::create_targets(&backend, &lock, AntiAliasing::None);
 
         drop(lock);
 
-        Self {
+        if pollster::block_on(backend.device().pop_error_scope()).is_some() {
+            return Err(EngineError::OutOfMemory);
+        }
+
+        let profiler = Profiler::new();
+        profiler.set_device(backend.device(), backend.queue());
+
+        Ok(Self {
             backend,
             current_size: AtomicCell::new(size),
             new_size: AtomicCell::new(size),
             camera,
             resized: AtomicBool::new(false),
+            frame_index: AtomicU64::new(0),
             depth_texture: Mutex::new(depth_texture),
+            msaa_texture: Mutex::new(msaa_texture),
+            hdr_textures: Mutex::new(hdr_textures),
+            offscreen_texture: Mutex::new(offscreen_texture),
+            transients: TransientPool::default(),
+            antialiasing: AtomicCell::new(AntiAliasing::None),
+            capture: Capture::new(),
+            profiler,
             configuration,
-        }
+            pipelines,
+        })
     }
 
     pub fn backend(&self) -> &Backend {
@@ -77,37 +152,173 @@ impl<'a, C: Configuration> Renderer<'a, C> {
         self.current_size.load()
     }
 
+    /// Index of the current frame
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index.load(Ordering::Relaxed)
+    }
+
     pub fn depth_texture(&self) -> &Mutex<Texture> {
         &self.depth_texture
     }
 
-    pub fn start_frame(&self) -> Frame<C> {
-        let output: SurfaceTexture;
+    pub fn msaa_texture(&self) -> &Mutex<Option<Texture>> {
+        &self.msaa_texture
+    }
+
+    pub fn hdr_textures(&self) -> &Mutex<[Texture; 2]> {
+        &self.hdr_textures
+    }
+
+    pub fn transients(&self) -> &TransientPool {
+        &self.transients
+    }
+
+    /// Removing it from the registry leaves the frame output black
+    pub fn resolve_pipeline(&self) -> Option<Arc<ResolvePipeline>> {
+        self.pipelines.get()
+    }
+
+    /// Screenshots and image sequences of finished frames
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    /// CPU scopes, GPU pass timings and counters of the recent frames
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    pub fn antialiasing(&self) -> AntiAliasing {
+        self.antialiasing.load()
+    }
+
+    /// Switches anti-aliasing, render targets are recreated before the next frame
+    /// Returns `false` and keeps the current mode if scene pipelines lack its sample count
+    pub fn set_antialiasing(&self, antialiasing: AntiAliasing) -> bool {
+        if !pipeline::supports_sample_count(&self.backend, antialiasing.sample_count()) {
+            return false;
+        }
+
+        self.antialiasing.store(antialiasing);
+        self.resized.store(true, Ordering::Relaxed);
+
+        true
+    }
+
+    /// Switches vsync, mailbox or immediate presentation, returns the mode the surface uses
+    pub fn set_present_mode(&self, present_mode: PresentMode) -> wgpu::PresentMode {
+        self.backend.set_present_mode(present_mode)
+    }
+
+    /// Acquires the next frame, reconfiguring the surface when it is outdated or lost
+    ///
+    /// `EngineError::SurfaceTimeout` means the frame should be skipped,
+    /// `EngineError::DeviceLost` requires `recover` before rendering again.
+    pub fn start_frame(&self) -> Result<Frame<'_, C>, EngineError> {
+        if let Some(reason) = self.backend.device_lost() {
+            return Err(EngineError::DeviceLost(reason));
+        }
 
         self.handle_resize();
 
-        loop {
-            match self.backend().surface().get_current_texture() {
-                Ok(o) => {
-                    output = o;
-                    break;
-                }
-                Err(wgpu::SurfaceError::Outdated) => {
+        self.frame_index.fetch_add(1, Ordering::Relaxed);
+
+        let Some(surface) = self.backend().surface() else {
+            let output = self.offscreen_texture.lock().unwrap().clone().unwrap();
+
+            return Ok(Frame::new(self, FrameOutput::Offscreen(output)));
+        };
+
+        let mut error = EngineError::SurfaceLost;
+
+        for _ in 0..Self::SURFACE_RETRIES {
+            match surface.get_current_texture() {
+                Ok(output) => return Ok(Frame::new(self, FrameOutput::Surface(output))),
+                Err(wgpu::SurfaceError::Timeout) => error = EngineError::SurfaceTimeout,
+                Err(wgpu::SurfaceError::OutOfMemory) => return Err(EngineError::OutOfMemory),
+                Err(
+                    wgpu::SurfaceError::Outdated
+                    | wgpu::SurfaceError::Lost
+                    | wgpu::SurfaceError::Other,
+                ) => {
+                    if let Some(reason) = self.backend.device_lost() {
+                        return Err(EngineError::DeviceLost(reason));
+                    }
+
+                    error = EngineError::SurfaceLost;
                     self.reconfigure_surface();
                 }
-                Err(e) => {
-                    panic!("{}", e)
-                }
             };
         }
 
-        Frame::new(self, output)
+        Err(error)
     }
 
+    /// Submits and presents the frame, failed captures are reported by `Capture::flush`
     pub fn finish_frame(&self, frame: Frame<C>) {
+        let index = frame.index();
         let output = frame.finish();
 
+        if let Err(e) = self.capture.capture(
+            self.backend().device(),
+            self.backend().queue(),
+            output.texture(),
+        ) {
+            self.capture.report(e);
+        }
+
         output.present();
+
+        self.capture.poll(self.backend().device());
+        self.profiler.end_frame(index);
+    }
+
+    /// Reads the last finished frame back, `None` unless the backend is headless
+    pub fn read_output(&self) -> Option<Image> {
+        let lock = self.offscreen_texture.lock().unwrap();
+
+        lock.as_ref().and_then(|output| {
+            readback::read_texture(
+                self.backend().device(),
+                self.backend().queue(),
+                &output.texture,
+            )
+        })
+    }
+
+    /// Recreates the device and everything created with it after `EngineError::DeviceLost`
+    ///
+    /// Registered pipelines, including those of the configuration, are initialized again once,
+    /// inserted ones are removed. Data uploaded to pipelines and buffers owned by the app have to be
+    /// recreated by the caller, viewports reconfigured with `Viewport::reconfigure`.
+    pub async fn recover(&mut self) -> Result<(), EngineError> {
+        self.backend.recreate_device().await?;
+
+        self.camera = self
+            .camera
+            .recreate(self.backend.device(), self.backend.queue().clone());
+
+        // The configuration registered its pipelines, initializing it again would build them twice
+        self.pipelines.recreate(&self.backend, &self.camera);
+
+        self.transients.clear();
+        self.capture.discard();
+        self.profiler
+            .set_device(self.backend.device(), self.backend.queue());
+
+        let lock = self.backend.surface_configuration().lock().unwrap();
+
+        let (depth_texture, msaa_texture, hdr_textures, offscreen_texture) =
+            Self::create_targets(&self.backend, &lock, self.antialiasing.load());
+
+        drop(lock);
+
+        *self.depth_texture.get_mut().unwrap() = depth_texture;
+        *self.msaa_texture.get_mut().unwrap() = msaa_texture;
+        *self.hdr_textures.get_mut().unwrap() = hdr_textures;
+        *self.offscreen_texture.get_mut().unwrap() = offscreen_texture;
+
+        Ok(())
     }
 
     pub fn resize(&self, size: Size) {
@@ -121,10 +332,32 @@ impl<'a, C: Configuration> Renderer<'a, C> {
         self.backend.window()
     }
 
+    /// Creates the surface and render targets of a further window sharing this renderer's device
+    /// Create the window with the event loop of the main window, see `WindowEventLoop`
+    pub fn create_viewport(&self, window: Window) -> Result<Viewport, EngineError> {
+        Viewport::new(&self.backend, window, self.antialiasing())
+    }
+
     pub fn configuration(&self) -> &C {
         &self.configuration
     }
 
+    pub fn pipelines(&self) -> &PipelineRegistry {
+        &self.pipelines
+    }
+
+    /// Registered pipeline of type `T`, panics if there is none
+    pub fn pipeline<T: Send + Sync + 'static>(&self) -> Arc<T> {
+        self.pipelines
+            .get()
+            .unwrap_or_else(|| panic!("pipeline {} is not registered", std::any::type_name::<T>()))
+    }
+
+    /// Initializes `T` with this renderer's backend and camera and registers it
+    pub fn register_pipeline<T: Pipeline + Send + Sync + 'static>(&self) -> Arc<T> {
+        self.pipelines.register(&self.backend, &self.camera)
+    }
+
     pub fn handle_resize(&self) {
         if self.resized.load(Ordering::Relaxed) {
             self.reconfigure_surface();
@@ -139,21 +372,185 @@ impl<'a, C: Configuration> Renderer<'a, C> {
         surface_lock.width = size.width;
         surface_lock.height = size.height;
 
-        self.backend()
-            .surface()
-            .configure(self.backend().device(), &surface_lock);
+        if let Some(surface) = self.backend().surface() {
+            surface.configure(self.backend().device(), &surface_lock);
+        }
 
-        let mut texture_lock = self.depth_texture.lock().unwrap();
+        let (depth_texture, msaa_texture, hdr_textures, offscreen_texture) =
+            Self::create_targets(self.backend(), &surface_lock, self.antialiasing.load());
 
-        *texture_lock = Texture::create_depth_texture(
-            self.backend().device(),
-            &surface_lock,
-            "vengine::depth_texture",
-        );
+        *self.depth_texture.lock().unwrap() = depth_texture;
+        *self.msaa_texture.lock().unwrap() = msaa_texture;
+        *self.hdr_textures.lock().unwrap() = hdr_textures;
+        *self.offscreen_texture.lock().unwrap() = offscreen_texture;
 
         self.camera
             .set_aspect(size.width as f32 / size.height as f32);
 
         self.current_size.store(size);
     }
+
+    fn create_targets(
+        backend: &Backend,
+        config: &SurfaceConfiguration,
+        antialiasing: AntiAliasing,
+    ) -> (Texture, Option<Texture>, [Texture; 2], Option<Texture>) {
+        let FrameTargets {
+            depth, msaa, hdr, ..
+        } = FrameTargets::new(backend, config.width, config.height, antialiasing);
+
+        let offscreen_texture = backend.is_headless().then(|| {
+            Texture::create_color_texture(
+                backend.device(),
+                config.width,
+                config.height,
+                config.format,
+                1,
+                "vengine::offscreen_texture",
+            )
+        });
+
+        (depth, msaa, hdr, offscreen_texture)
+    }
+}
+
+/// Fields drop after this, in declaration order with the backend last
+impl<C: Configuration> Drop for Renderer<C> {
+    /// Waits for submitted work, so nothing is released while the GPU still uses it
+    fn drop(&mut self) {
+        self.backend.device().poll(wgpu::Maintain::Wait);
+    }
+}
+
+/// Headless backend for GPU tests, `None` when no adapter is available and the test is skipped
+#[cfg(test)]
+pub(crate) fn test_backend(width: u32, height: u32) -> Option<Backend> {
+    test_backend_with_options(
+        width,
+        height,
+        options::BackendOptions::default().backends(wgpu::Backends::all()),
+    )
+}
+
+#[cfg(test)]
+pub(crate) fn test_backend_with_options(
+    width: u32,
+    height: u32,
+    options: options::BackendOptions,
+) -> Option<Backend> {
+    match pollster::block_on(Backend::headless_with_options(width, height, options)) {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            eprintln!("no adapter available, skipping test: {}", e);
+            None
+        }
+    }
+}
+
+#[test]
+fn test_set_antialiasing() {
+    use crate::engine::voxel::rendering::{pass::VoxelPass, pipeline::VoxelPipeline};
+
+    let Some(backend) = test_backend(16, 16) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.register_pipeline::<VoxelPipeline>();
+
+    let supported = renderer.backend().supports_sample_count(4);
+
+    assert_eq!(renderer.set_antialiasing(AntiAliasing::Msaa4x), supported);
+    assert_eq!(
+        renderer.antialiasing(),
+        if supported {
+            AntiAliasing::Msaa4x
+        } else {
+            AntiAliasing::None
+        }
+    );
+
+    // Scene passes find a pipeline for whatever mode was accepted
+    let frame = renderer.start_frame().unwrap();
+    let pass: VoxelPass = frame.start_render_pass().unwrap();
+    frame.finish_render_pass(pass);
+    renderer.finish_frame(frame);
+}
+
+#[test]
+fn test_recover_device_lost() {
+    use crate::engine::voxel::rendering::pipeline::VoxelPipeline;
+    use cgmath::Point3;
+    use std::sync::atomic::AtomicUsize;
+
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Pipeline for Counted {
+        fn initialize(_backend: &Backend, _camera: &Camera) -> Self {
+            BUILDS.fetch_add(1, Ordering::Relaxed);
+            Counted
+        }
+    }
+
+    struct Counting;
+
+    impl Configuration for Counting {
+        fn initialize(&mut self, pipelines: &PipelineRegistry, backend: &Backend, camera: &Camera) {
+            pipelines.register::<Counted>(backend, camera);
+        }
+    }
+
+    let Some(backend) = test_backend(16, 16) else {
+        return;
+    };
+
+    let mut renderer = Renderer::new(Counting, backend).unwrap();
+    let old = renderer.register_pipeline::<VoxelPipeline>();
+    renderer.pipelines().insert(0u32);
+
+    renderer.camera().set_eye(Point3::new(1.0, 2.0, 3.0));
+    renderer.camera().set_look_at(Point3::new(0.0, 2.0, 0.0));
+    let view_projection = renderer.camera().build_view_projection_matrix();
+
+    renderer.backend().device().destroy();
+    renderer.backend().device().poll(wgpu::Maintain::Wait);
+
+    assert!(matches!(
+        renderer.start_frame(),
+        Err(EngineError::DeviceLost(_))
+    ));
+
+    pollster::block_on(renderer.recover()).unwrap();
+
+    assert!(renderer.backend().device_lost().is_none());
+    assert!(!Arc::ptr_eq(&old, &renderer.pipeline::<VoxelPipeline>()));
+    assert!(!renderer.pipelines().contains::<u32>());
+    assert_eq!(
+        renderer.camera().build_view_projection_matrix(),
+        view_projection
+    );
+
+    // Configuration pipelines are rebuilt once
+    assert_eq!(BUILDS.load(Ordering::Relaxed), 2);
+
+    let frame = renderer.start_frame().unwrap();
+    renderer.finish_frame(frame);
+    assert!(renderer.read_output().is_some());
+}
+
+#[test]
+fn test_new_device_lost() {
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    backend.device().destroy();
+    backend.device().poll(wgpu::Maintain::Wait);
+
+    assert!(matches!(
+        Renderer::new((), backend),
+        Err(EngineError::DeviceLost(_))
+    ));
 }
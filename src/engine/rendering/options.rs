@@ -0,0 +1,124 @@
+use wgpu::{Backends, Features, Limits, PowerPreference, TextureFormat};
+
+/// How finished frames are presented, unsupported modes fall back towards `Vsync`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Waits for vertical blank, supported everywhere
+    #[default]
+    Vsync,
+    /// Replaces the queued frame with newer ones, no tearing and low latency
+    Mailbox,
+    /// Presents right away, may tear
+    Immediate,
+}
+
+impl PresentMode {
+    /// Closest mode in `supported`, `Fifo` is always available
+    pub fn select(&self, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+        let preferred: &[wgpu::PresentMode] = match self {
+            PresentMode::Vsync => &[],
+            PresentMode::Mailbox => &[wgpu::PresentMode::Mailbox],
+            PresentMode::Immediate => &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox],
+        };
+
+        preferred
+            .iter()
+            .copied()
+            .find(|mode| supported.contains(mode))
+            .unwrap_or(wgpu::PresentMode::Fifo)
+    }
+}
+
+/// Surface formats preferred with `BackendOptions::hdr`, in order
+pub const HDR_FORMATS: [TextureFormat; 2] =
+    [TextureFormat::Rgba16Float, TextureFormat::Rgb10a2Unorm];
+
+/// Adapter, device and surface settings of a `Backend`
+#[derive(Clone, Debug)]
+pub struct BackendOptions {
+    pub(super) present_mode: PresentMode,
+    pub(super) power_preference: PowerPreference,
+    pub(super) adapter_index: Option<usize>,
+    pub(super) backends: Backends,
+    pub(super) features: Features,
+    pub(super) limits: Limits,
+    pub(super) hdr: bool,
+    pub(super) frame_latency: u32,
+}
+
+impl BackendOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Uses the adapter at `index` of `Instance::enumerate_adapters` instead of the preferred one
+    pub fn adapter_index(mut self, index: usize) -> Self {
+        self.adapter_index = Some(index);
+        self
+    }
+
+    pub fn backends(mut self, backends: Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Features the device must have, `PUSH_CONSTANTS` is enabled anyway where available
+    pub fn features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Limits the device must have, `EngineError::UnsupportedLimits` names those the adapter can't
+    /// meet, texture sizes always use the adapter's maximum
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Prefers `HDR_FORMATS` for the surface, headless backends keep `Texture::HEADLESS_FORMAT`
+    pub fn hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    /// Frames the presentation engine may queue ahead
+    pub fn frame_latency(mut self, frame_latency: u32) -> Self {
+        self.frame_latency = frame_latency.max(1);
+        self
+    }
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentMode::Vsync,
+            power_preference: PowerPreference::default(),
+            adapter_index: None,
+            backends: Backends::PRIMARY,
+            features: Features::empty(),
+            limits: Limits::downlevel_defaults(),
+            hdr: false,
+            frame_latency: 2,
+        }
+    }
+}
+
+#[test]
+fn test_select_present_mode() {
+    use wgpu::PresentMode::{Fifo, Immediate, Mailbox};
+
+    assert_eq!(PresentMode::Vsync.select(&[Immediate, Mailbox, Fifo]), Fifo);
+    assert_eq!(PresentMode::Mailbox.select(&[Immediate, Fifo]), Fifo);
+    assert_eq!(PresentMode::Immediate.select(&[Mailbox, Fifo]), Mailbox);
+    assert_eq!(PresentMode::Immediate.select(&[Fifo, Immediate]), Immediate);
+}
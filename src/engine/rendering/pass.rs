@@ -1,11 +1,71 @@
-use crate::engine::rendering::{configuration::Configuration, pipeline::GetPipeline};
+use crate::engine::rendering::configuration::Configuration;
+use std::fmt::Display;
 
 use super::Frame;
 
+/// `RenderPass::RequiredPipeline` of a pass isn't registered
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingPipeline {
+    pub pass: &'static str,
+    pub pipeline: &'static str,
+}
+
+impl Display for MissingPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} requires {}, register it before starting the pass",
+            self.pass, self.pipeline
+        )
+    }
+}
+
+impl std::error::Error for MissingPipeline {}
+
 pub trait RenderPass {
-    type RequiredPipeline;
+    /// Looked up in the `PipelineRegistry` of the renderer, `Frame::start_render_pass` fails
+    /// while it isn't registered
+    type RequiredPipeline: Send + Sync + 'static;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self;
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>);
+}
+
+#[test]
+fn test_missing_pipeline() {
+    use super::{
+        graph::{GraphError, RenderGraph, Resource},
+        test_backend, Renderer,
+    };
+    use crate::engine::voxel::rendering::pass::VoxelPass;
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+
+    let frame = renderer.start_frame().unwrap();
+
+    assert_eq!(
+        frame.start_render_pass::<VoxelPass>().err(),
+        Some(MissingPipeline {
+            pass: "VoxelPass",
+            pipeline: "VoxelPipeline",
+        })
+    );
+
+    let mut graph = RenderGraph::new();
+    graph.add_render_pass::<VoxelPass, _>("voxel", &[], &[Resource::Hdr], |_| {});
 
-    fn start<C: Configuration + GetPipeline<Self::RequiredPipeline>>(frame: &Frame<C>) -> Self;
+    assert!(matches!(
+        graph.execute(&frame),
+        Err(GraphError::MissingPipeline(MissingPipeline {
+            pass: "VoxelPass",
+            ..
+        }))
+    ));
 
-    fn finish<C: Configuration + GetPipeline<Self::RequiredPipeline>>(self, frame: &Frame<C>);
+    renderer.finish_frame(frame);
 }
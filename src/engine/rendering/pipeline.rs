@@ -1,9 +1,40 @@
 use super::{backend::Backend, camera::Camera};
+use wgpu::RenderPipeline;
 
 pub trait Pipeline {
-    fn initialize(backend: &Backend<'_>, camera: &Camera) -> Self;
+    fn initialize(backend: &Backend, camera: &Camera) -> Self;
 }
 
-pub trait GetPipeline<T> {
-    fn get_pipeline(&self) -> &T;
+/// Sample counts every scene pipeline is built for
+pub const SAMPLE_COUNTS: [u32; 2] = [1, 4];
+
+/// Whether `MultisampledPipeline`s created with `backend` have a pipeline for `sample_count`
+pub fn supports_sample_count(backend: &Backend, sample_count: u32) -> bool {
+    SAMPLE_COUNTS.contains(&sample_count) && backend.supports_sample_count(sample_count)
+}
+
+/// One render pipeline per entry of `SAMPLE_COUNTS`, so anti-aliasing can change at runtime
+/// Sample counts the adapter doesn't support are skipped
+pub struct MultisampledPipeline {
+    pipelines: [Option<RenderPipeline>; SAMPLE_COUNTS.len()],
+}
+
+impl MultisampledPipeline {
+    pub fn new<F: FnMut(u32) -> RenderPipeline>(backend: &Backend, mut create: F) -> Self {
+        Self {
+            pipelines: SAMPLE_COUNTS.map(|sample_count| {
+                supports_sample_count(backend, sample_count).then(|| create(sample_count))
+            }),
+        }
+    }
+
+    /// Panics for sample counts `supports_sample_count` rejects, `Renderer::set_antialiasing`
+    /// never switches to those
+    pub fn get(&self, sample_count: u32) -> &RenderPipeline {
+        SAMPLE_COUNTS
+            .iter()
+            .position(|n| *n == sample_count)
+            .and_then(|index| self.pipelines[index].as_ref())
+            .expect("unsupported sample count")
+    }
 }
@@ -0,0 +1,226 @@
+use crate::engine::voxel::texture::{decode_png, TextureError};
+use std::path::Path;
+use wgpu::{Buffer, CommandEncoder, Device, Queue, TextureFormat};
+
+/// Tightly packed RGBA8 pixels read back from the GPU
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(bytes)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), png::EncodingError> {
+        std::fs::write(path, self.encode_png()?)?;
+
+        Ok(())
+    }
+
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, TextureError> {
+        let (width, height, pixels) = decode_png(&std::fs::read(path)?)?;
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Largest difference of any channel, `None` if the sizes differ
+    pub fn max_difference(&self, other: &Image) -> Option<u8> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        self.pixels
+            .iter()
+            .zip(&other.pixels)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .or(Some(0))
+    }
+}
+
+/// Rows of texture to buffer copies have to be aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    (width * 4).div_ceil(align) * align
+}
+
+/// Records a copy of an RGBA8 or BGRA8 texture into a new mappable buffer
+pub fn copy_to_buffer(
+    device: &Device,
+    encoder: &mut CommandEncoder,
+    texture: &wgpu::Texture,
+) -> Buffer {
+    let bytes_per_row = padded_bytes_per_row(texture.width());
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("vengine::readback_buffer"),
+        size: (bytes_per_row * texture.height()) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(texture.height()),
+            },
+        },
+        texture.size(),
+    );
+
+    buffer
+}
+
+/// Whether `unpad` can convert textures of `format`
+pub fn is_readable(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+    )
+}
+
+/// Strips the row padding of a mapped readback buffer and swizzles BGRA into RGBA
+/// `None` unless `is_readable(format)`
+pub fn unpad(data: &[u8], width: u32, height: u32, format: TextureFormat) -> Option<Image> {
+    let bgra = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        _ => return None,
+    };
+
+    let bytes_per_row = padded_bytes_per_row(width) as usize;
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+
+    for row in data.chunks_exact(bytes_per_row).take(height as usize) {
+        pixels.extend_from_slice(&row[..width as usize * 4]);
+    }
+
+    if bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    Some(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Copies `texture` back to the CPU, blocking until the GPU is done
+/// `None` if it can't be copied or isn't `is_readable`, e.g. the HDR targets
+pub fn read_texture(device: &Device, queue: &Queue, texture: &wgpu::Texture) -> Option<Image> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) || !is_readable(texture.format()) {
+        return None;
+    }
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("vengine::readback_encoder"),
+    });
+
+    let buffer = copy_to_buffer(device, &mut encoder, texture);
+
+    queue.submit([encoder.finish()]);
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let image = unpad(
+        &slice.get_mapped_range(),
+        texture.width(),
+        texture.height(),
+        texture.format(),
+    );
+
+    buffer.unmap();
+
+    image
+}
+
+/// Compares `image` against `tests/golden/<name>.png`, allowing `tolerance` per channel
+/// With `VENGINE_UPDATE_GOLDEN` set the golden is written instead, a missing golden fails
+pub fn assert_golden(image: &Image, name: &str, tolerance: u8) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+
+    if std::env::var_os("VENGINE_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image.save_png(&path).unwrap();
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "missing golden image {}, run with VENGINE_UPDATE_GOLDEN=1 to create it",
+        path.display()
+    );
+
+    let golden = Image::load_png(&path).unwrap();
+
+    match image.max_difference(&golden) {
+        Some(difference) => assert!(
+            difference <= tolerance,
+            "{} differs from its golden image by {}",
+            name,
+            difference
+        ),
+        None => panic!(
+            "{} is {}x{}, its golden image is {}x{}",
+            name, image.width, image.height, golden.width, golden.height
+        ),
+    }
+}
+
+#[test]
+fn test_unpad() {
+    let width = 3;
+    let stride = padded_bytes_per_row(width) as usize;
+
+    assert_eq!(stride, 256);
+
+    let mut data = vec![0u8; stride * 2];
+    data[..4].copy_from_slice(&[1, 2, 3, 4]);
+    data[stride..stride + 4].copy_from_slice(&[5, 6, 7, 8]);
+
+    let image = unpad(&data, width, 2, TextureFormat::Bgra8UnormSrgb).unwrap();
+
+    assert_eq!(image.pixels.len(), 24);
+    assert_eq!(&image.pixels[..4], &[3, 2, 1, 4]);
+    assert_eq!(&image.pixels[12..16], &[7, 6, 5, 8]);
+
+    assert_eq!(unpad(&data, width, 2, TextureFormat::Rgba16Float), None);
+}
@@ -0,0 +1,127 @@
+use super::{backend::Backend, camera::Camera, pipeline::Pipeline};
+use ahash::HashMap;
+use std::{
+    any::{Any, TypeId},
+    sync::{Arc, RwLock},
+};
+
+type AnyPipeline = Arc<dyn Any + Send + Sync>;
+
+type Initialize = fn(&Backend, &Camera) -> AnyPipeline;
+
+struct Entry {
+    pipeline: AnyPipeline,
+    /// Recreates the pipeline after the device was lost, `None` for inserted pipelines
+    initialize: Option<Initialize>,
+}
+
+/// Pipelines of a `Renderer` looked up by type, plugins can add and remove them at runtime
+#[derive(Default)]
+pub struct PipelineRegistry {
+    pipelines: RwLock<HashMap<TypeId, Entry>>,
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initializes `T` and adds it, replacing a registered `T`
+    pub fn register<T: Pipeline + Send + Sync + 'static>(
+        &self,
+        backend: &Backend,
+        camera: &Camera,
+    ) -> Arc<T> {
+        self.add(
+            T::initialize(backend, camera),
+            Some(|backend, camera| Arc::new(T::initialize(backend, camera))),
+        )
+    }
+
+    /// Adds `pipeline`, unlike registered ones it is dropped by `recreate`
+    pub fn insert<T: Send + Sync + 'static>(&self, pipeline: T) -> Arc<T> {
+        self.add(pipeline, None)
+    }
+
+    fn add<T: Send + Sync + 'static>(&self, pipeline: T, initialize: Option<Initialize>) -> Arc<T> {
+        let pipeline = Arc::new(pipeline);
+
+        self.pipelines.write().unwrap().insert(
+            TypeId::of::<T>(),
+            Entry {
+                pipeline: pipeline.clone(),
+                initialize,
+            },
+        );
+
+        pipeline
+    }
+
+    /// Initializes every registered pipeline again, e.g. with a new device
+    /// Inserted pipelines can't be rebuilt and are removed
+    pub fn recreate(&self, backend: &Backend, camera: &Camera) {
+        let mut pipelines = self.pipelines.write().unwrap();
+
+        pipelines.retain(|_, entry| entry.initialize.is_some());
+
+        for entry in pipelines.values_mut() {
+            if let Some(initialize) = entry.initialize {
+                entry.pipeline = initialize(backend, camera);
+            }
+        }
+    }
+
+    /// Removes `T`, passes that still hold it keep it alive until they are finished
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.pipelines
+            .write()
+            .unwrap()
+            .remove(&TypeId::of::<T>())
+            .map(|e| e.pipeline.downcast().unwrap())
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.pipelines
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .map(|e| e.pipeline.clone().downcast().unwrap())
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.pipelines
+            .read()
+            .unwrap()
+            .contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_pipeline_registry() {
+    struct A(u32);
+    struct B;
+
+    let registry = PipelineRegistry::new();
+
+    registry.insert(A(1));
+    assert!(registry.contains::<A>());
+    assert!(!registry.contains::<B>());
+    assert!(registry.get::<B>().is_none());
+
+    registry.insert(A(2));
+    registry.insert(B);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.get::<A>().unwrap().0, 2);
+
+    assert_eq!(registry.remove::<A>().unwrap().0, 2);
+    assert!(registry.get::<A>().is_none());
+    assert_eq!(registry.len(), 1);
+}
@@ -0,0 +1,195 @@
+use crate::engine::rendering::{
+    antialiasing::AntiAliasing,
+    backend::Backend,
+    camera::Camera,
+    pipeline::Pipeline,
+    shader::{self, ReloadShader, ShaderError},
+    texture::Texture,
+};
+use std::{path::PathBuf, sync::RwLock};
+use wgpu::{
+    BindGroupLayout, CommandEncoder, Device, PipelineLayout, RenderPipeline, TextureFormat,
+    TextureView,
+};
+
+/// Fullscreen copy of the HDR scene into the frame output, optionally with FXAA
+/// Registered by `Renderer::new` for the surface format
+pub struct ResolvePipeline {
+    /// FXAA and blit pipelines
+    pipelines: RwLock<[RenderPipeline; 2]>,
+    layout: PipelineLayout,
+    format: TextureFormat,
+    bind_group_layout: BindGroupLayout,
+}
+
+impl ResolvePipeline {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("vengine::resolve_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("vengine::resolve_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipelines = create_pipelines(
+            device,
+            &layout,
+            format,
+            include_str!("shaders/resolve.wgsl"),
+        );
+
+        Self {
+            pipelines: RwLock::new(pipelines),
+            layout,
+            format,
+            bind_group_layout,
+        }
+    }
+
+    pub fn render(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &Texture,
+        target: &TextureView,
+        antialiasing: AntiAliasing,
+    ) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("vengine::resolve_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&source.sampler),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("vengine::resolve_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        let [fxaa, blit] = &*self.pipelines.read().unwrap();
+
+        pass.set_pipeline(match antialiasing {
+            AntiAliasing::Fxaa => fxaa,
+            AntiAliasing::None | AntiAliasing::Msaa4x => blit,
+        });
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+impl Pipeline for ResolvePipeline {
+    fn initialize(backend: &Backend, _camera: &Camera) -> Self {
+        Self::new(backend.device(), *backend.surface_format())
+    }
+}
+
+impl ReloadShader for ResolvePipeline {
+    fn shader_files() -> Vec<PathBuf> {
+        vec![PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/engine/rendering/shaders/resolve.wgsl")]
+    }
+
+    fn reload_shader(&self, backend: &Backend, source: &str) -> Result<(), ShaderError> {
+        shader::validate_wgsl(
+            "vengine::resolve_shader",
+            source,
+            &["vs_main", "fs_main", "fs_blit"],
+        )?;
+
+        let pipelines = shader::capture_validation(backend, "vengine::resolve_shader", || {
+            create_pipelines(backend.device(), &self.layout, self.format, source)
+        })?;
+
+        *self.pipelines.write().unwrap() = pipelines;
+
+        Ok(())
+    }
+}
+
+fn create_pipelines(
+    device: &Device,
+    layout: &PipelineLayout,
+    format: TextureFormat,
+    source: &str,
+) -> [RenderPipeline; 2] {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("vengine::resolve_shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    let create = |label, entry_point| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    };
+
+    [
+        create("vengine::fxaa_pipeline", "fs_main"),
+        create("vengine::blit_pipeline", "fs_blit"),
+    ]
+}
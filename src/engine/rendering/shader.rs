@@ -0,0 +1,134 @@
+use super::backend::Backend;
+use std::{fmt::Display, path::PathBuf};
+
+/// WGSL that failed to parse or validate, the message points at the offending source
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub label: String,
+    pub message: String,
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.label, self.message)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Parses and validates WGSL with naga and checks that `entry_points` exist
+/// Invalid modules would otherwise only fail inside wgpu, which panics
+pub fn validate_wgsl(label: &str, source: &str, entry_points: &[&str]) -> Result<(), ShaderError> {
+    let error = |message: String| ShaderError {
+        label: label.to_string(),
+        message,
+    };
+
+    let module =
+        naga::front::wgsl::parse_str(source).map_err(|e| error(e.emit_to_string(source)))?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .map_err(|e| error(e.emit_to_string(source)))?;
+
+    for name in entry_points {
+        if !module.entry_points.iter().any(|e| e.name == *name) {
+            return Err(error(format!("missing entry point `{}`", name)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `create` inside a validation error scope, so wgpu reports invalid shader modules and
+/// pipelines here instead of panicking in its uncaptured error handler
+pub fn capture_validation<T>(
+    backend: &Backend,
+    label: &str,
+    create: impl FnOnce() -> T,
+) -> Result<T, ShaderError> {
+    let device = backend.device();
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let created = create();
+
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(e) => Err(ShaderError {
+            label: label.to_string(),
+            message: e.to_string(),
+        }),
+        None => Ok(created),
+    }
+}
+
+/// Pipelines whose shader can be swapped while running, see `ShaderReloader`
+pub trait ReloadShader {
+    /// WGSL files in the source tree, concatenated in order to form the shader
+    fn shader_files() -> Vec<PathBuf>;
+
+    /// Validates `source` and rebuilds the render pipelines, keeps the old ones on error
+    fn reload_shader(&self, backend: &Backend, source: &str) -> Result<(), ShaderError>;
+}
+
+#[test]
+fn test_validate_wgsl() {
+    let source = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+
+    assert!(validate_wgsl("test", source, &["fs_main"]).is_ok());
+    assert!(validate_wgsl("test", source, &["vs_main"])
+        .unwrap_err()
+        .message
+        .contains("vs_main"));
+
+    let error = validate_wgsl("test", "fn broken( {}", &[]).unwrap_err();
+    assert_eq!(error.label, "test");
+    assert!(!error.message.is_empty());
+}
+
+#[test]
+fn test_reload_shaders() {
+    use crate::engine::{
+        post::rendering::pipeline::PostProcessPipeline,
+        rendering::{test_backend, Renderer},
+        sky::rendering::pipeline::SkyPipeline,
+    };
+
+    fn check<T: ReloadShader>(pipeline: &T, backend: &Backend, entry_point: &str) {
+        let source = T::shader_files()
+            .iter()
+            .map(std::fs::read_to_string)
+            .collect::<std::io::Result<String>>()
+            .unwrap();
+
+        assert!(pipeline.reload_shader(backend, &source).is_ok());
+
+        let broken = source.replace(&format!("fn {}(", entry_point), "fn renamed(");
+        let error = pipeline.reload_shader(backend, &broken).unwrap_err();
+        assert!(error.message.contains(entry_point));
+    }
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+
+    check(
+        &*renderer.register_pipeline::<SkyPipeline>(),
+        renderer.backend(),
+        "fs_main",
+    );
+    check(
+        &*renderer.register_pipeline::<PostProcessPipeline>(),
+        renderer.backend(),
+        "fs_gamma",
+    );
+    check(
+        &*renderer.resolve_pipeline().unwrap(),
+        renderer.backend(),
+        "fs_blit",
+    );
+}
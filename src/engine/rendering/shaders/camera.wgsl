@@ -0,0 +1,98 @@
+// Camera
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    eye: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// Sky & Fog
+struct SkyUniform {
+    zenith: vec4<f32>,
+    horizon: vec4<f32>,
+    ground: vec4<f32>,
+    sun_direction: vec4<f32>,
+    sun_color: vec4<f32>,
+    cubemap_colors: array<vec4<f32>, 6>,
+    fog_density: f32,
+    mode: u32,
+};
+@group(0) @binding(1)
+var<uniform> sky: SkyUniform;
+
+const SKY_GRADIENT: u32 = 0u;
+const SKY_ATMOSPHERE: u32 = 1u;
+const SKY_CUBEMAP: u32 = 2u;
+
+fn sky_gradient(direction: vec3<f32>) -> vec3<f32> {
+    let h = direction.y;
+
+    if h >= 0.0 {
+        return mix(sky.horizon.rgb, sky.zenith.rgb, pow(h, 0.5));
+    }
+
+    return mix(sky.horizon.rgb, sky.ground.rgb, pow(-h, 0.35));
+}
+
+fn sky_atmosphere(direction: vec3<f32>) -> vec3<f32> {
+    let sun = sky.sun_direction.xyz;
+    let cos_theta = dot(direction, sun);
+
+    // Rayleigh-like falloff towards the horizon, brighter around the sun
+    var color = sky_gradient(direction);
+    let scatter = pow(1.0 - abs(direction.y), 4.0) * max(sun.y, 0.0);
+    color += sky.sun_color.rgb * scatter * 0.25;
+
+    // Mie halo and sun disk
+    let halo = pow(max(cos_theta, 0.0), 64.0) * 0.5;
+    let disk = smoothstep(0.9995, 0.9998, cos_theta);
+    color += sky.sun_color.rgb * (halo + disk * 4.0) * step(0.0, direction.y);
+
+    return color;
+}
+
+// Cubemap face averages weighted by how much the direction faces them
+fn sky_cubemap(direction: vec3<f32>) -> vec3<f32> {
+    let weights = direction * direction;
+
+    let x = select(sky.cubemap_colors[1], sky.cubemap_colors[0], direction.x >= 0.0).rgb;
+    let y = select(sky.cubemap_colors[3], sky.cubemap_colors[2], direction.y >= 0.0).rgb;
+    let z = select(sky.cubemap_colors[5], sky.cubemap_colors[4], direction.z >= 0.0).rgb;
+
+    return x * weights.x + y * weights.y + z * weights.z;
+}
+
+// Sky color in a world-space view direction, cubemaps are approximated by their face averages
+fn sky_color(direction: vec3<f32>) -> vec3<f32> {
+    let d = normalize(direction);
+
+    switch sky.mode {
+        case 1u: {
+            return sky_atmosphere(d);
+        }
+        case 2u: {
+            return sky_cubemap(d);
+        }
+        default: {
+            return sky_gradient(d);
+        }
+    }
+}
+
+// Exponential squared distance fog towards the sky color behind the fragment
+fn apply_fog(color: vec4<f32>, world_position: vec3<f32>) -> vec4<f32> {
+    let view = world_position - camera.eye.xyz;
+    let distance = length(view);
+    let density = distance * sky.fog_density;
+    let visibility = exp(-(density * density));
+
+    if distance <= 0.0 {
+        return color;
+    }
+
+    // Blend into exactly the sky pixel behind the fragment so far chunk edges vanish
+    let fog_color = sky_color(view);
+
+    return vec4<f32>(mix(fog_color, color.rgb, visibility), color.a);
+}
@@ -0,0 +1,77 @@
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Fullscreen triangle, no vertex buffer required
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+
+    return out;
+}
+
+const FXAA_SPAN_MAX: f32 = 8.0;
+const FXAA_REDUCE_MUL: f32 = 1.0 / 8.0;
+const FXAA_REDUCE_MIN: f32 = 1.0 / 128.0;
+const LUMA: vec3<f32> = vec3<f32>(0.299, 0.587, 0.114);
+
+fn fetch(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(scene, scene_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene));
+
+    let rgb_nw = fetch(in.uv + vec2<f32>(-1.0, -1.0) * texel);
+    let rgb_ne = fetch(in.uv + vec2<f32>(1.0, -1.0) * texel);
+    let rgb_sw = fetch(in.uv + vec2<f32>(-1.0, 1.0) * texel);
+    let rgb_se = fetch(in.uv + vec2<f32>(1.0, 1.0) * texel);
+    let rgb_m = fetch(in.uv);
+
+    let luma_nw = dot(rgb_nw, LUMA);
+    let luma_ne = dot(rgb_ne, LUMA);
+    let luma_sw = dot(rgb_sw, LUMA);
+    let luma_se = dot(rgb_se, LUMA);
+    let luma_m = dot(rgb_m, LUMA);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+
+    dir = clamp(dir * rcp_dir_min, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+    let rgb_a = 0.5 * (fetch(in.uv + dir * (1.0 / 3.0 - 0.5)) + fetch(in.uv + dir * (2.0 / 3.0 - 0.5)));
+    let rgb_b = rgb_a * 0.5 + 0.25 * (fetch(in.uv - dir * 0.5) + fetch(in.uv + dir * 0.5));
+    let luma_b = dot(rgb_b, LUMA);
+
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4<f32>(rgb_a, 1.0);
+    }
+
+    return vec4<f32>(rgb_b, 1.0);
+}
+
+// Plain copy for when no screen-space anti-aliasing is selected
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSampleLevel(scene, scene_sampler, in.uv, 0.0).rgb, 1.0);
+}
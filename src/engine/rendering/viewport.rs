@@ -0,0 +1,214 @@
+use super::{
+    antialiasing::AntiAliasing,
+    backend::Backend,
+    configuration::Configuration,
+    frame::{Frame, FrameOutput, FrameTargets},
+    size::Size,
+    Renderer,
+};
+use crate::engine::{
+    core::window::{handler::Event, window::Window},
+    error::EngineError,
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, MutexGuard,
+};
+use wgpu::{Surface, SurfaceConfiguration};
+use winit::{event::WindowEvent, window::WindowId};
+
+/// Surface and render targets of a further window, e.g. a material preview or a detached panel
+///
+/// `start_frame` returns a `Frame` drawing into the viewport's own depth, MSAA and HDR targets,
+/// so scene, post-process and debug passes render here like in the main window, seen through the
+/// renderer's camera. Resizes and anti-aliasing changes are picked up by `start_frame`, after
+/// `Renderer::recover` call `reconfigure` with the new device. Drop viewports before the
+/// renderer, the window closes with its viewport.
+pub struct Viewport {
+    surface: Surface<'static>,
+    targets: Mutex<FrameTargets>,
+    config: Mutex<SurfaceConfiguration>,
+    /// Window events drained by the last `start_frame`
+    events: Mutex<Vec<Event>>,
+    close_requested: AtomicBool,
+    window: Window,
+}
+
+impl Viewport {
+    /// Attempts to acquire a frame before `start_frame` gives up
+    const SURFACE_RETRIES: usize = 3;
+
+    pub fn new(
+        backend: &Backend,
+        window: Window,
+        antialiasing: AntiAliasing,
+    ) -> Result<Self, EngineError> {
+        let surface = backend.instance().create_surface(window.handle())?;
+
+        let caps = surface.get_capabilities(backend.adapter());
+        let format = *backend.surface_format();
+
+        if !caps.formats.contains(&format) {
+            return Err(EngineError::IncompatibleSurface);
+        }
+
+        let (width, height) = window.dimension();
+
+        let config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: backend.present_mode().select(&caps.present_modes),
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: backend.options().frame_latency,
+        };
+        surface.configure(backend.device(), &config);
+
+        let targets = FrameTargets::new(backend, config.width, config.height, antialiasing);
+
+        Ok(Self {
+            window,
+            surface,
+            config: Mutex::new(config),
+            targets: Mutex::new(targets),
+            events: Mutex::new(Vec::new()),
+            close_requested: AtomicBool::new(false),
+        })
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn size(&self) -> Size {
+        let config = self.config.lock().unwrap();
+
+        Size {
+            width: config.width,
+            height: config.height,
+            pixels_per_point: self.window.window().scale_factor() as f32,
+        }
+    }
+
+    pub fn surface_configuration(&self) -> &Mutex<SurfaceConfiguration> {
+        &self.config
+    }
+
+    /// Window events drained by the last `start_frame`, e.g. for an `Input` or `UiPipeline`
+    /// Replaced by the next `start_frame`, so unread events don't pile up
+    pub fn events(&self) -> MutexGuard<'_, Vec<Event>> {
+        self.events.lock().unwrap()
+    }
+
+    /// Whether the window was asked to close, dropping the viewport closes it
+    pub fn close_requested(&self) -> bool {
+        self.close_requested.load(Ordering::Relaxed)
+    }
+
+    /// Configures the surface for the current window size and recreates the render targets
+    pub fn reconfigure(&self, backend: &Backend) {
+        let antialiasing = self.targets.lock().unwrap().antialiasing;
+        self.configure(backend, antialiasing);
+    }
+
+    fn configure(&self, backend: &Backend, antialiasing: AntiAliasing) {
+        let (width, height) = self.window.dimension();
+
+        let mut config = self.config.lock().unwrap();
+        config.width = width.max(1);
+        config.height = height.max(1);
+
+        self.surface.configure(backend.device(), &config);
+
+        *self.targets.lock().unwrap() =
+            FrameTargets::new(backend, config.width, config.height, antialiasing);
+    }
+
+    /// Drains both channels of the window, resizes are read from the window itself
+    fn handle_events(&self) {
+        for event in self.window.engine_events().try_iter() {
+            if let Event::WindowEvent(WindowEvent::CloseRequested) = event {
+                self.close_requested.store(true, Ordering::Relaxed);
+            }
+        }
+
+        *self.events.lock().unwrap() = self.window.events().try_iter().collect();
+    }
+
+    /// Acquires the next frame of this window, see `Renderer::start_frame` for the errors
+    ///
+    /// Passes and `RenderGraph`s run on the returned frame like on the main one, finish it with
+    /// `finish_frame`. The targets follow `Renderer::antialiasing`.
+    pub fn start_frame<'r, C: Configuration>(
+        &self,
+        renderer: &'r Renderer<C>,
+    ) -> Result<Frame<'r, C>, EngineError> {
+        self.handle_events();
+
+        let backend = renderer.backend();
+
+        if let Some(reason) = backend.device_lost() {
+            return Err(EngineError::DeviceLost(reason));
+        }
+
+        let (width, height) = self.window.dimension();
+
+        // Minimized windows have no area to render into
+        if width == 0 || height == 0 {
+            return Err(EngineError::SurfaceTimeout);
+        }
+
+        let resized = {
+            let config = self.config.lock().unwrap();
+            (config.width, config.height) != (width, height)
+        };
+
+        let antialiasing = renderer.antialiasing();
+
+        if resized || self.targets.lock().unwrap().antialiasing != antialiasing {
+            self.configure(backend, antialiasing);
+        }
+
+        let mut error = EngineError::SurfaceLost;
+
+        for _ in 0..Self::SURFACE_RETRIES {
+            match self.surface.get_current_texture() {
+                Ok(output) => {
+                    return Ok(Frame::with_targets(
+                        renderer,
+                        FrameOutput::Surface(output),
+                        &self.targets.lock().unwrap(),
+                        self.size(),
+                    ));
+                }
+                Err(wgpu::SurfaceError::Timeout) => error = EngineError::SurfaceTimeout,
+                Err(wgpu::SurfaceError::OutOfMemory) => return Err(EngineError::OutOfMemory),
+                Err(
+                    wgpu::SurfaceError::Outdated
+                    | wgpu::SurfaceError::Lost
+                    | wgpu::SurfaceError::Other,
+                ) => {
+                    if let Some(reason) = backend.device_lost() {
+                        return Err(EngineError::DeviceLost(reason));
+                    }
+
+                    error = EngineError::SurfaceLost;
+                    self.reconfigure(backend);
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Submits the passes of `frame` and presents it
+    pub fn finish_frame<C: Configuration>(&self, frame: Frame<C>) {
+        frame.finish().present();
+    }
+}
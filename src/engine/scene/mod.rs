@@ -0,0 +1,298 @@
+pub mod transform;
+
+use crate::engine::voxel::object::Object;
+use cgmath::{Matrix4, SquareMatrix};
+use transform::Transform;
+
+/// Handle of a node, stale handles of removed nodes are rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+pub struct Node {
+    local: Transform,
+    /// Cached by `SceneGraph::update`
+    world: Matrix4<f32>,
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /// Drawn at the world matrix, its own transform is applied inside the node, e.g. as pivot
+    pub object: Option<Object>,
+    pub visible: bool,
+}
+
+impl Node {
+    pub fn local(&self) -> &Transform {
+        &self.local
+    }
+
+    /// World matrix as of the last `SceneGraph::update`
+    pub fn world(&self) -> &Matrix4<f32> {
+        &self.world
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Tree of nodes with local transforms, e.g. a turret on a tank or arms on a torso
+///
+/// Changing a transform marks the node dirty, `update` recomputes the world matrices of dirty
+/// nodes and their descendants. Render with `VoxelPass::render_scene`.
+#[derive(Default)]
+pub struct SceneGraph {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node under `parent`, or as a root, panics if `parent` was removed
+    pub fn add(&mut self, parent: Option<NodeId>, local: Transform) -> NodeId {
+        assert!(
+            parent.is_none_or(|p| self.get(p).is_some()),
+            "parent node was removed"
+        );
+
+        let node = Node {
+            local,
+            world: Matrix4::identity(),
+            dirty: true,
+            parent,
+            children: Vec::new(),
+            object: None,
+            visible: true,
+        };
+
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+
+                NodeId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        match parent {
+            Some(parent) => self.get_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+
+        id
+    }
+
+    pub fn add_object(
+        &mut self,
+        parent: Option<NodeId>,
+        local: Transform,
+        object: Object,
+    ) -> NodeId {
+        let id = self.add(parent, local);
+        self.get_mut(id).unwrap().object = Some(object);
+
+        id
+    }
+
+    /// Removes `id` and its descendants, returns `false` if it was already removed
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        let Some(node) = self.get(id) else {
+            return false;
+        };
+
+        match node.parent {
+            Some(parent) => self.get_mut(parent).unwrap().children.retain(|c| *c != id),
+            None => self.roots.retain(|r| *r != id),
+        }
+
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index];
+
+            if let Some(node) = slot.node.take() {
+                stack.extend(node.children);
+            }
+
+            slot.generation += 1;
+            self.free.push(id.index);
+        }
+
+        true
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.slots
+            .get(id.index)
+            .filter(|s| s.generation == id.generation)
+            .and_then(|s| s.node.as_ref())
+    }
+
+    /// Use `set_local` to change the transform, so the world matrices are updated
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.slots
+            .get_mut(id.index)
+            .filter(|s| s.generation == id.generation)
+            .and_then(|s| s.node.as_mut())
+    }
+
+    pub fn set_local(&mut self, id: NodeId, local: Transform) {
+        if let Some(node) = self.get_mut(id) {
+            node.local = local;
+            node.dirty = true;
+        }
+    }
+
+    /// Moves `id` under `parent`, keeping its local transform
+    /// Returns `false` if a node was removed or `parent` is `id` or one of its descendants
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        if self.get(id).is_none() {
+            return false;
+        }
+
+        if let Some(parent) = parent {
+            let mut ancestor = Some(parent);
+
+            while let Some(a) = ancestor {
+                if a == id {
+                    return false;
+                }
+
+                match self.get(a) {
+                    Some(node) => ancestor = node.parent,
+                    None => return false,
+                }
+            }
+        }
+
+        let node = self.get_mut(id).unwrap();
+        let old = std::mem::replace(&mut node.parent, parent);
+        node.dirty = true;
+
+        match old {
+            Some(old) => self.get_mut(old).unwrap().children.retain(|c| *c != id),
+            None => self.roots.retain(|r| *r != id),
+        }
+
+        match parent {
+            Some(parent) => self.get_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+
+        true
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Recomputes the world matrices of dirty nodes and their descendants
+    pub fn update(&mut self) {
+        let mut stack = self
+            .roots
+            .iter()
+            .rev()
+            .map(|r| (*r, Matrix4::identity(), false))
+            .collect::<Vec<_>>();
+
+        while let Some((id, parent, parent_dirty)) = stack.pop() {
+            let node = self.get_mut(id).unwrap();
+
+            let dirty = parent_dirty || node.dirty;
+            if dirty {
+                node.world = parent * node.local.matrix();
+                node.dirty = false;
+            }
+
+            let world = node.world;
+
+            stack.extend(node.children.iter().rev().map(|c| (*c, world, dirty)));
+        }
+    }
+
+    /// Visible nodes with an object, each with the matrix to draw it at
+    pub fn objects(&self) -> impl Iterator<Item = (Matrix4<f32>, &Object)> {
+        self.slots
+            .iter()
+            .filter_map(|s| s.node.as_ref())
+            .filter(|n| n.visible)
+            .filter_map(|n| n.object.as_ref().map(|o| (n.world * o.transform(), o)))
+    }
+}
+
+#[test]
+fn test_scene_graph() {
+    use cgmath::{Deg, InnerSpace, Quaternion, Rotation3, Vector3, Vector4};
+
+    let position =
+        |scene: &SceneGraph, id| (scene.get(id).unwrap().world() * Vector4::unit_w()).truncate();
+
+    let mut scene = SceneGraph::new();
+
+    let tank = scene.add(None, Transform::from_position(Vector3::new(10.0, 0.0, 0.0)));
+    let turret = scene.add(
+        Some(tank),
+        Transform {
+            position: Vector3::new(0.0, 2.0, 0.0),
+            rotation: Quaternion::from_angle_y(Deg(90.0)),
+            ..Default::default()
+        },
+    );
+    let barrel = scene.add(
+        Some(turret),
+        Transform::from_position(Vector3::new(0.0, 0.0, 3.0)),
+    );
+
+    scene.update();
+    assert!((position(&scene, barrel) - Vector3::new(13.0, 2.0, 0.0)).magnitude() < 1e-5);
+
+    // Moving the tank moves everything on it
+    scene.set_local(tank, Transform::from_position(Vector3::new(0.0, 0.0, 5.0)));
+    scene.update();
+    assert!((position(&scene, barrel) - Vector3::new(3.0, 2.0, 5.0)).magnitude() < 1e-5);
+
+    assert!(!scene.set_parent(tank, Some(barrel)));
+    assert!(scene.set_parent(barrel, None));
+    scene.update();
+    assert!((position(&scene, barrel) - Vector3::new(0.0, 0.0, 3.0)).magnitude() < 1e-5);
+    assert_eq!(scene.roots(), &[tank, barrel]);
+
+    assert!(scene.remove(tank));
+    assert!(scene.get(turret).is_none());
+    assert!(!scene.remove(turret));
+    assert_eq!(scene.roots(), &[barrel]);
+
+    // Reused slots don't resolve old handles
+    let new = scene.add(None, Transform::default());
+    assert!(scene.get(new).is_some());
+    assert!(scene.get(tank).is_none() && scene.get(turret).is_none());
+}
@@ -0,0 +1,77 @@
+use cgmath::{InnerSpace, Matrix3, Matrix4, One, Quaternion, Rotation, Vector3, Zero};
+
+/// Position, rotation and scale, relative to the parent node in a `SceneGraph`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn from_position(position: Vector3<f32>) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    /// Decomposes a matrix without shear or projection, like `Object::transform`
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Self {
+        let x = matrix.x.truncate();
+        let y = matrix.y.truncate();
+        let z = matrix.z.truncate();
+
+        let scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+
+        let rotation = if scale.x * scale.y * scale.z == 0.0 {
+            Quaternion::one()
+        } else {
+            Quaternion::from(Matrix3::from_cols(x / scale.x, y / scale.y, z / scale.z))
+        };
+
+        Self {
+            position: matrix.w.truncate(),
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// Direction the entity faces, `+Z` when not rotated like the renderer camera
+    pub fn forward(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(Vector3::unit_z())
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[test]
+fn test_transform_from_matrix() {
+    use cgmath::{Deg, Rotation3};
+
+    let transform = Transform {
+        position: Vector3::new(1.0, 2.0, 3.0),
+        rotation: Quaternion::from_angle_y(Deg(90.0)),
+        scale: Vector3::new(2.0, 1.0, 0.5),
+    };
+
+    let decomposed = Transform::from_matrix(&transform.matrix());
+
+    assert!((decomposed.position - transform.position).magnitude() < 1e-5);
+    assert!((decomposed.scale - transform.scale).magnitude() < 1e-5);
+    assert!(decomposed.rotation.dot(transform.rotation).abs() > 1.0 - 1e-5);
+}
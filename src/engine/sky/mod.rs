@@ -0,0 +1,144 @@
+pub mod rendering;
+
+use cgmath::{InnerSpace, Vector3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkyMode {
+    /// Three-color gradient between ground, horizon and zenith
+    Gradient = 0,
+    /// Procedural atmosphere with a sun disk and halo
+    Atmosphere = 1,
+    /// Cubemap set through `SkyPipeline::set_cubemap`
+    Cubemap = 2,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sky {
+    pub mode: SkyMode,
+    pub zenith: [f32; 3],
+    pub horizon: [f32; 3],
+    pub ground: [f32; 3],
+    /// Direction pointing towards the sun
+    pub sun_direction: Vector3<f32>,
+    pub sun_color: [f32; 3],
+    /// Density of the exponential distance fog, `0.0` disables fog
+    pub fog_density: f32,
+    /// Linear average colors of the cubemap faces, which fog blends into in `SkyMode::Cubemap`
+    /// Set by `SkyPipeline::set_cubemap`
+    pub cubemap_colors: [[f32; 3]; 6],
+}
+
+impl Sky {
+    /// Chooses a fog density so geometry at `distance` is fogged by `coverage` (0.0 - 1.0)
+    pub fn fog_for_distance(distance: f32, coverage: f32) -> f32 {
+        (-(1.0 - coverage.clamp(0.0, 0.999)).ln()).sqrt() / distance.max(f32::EPSILON)
+    }
+
+    /// Linear average color of each tightly packed RGBA8 (sRGB) cubemap face
+    pub fn cubemap_colors(faces: [&[u8]; 6]) -> [[f32; 3]; 6] {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        faces.map(|face| {
+            let pixels = (face.len() / 4).max(1) as f32;
+            let mut sum = [0.0; 3];
+
+            for pixel in face.chunks_exact(4) {
+                for channel in 0..3 {
+                    sum[channel] += linear(pixel[channel]);
+                }
+            }
+
+            sum.map(|c| c / pixels)
+        })
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        wgpu::Color {
+            r: self.horizon[0] as f64,
+            g: self.horizon[1] as f64,
+            b: self.horizon[2] as f64,
+            a: 1.0,
+        }
+    }
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self {
+            mode: SkyMode::Gradient,
+            zenith: [0.02, 0.07, 0.2],
+            horizon: [0.1, 0.2, 0.3],
+            ground: [0.05, 0.05, 0.06],
+            sun_direction: Vector3::new(0.3, 0.8, 0.5),
+            sun_color: [1.0, 0.9, 0.7],
+            fog_density: Sky::fog_for_distance(100.0, 0.95),
+            cubemap_colors: [[0.1, 0.2, 0.3]; 6],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkyUniform {
+    zenith: [f32; 4],
+    horizon: [f32; 4],
+    ground: [f32; 4],
+    sun_direction: [f32; 4],
+    sun_color: [f32; 4],
+    cubemap_colors: [[f32; 4]; 6],
+    fog_density: f32,
+    mode: u32,
+    _padding: [u32; 2],
+}
+
+impl From<&Sky> for SkyUniform {
+    fn from(sky: &Sky) -> Self {
+        let sun = if sky.sun_direction.magnitude2() > 0.0 {
+            sky.sun_direction.normalize()
+        } else {
+            Vector3::unit_y()
+        };
+
+        Self {
+            zenith: [sky.zenith[0], sky.zenith[1], sky.zenith[2], 1.0],
+            horizon: [sky.horizon[0], sky.horizon[1], sky.horizon[2], 1.0],
+            ground: [sky.ground[0], sky.ground[1], sky.ground[2], 1.0],
+            sun_direction: [sun.x, sun.y, sun.z, 0.0],
+            sun_color: [sky.sun_color[0], sky.sun_color[1], sky.sun_color[2], 1.0],
+            cubemap_colors: sky.cubemap_colors.map(|[r, g, b]| [r, g, b, 1.0]),
+            fog_density: sky.fog_density.max(0.0),
+            mode: sky.mode as u32,
+            _padding: [0; 2],
+        }
+    }
+}
+
+#[test]
+fn test_fog_for_distance() {
+    let density = Sky::fog_for_distance(100.0, 0.95);
+    let fog = 1.0 - (-(100.0 * density as f64).powi(2)).exp();
+
+    assert!((fog - 0.95).abs() < 1e-4);
+}
+
+#[test]
+fn test_cubemap_colors() {
+    let black = [0u8, 0, 0, 255].repeat(4);
+    let white = [255u8, 255, 255, 255].repeat(4);
+    let mixed = [[0u8, 0, 0, 255], [255, 255, 255, 255]].concat();
+
+    let colors = Sky::cubemap_colors([&black, &white, &mixed, &black, &black, &black]);
+
+    assert_eq!(colors[0], [0.0; 3]);
+    assert_eq!(colors[1], [1.0; 3]);
+    // Averaged in linear space, not in sRGB
+    assert!((colors[2][0] - 0.5).abs() < 1e-5);
+}
@@ -0,0 +1,68 @@
+use super::pipeline::SkyPipeline;
+use crate::engine::{
+    profiler::Counters,
+    rendering::{configuration::Configuration, frame::Frame, pass::RenderPass},
+};
+use wgpu::CommandEncoder;
+
+/// Draws the sky behind everything else, start it before any `VoxelPass`
+pub struct SkyPass {
+    encoder: CommandEncoder,
+}
+
+impl RenderPass for SkyPass {
+    type RequiredPipeline = SkyPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let mut encoder = frame.renderer().backend().device().create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("vengine::render_sky_encoder"),
+            },
+        );
+
+        let (view, resolve_target) = frame.color_target();
+
+        let pipeline = frame.renderer().pipeline::<SkyPipeline>();
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("vengine::sky_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: frame.color_load_op(),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: frame.depth_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: frame.depth_load_op(),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(pipeline.pipeline().get(frame.sample_count()));
+        pass.set_bind_group(0, frame.renderer().camera().bind_group(), &[]);
+        pass.set_bind_group(1, &*pipeline.cubemap_bind_group(), &[]);
+        pass.draw(0..3, 0..1);
+
+        drop(pass);
+
+        frame.renderer().profiler().count(Counters {
+            draw_calls: 1,
+            instances: 1,
+            chunks: 0,
+        });
+
+        Self { encoder }
+    }
+
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.push_encoder(self.encoder);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::engine::{
+    rendering::{
+        backend::Backend,
+        camera::Camera,
+        pipeline::{MultisampledPipeline, Pipeline},
+        shader::{self, ReloadShader, ShaderError},
+        texture::Texture,
+    },
+    sky::Sky,
+};
+use std::{
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard},
+};
+use wgpu::{BindGroup, BindGroupLayout, Device, PipelineLayout, Queue, Sampler};
+
+pub struct SkyPipeline {
+    pipeline: RwLock<MultisampledPipeline>,
+    layout: PipelineLayout,
+    cubemap_bind_group_layout: BindGroupLayout,
+    cubemap_bind_group: Mutex<BindGroup>,
+    sampler: Sampler,
+}
+
+impl SkyPipeline {
+    pub fn pipeline(&self) -> RwLockReadGuard<'_, MultisampledPipeline> {
+        self.pipeline.read().unwrap()
+    }
+
+    pub fn cubemap_bind_group(&self) -> MutexGuard<'_, BindGroup> {
+        self.cubemap_bind_group.lock().unwrap()
+    }
+
+    /// Uploads a cubemap used by `SkyMode::Cubemap` and matches the fog of `camera` to it
+    /// Faces are tightly packed RGBA8 (sRGB) in the order +X, -X, +Y, -Y, +Z, -Z
+    pub fn set_cubemap(&self, backend: &Backend, camera: &Camera, size: u32, faces: [&[u8]; 6]) {
+        let bind_group = Self::create_cubemap_bind_group(
+            backend.device(),
+            backend.queue(),
+            &self.cubemap_bind_group_layout,
+            &self.sampler,
+            size,
+            faces,
+        );
+
+        *self.cubemap_bind_group() = bind_group;
+
+        let mut sky = camera.sky();
+        sky.cubemap_colors = Sky::cubemap_colors(faces);
+        camera.set_sky(sky);
+    }
+
+    fn create_cubemap_bind_group(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        size: u32,
+        faces: [&[u8]; 6],
+    ) -> BindGroup {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("vengine::sky_cubemap"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            assert_eq!(face.len(), (size * size * 4) as usize);
+
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                face,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size * 4),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("vengine::sky_cubemap_view"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("vengine::sky_cubemap_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+}
+
+impl Pipeline for SkyPipeline {
+    fn initialize(backend: &Backend, camera: &Camera) -> Self {
+        let device = backend.device();
+
+        let cubemap_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("vengine::sky_cubemap_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("vengine::sky_cubemap_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // Placeholder until a cubemap is set
+        let cubemap_bind_group = Self::create_cubemap_bind_group(
+            device,
+            backend.queue(),
+            &cubemap_bind_group_layout,
+            &sampler,
+            1,
+            [&[0u8, 0, 0, 255]; 6],
+        );
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("vengine::sky_pipeline_layout"),
+                bind_group_layouts: &[camera.bind_group_layout(), &cubemap_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = create_pipeline(
+            backend,
+            &render_pipeline_layout,
+            concat!(
+                include_str!("../../rendering/shaders/camera.wgsl"),
+                include_str!("shaders/sky.wgsl")
+            ),
+        );
+
+        Self {
+            pipeline: RwLock::new(pipeline),
+            layout: render_pipeline_layout,
+            cubemap_bind_group_layout,
+            cubemap_bind_group: Mutex::new(cubemap_bind_group),
+            sampler,
+        }
+    }
+}
+
+impl ReloadShader for SkyPipeline {
+    fn shader_files() -> Vec<PathBuf> {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/engine");
+
+        vec![
+            root.join("rendering/shaders/camera.wgsl"),
+            root.join("sky/rendering/shaders/sky.wgsl"),
+        ]
+    }
+
+    fn reload_shader(&self, backend: &Backend, source: &str) -> Result<(), ShaderError> {
+        shader::validate_wgsl("vengine::sky_shader", source, &["vs_main", "fs_main"])?;
+
+        let pipeline = shader::capture_validation(backend, "vengine::sky_shader", || {
+            create_pipeline(backend, &self.layout, source)
+        })?;
+
+        *self.pipeline.write().unwrap() = pipeline;
+
+        Ok(())
+    }
+}
+
+fn create_pipeline(
+    backend: &Backend,
+    layout: &PipelineLayout,
+    source: &str,
+) -> MultisampledPipeline {
+    let device = backend.device();
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("vengine::sky_shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    MultisampledPipeline::new(backend, |sample_count| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("vengine::sky_pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    })
+}
@@ -0,0 +1,38 @@
+// Camera and sky bindings are prepended from rendering/shaders/camera.wgsl
+
+@group(1) @binding(0)
+var cubemap: texture_cube<f32>;
+@group(1) @binding(1)
+var cubemap_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+// Fullscreen triangle, no vertex buffer required
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+
+    out.ndc = ndc;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let near = camera.inverse_view_proj * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = camera.inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+
+    if sky.mode == SKY_CUBEMAP {
+        return vec4<f32>(textureSample(cubemap, cubemap_sampler, direction).rgb, 1.0);
+    }
+
+    return vec4<f32>(sky_color(direction), 1.0);
+}
@@ -0,0 +1,283 @@
+use crate::engine::{
+    rendering::camera::Camera,
+    scene::transform::Transform,
+    voxel::{chunk::chunk_bounds, object::Object},
+};
+use cgmath::{Deg, Euler, Matrix4, Point3, Quaternion, Vector3, Vector4};
+use egui::{
+    CollapsingHeader, Color32, Context, DragValue, Id, LayerId, Order, Painter, Pos2, Rect,
+    ScrollArea, Stroke, Ui, Window,
+};
+
+const BOUNDS_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+
+/// Debug window listing objects and their chunks, with the camera and a transform editor
+///
+/// The selected chunk is outlined in the 3D view, hidden chunks are skipped by the voxel passes.
+#[derive(Default)]
+pub struct Inspector {
+    /// Index into the objects passed to `render`
+    object: Option<usize>,
+    chunk: Option<Vector3<i32>>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selected object and chunk
+    pub fn selection(&self) -> (Option<usize>, Option<Vector3<i32>>) {
+        (self.object, self.chunk)
+    }
+
+    pub fn render<'o>(
+        &mut self,
+        context: &Context,
+        camera: &Camera,
+        objects: impl IntoIterator<Item = &'o mut Object>,
+    ) {
+        let mut objects = objects.into_iter().collect::<Vec<_>>();
+
+        let window = Window::new("Inspector")
+            .default_width(320.0)
+            .default_height(480.0)
+            .resizable([true, true]);
+
+        window.show(context, |ui| {
+            CollapsingHeader::new("Camera")
+                .default_open(true)
+                .show(ui, |ui| camera_ui(ui, camera));
+
+            ui.separator();
+
+            ScrollArea::vertical().show(ui, |ui| {
+                for (index, object) in objects.iter_mut().enumerate() {
+                    self.object_ui(ui, index, object);
+                }
+            });
+        });
+
+        let selected = self
+            .object
+            .and_then(|index| objects.get(index))
+            .zip(self.chunk)
+            .filter(|(object, offset)| object.get_chunk(offset).is_some());
+
+        if let Some((object, offset)) = selected {
+            draw_chunk_bounds(context, camera, object.transform(), offset);
+        }
+    }
+
+    fn object_ui(&mut self, ui: &mut Ui, index: usize, object: &mut Object) {
+        let title = format!(
+            "Object {}: {} voxels in {} chunks",
+            index,
+            object.count(),
+            object.chunks().len()
+        );
+
+        CollapsingHeader::new(title)
+            .id_salt(("vengine::inspector_object", index))
+            .show(ui, |ui| {
+                transform_ui(ui, object);
+
+                ui.separator();
+
+                let mut offsets = object.chunks().map(|(o, _)| *o).collect::<Vec<_>>();
+                offsets.sort_by_key(|o| (o.x, o.y, o.z));
+
+                for offset in offsets {
+                    let chunk = object.get_chunk_mut(offset).unwrap();
+
+                    let quads = chunk.quads().map_or(0, |q| q.len());
+                    let translucent = chunk.translucent_quads().map_or(0, |q| q.len());
+
+                    ui.horizontal(|ui| {
+                        let mut visible = chunk.is_visible();
+                        if ui.checkbox(&mut visible, "").changed() {
+                            chunk.set_visible(visible);
+                        }
+
+                        let selected = self.object == Some(index) && self.chunk == Some(offset);
+                        let label = format!(
+                            "[{}, {}, {}] {} voxels, {} + {} quads",
+                            offset.x,
+                            offset.y,
+                            offset.z,
+                            chunk.chunk().count(),
+                            quads,
+                            translucent
+                        );
+
+                        if ui.selectable_label(selected, label).clicked() {
+                            if selected {
+                                self.chunk = None;
+                            } else {
+                                self.object = Some(index);
+                                self.chunk = Some(offset);
+                            }
+                        }
+                    });
+                }
+            });
+    }
+}
+
+fn camera_ui(ui: &mut Ui, camera: &Camera) {
+    let eye = camera.get_eye();
+    let target = camera.get_look_at();
+
+    ui.label(format!("Eye {:.2} {:.2} {:.2}", eye.x, eye.y, eye.z));
+    ui.label(format!(
+        "Target {:.2} {:.2} {:.2}",
+        target.x, target.y, target.z
+    ));
+    ui.label(format!(
+        "FOV {:.1}° / aspect {:.2}",
+        camera.fovy(),
+        camera.aspect()
+    ));
+}
+
+/// Edits position, rotation in degrees and scale of the object transform
+fn transform_ui(ui: &mut Ui, object: &mut Object) {
+    let mut transform = Transform::from_matrix(object.transform());
+    let euler = Euler::from(transform.rotation);
+    let mut rotation = [
+        Deg::from(euler.x).0,
+        Deg::from(euler.y).0,
+        Deg::from(euler.z).0,
+    ];
+
+    let mut changed = false;
+
+    let mut row = |ui: &mut Ui, label: &str, values: [&mut f32; 3], speed: f64| {
+        ui.horizontal(|ui| {
+            ui.label(label);
+
+            for value in values {
+                changed |= ui.add(DragValue::new(value).speed(speed)).changed();
+            }
+        });
+    };
+
+    let [px, py, pz] = transform.position.as_mut();
+    row(ui, "Position", [px, py, pz], 0.1);
+
+    let [rx, ry, rz] = &mut rotation;
+    row(ui, "Rotation", [rx, ry, rz], 1.0);
+
+    let [sx, sy, sz] = transform.scale.as_mut();
+    row(ui, "Scale", [sx, sy, sz], 0.01);
+
+    if changed {
+        transform.rotation = Quaternion::from(Euler::new(
+            Deg(rotation[0]),
+            Deg(rotation[1]),
+            Deg(rotation[2]),
+        ));
+
+        object.set_transform(transform.matrix());
+    }
+}
+
+/// Projects a world position to screen points, `None` behind the camera
+pub(crate) fn project(view_proj: &Matrix4<f32>, screen: Rect, point: Point3<f32>) -> Option<Pos2> {
+    let clip = view_proj * Vector4::new(point.x, point.y, point.z, 1.0);
+
+    if clip.w <= f32::EPSILON {
+        return None;
+    }
+
+    let ndc = clip.truncate() / clip.w;
+
+    Some(Pos2::new(
+        screen.min.x + (ndc.x + 1.0) / 2.0 * screen.width(),
+        screen.min.y + (1.0 - ndc.y) / 2.0 * screen.height(),
+    ))
+}
+
+/// Outlines the chunk at `offset` behind all windows
+fn draw_chunk_bounds(
+    context: &Context,
+    camera: &Camera,
+    transform: &Matrix4<f32>,
+    offset: Vector3<i32>,
+) {
+    let (min, max) = chunk_bounds(offset);
+
+    let view_proj = camera.build_view_projection_matrix() * transform;
+
+    let painter = context.layer_painter(LayerId::new(
+        Order::Background,
+        Id::new("vengine::inspector_bounds"),
+    ));
+
+    draw_box(
+        &painter,
+        &view_proj,
+        context.screen_rect(),
+        min,
+        max,
+        Stroke::new(1.5, BOUNDS_COLOR),
+    );
+}
+
+/// Draws the edges of the box between `min` and `max`, skipping edges behind the camera
+pub(crate) fn draw_box(
+    painter: &Painter,
+    view_proj: &Matrix4<f32>,
+    screen: Rect,
+    min: Point3<f32>,
+    max: Point3<f32>,
+    stroke: Stroke,
+) {
+    let corners = (0..8)
+        .map(|n| {
+            let corner = Point3::new(
+                if n & 1 == 0 { min.x } else { max.x },
+                if (n >> 1) & 1 == 0 { min.y } else { max.y },
+                if n >> 2 == 0 { min.z } else { max.z },
+            );
+            project(view_proj, screen, corner)
+        })
+        .collect::<Vec<_>>();
+
+    // Corners differing in one bit share an edge
+    for a in 0..8 {
+        for bit in [1, 2, 4] {
+            let b = a | bit;
+
+            if b == a {
+                continue;
+            }
+
+            if let (Some(start), Some(end)) = (corners[a], corners[b]) {
+                painter.line_segment([start, end], stroke);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_project() {
+    use cgmath::SquareMatrix;
+
+    let screen = Rect::from_min_size(Pos2::ZERO, egui::vec2(200.0, 100.0));
+    let identity = Matrix4::identity();
+
+    assert_eq!(
+        project(&identity, screen, Point3::new(0.0, 0.0, 0.5)),
+        Some(Pos2::new(100.0, 50.0))
+    );
+    assert_eq!(
+        project(&identity, screen, Point3::new(-1.0, 1.0, 0.5)),
+        Some(Pos2::ZERO)
+    );
+
+    // Behind the camera once the perspective divide flips
+    let mut behind = identity;
+    behind.w.w = -1.0;
+    assert_eq!(project(&behind, screen, Point3::new(0.0, 0.0, 0.0)), None);
+}
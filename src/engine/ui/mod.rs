@@ -1,3 +1,4 @@
+pub mod inspector;
 pub mod performance;
 pub mod rendering;
 
@@ -21,11 +22,11 @@ pub struct UiPipeline {
 }
 
 impl UiPipeline {
-    pub fn state(&self) -> MutexGuard<State> {
+    pub fn state(&self) -> MutexGuard<'_, State> {
         self.state.lock().unwrap()
     }
 
-    pub fn renderer(&self) -> MutexGuard<egui_wgpu::Renderer> {
+    pub fn renderer(&self) -> MutexGuard<'_, egui_wgpu::Renderer> {
         self.rendererer.lock().unwrap()
     }
 
@@ -39,7 +40,7 @@ impl UiPipeline {
 }
 
 impl Pipeline for UiPipeline {
-    fn initialize(backend: &Backend<'_>, _camera: &Camera) -> Self {
+    fn initialize(backend: &Backend, _camera: &Camera) -> Self {
         let context = Context::default();
         let state = State::new(
             context.clone(),
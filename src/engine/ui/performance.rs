@@ -1,9 +1,20 @@
-use egui::{Color32, Context, Id, LayerId, Order, Painter, Rangef, Rect, Stroke, Vec2, Window};
-use std::{collections::VecDeque, time::Instant};
+use crate::engine::profiler::{percentile, FrameProfile, Profiler};
+use egui::{
+    ecolor::Hsva, Align2, Color32, Context, FontId, Painter, Rangef, Rect, Sense, Stroke, Ui, Vec2,
+    Window,
+};
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, Instant},
+};
 
 const BACKLOG: usize = 100;
 const HEIGHT: f32 = 100.0;
 const AVG: usize = 25;
+const PERCENTILES: [f32; 3] = [50.0, 95.0, 99.0];
+/// Time not covered by an outermost scope
+const OTHER_COLOR: Color32 = Color32::from_gray(90);
 
 pub struct Stats {
     fps: VecDeque<f32>,
@@ -70,31 +81,36 @@ impl Stats {
                 self.avg_timing(AVG)
             ));
 
-            let width = ui.available_width();
+            let mut sorted = self
+                .timings
+                .iter()
+                .map(|t| Duration::from_secs_f32(t / 1000.0))
+                .collect::<Vec<_>>();
+            sorted.sort();
 
-            let pos = ui.next_widget_position();
+            let percentiles = PERCENTILES.map(|p| (p, percentile(&sorted, p)));
+            percentile_label(ui, &percentiles);
 
-            ui.add_space(HEIGHT);
+            let (rect, _) =
+                ui.allocate_exact_size(Vec2::new(ui.available_width(), HEIGHT), Sense::hover());
+            let painter = ui.painter_at(rect);
 
-            let painter = Painter::new(
-                context.clone(),
-                LayerId::new(Order::Foreground, Id::new(0)),
-                Rect::from_min_size(pos, Vec2::new(width, HEIGHT)),
-            );
+            let scale = graph_scale(&percentiles);
+            let bar = rect.width() / BACKLOG as f32;
 
-            let x_delta = width / BACKLOG as f32 / 2f32;
+            // Newest frame on the right
+            for (n, timing) in self.timings.iter().enumerate() {
+                let x = rect.max.x - (n as f32 + 0.5) * bar;
+                let height = (timing * scale).min(HEIGHT);
 
-            let mut x = pos.x;
-
-            for n in &self.fps {
                 painter.vline(
                     x,
-                    Rangef::new(pos.y + HEIGHT - n.min(HEIGHT), pos.y + HEIGHT),
-                    Stroke::new(x_delta * 2f32, Color32::WHITE),
+                    Rangef::new(rect.max.y - height, rect.max.y),
+                    Stroke::new(bar, Color32::WHITE),
                 );
-
-                x += x_delta;
             }
+
+            draw_percentiles(&painter, rect, scale, &percentiles);
         });
     }
 }
@@ -104,3 +120,250 @@ impl Default for Stats {
         Self::new()
     }
 }
+
+/// Window with a stacked frame-time graph of a `Profiler`, its counters and exports
+pub struct ProfilerView {
+    /// Stacks GPU passes instead of CPU scopes
+    gpu: bool,
+    /// Path of the exports without extension
+    path: String,
+    /// Outcome of the last export
+    status: Option<String>,
+}
+
+impl ProfilerView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(&mut self, context: &Context, profiler: &Profiler) {
+        let window = Window::new("Profiler")
+            .default_width(420.0)
+            .min_width(300.0)
+            .resizable([true, true]);
+
+        window.show(context, |ui| {
+            let frames = profiler.frames();
+
+            ui.horizontal(|ui| {
+                let mut enabled = profiler.is_enabled();
+                if ui.checkbox(&mut enabled, "Record").changed() {
+                    profiler.set_enabled(enabled);
+                }
+
+                ui.selectable_value(&mut self.gpu, false, "CPU");
+                ui.add_enabled_ui(profiler.supports_gpu_timing(), |ui| {
+                    ui.selectable_value(&mut self.gpu, true, "GPU");
+                });
+
+                if ui.button("Clear").clicked() {
+                    profiler.clear();
+                }
+            });
+
+            let percentiles = PERCENTILES.map(|p| (p, profiler.percentile(p)));
+            percentile_label(ui, &percentiles);
+
+            self.graph(ui, &frames, &percentiles);
+
+            if let Some(frame) = frames.last() {
+                ui.label(format!(
+                    "{} draw calls, {} instances, {} chunks",
+                    frame.counters.draw_calls, frame.counters.instances, frame.counters.chunks
+                ));
+            }
+
+            ui.separator();
+            self.legend(ui, &frames);
+
+            ui.separator();
+            self.export_ui(ui, profiler);
+        });
+    }
+
+    fn timings<'f>(
+        &self,
+        frame: &'f FrameProfile,
+    ) -> impl Iterator<Item = (&'static str, Duration)> + 'f {
+        let timings = if self.gpu { &frame.gpu } else { &frame.cpu };
+
+        timings
+            .iter()
+            .filter(|t| t.depth == 0)
+            .map(|t| (t.name, t.duration))
+    }
+
+    fn graph(&self, ui: &mut Ui, frames: &[FrameProfile], percentiles: &[(f32, Duration); 3]) {
+        let (rect, response) = ui.allocate_exact_size(
+            Vec2::new(ui.available_width(), HEIGHT * 1.5),
+            Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+
+        let scale = graph_scale(percentiles) / 1.5;
+        let bar = rect.width() / Profiler::HISTORY as f32;
+
+        for (n, frame) in frames.iter().rev().enumerate() {
+            let x = rect.max.x - (n as f32 + 0.5) * bar;
+            let mut y = rect.max.y;
+
+            for (name, duration) in self.timings(frame) {
+                let height = duration.as_secs_f32() * 1000.0 * scale;
+                painter.vline(
+                    x,
+                    Rangef::new((y - height).max(rect.min.y), y),
+                    Stroke::new(bar, name_color(name)),
+                );
+                y -= height;
+            }
+
+            // The rest of the frame, GPU time isn't part of the wall clock frame
+            if !self.gpu {
+                let top = rect.max.y - frame.duration.as_secs_f32() * 1000.0 * scale;
+                if top < y {
+                    painter.vline(
+                        x,
+                        Rangef::new(top.max(rect.min.y), y),
+                        Stroke::new(bar, OTHER_COLOR),
+                    );
+                }
+            }
+        }
+
+        draw_percentiles(&painter, rect, scale * 1.5, percentiles);
+
+        // Breakdown of the frame under the cursor
+        if let Some(pointer) = response.hover_pos() {
+            let n = ((rect.max.x - pointer.x) / bar) as usize;
+
+            if let Some(frame) = frames.iter().rev().nth(n) {
+                response.on_hover_ui_at_pointer(|ui| {
+                    ui.label(format!(
+                        "Frame {}: {:.2}ms",
+                        frame.index,
+                        frame.duration.as_secs_f32() * 1000.0
+                    ));
+
+                    let timings = if self.gpu { &frame.gpu } else { &frame.cpu };
+                    for timing in timings {
+                        ui.label(format!(
+                            "{}{} {:.3}ms",
+                            "  ".repeat(timing.depth as usize),
+                            timing.name,
+                            timing.duration.as_secs_f32() * 1000.0
+                        ));
+                    }
+                });
+            }
+        }
+    }
+
+    /// Colors and average times of the stacked names over the last frames
+    fn legend(&self, ui: &mut Ui, frames: &[FrameProfile]) {
+        let recent = &frames[frames.len().saturating_sub(AVG)..];
+        let mut totals: Vec<(&'static str, Duration)> = Vec::new();
+
+        for frame in recent {
+            for (name, duration) in self.timings(frame) {
+                match totals.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, total)) => *total += duration,
+                    None => totals.push((name, duration)),
+                }
+            }
+        }
+
+        for (name, total) in totals {
+            ui.horizontal(|ui| {
+                let (rect, _) = ui.allocate_exact_size(Vec2::splat(10.0), Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, name_color(name));
+
+                ui.label(format!(
+                    "{} {:.3}ms",
+                    name,
+                    total.as_secs_f32() * 1000.0 / recent.len().max(1) as f32
+                ));
+            });
+        }
+    }
+
+    fn export_ui(&mut self, ui: &mut Ui, profiler: &Profiler) {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+
+            if ui.button("CSV").clicked() {
+                let path = format!("{}.csv", self.path);
+                self.status = Some(match profiler.save_csv(&path) {
+                    Ok(()) => format!("Exported {}", path),
+                    Err(e) => format!("failed to write {}: {}", path, e),
+                });
+            }
+
+            if ui.button("JSON").clicked() {
+                let path = format!("{}.json", self.path);
+                self.status = Some(match profiler.save_json(&path) {
+                    Ok(()) => format!("Exported {}", path),
+                    Err(e) => format!("failed to write {}: {}", path, e),
+                });
+            }
+        });
+
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+    }
+}
+
+impl Default for ProfilerView {
+    fn default() -> Self {
+        Self {
+            gpu: false,
+            path: "profile".to_string(),
+            status: None,
+        }
+    }
+}
+
+fn percentile_label(ui: &mut Ui, percentiles: &[(f32, Duration); 3]) {
+    ui.label(
+        percentiles
+            .iter()
+            .map(|(p, d)| format!("p{} {:.2}ms", p, d.as_secs_f32() * 1000.0))
+            .collect::<Vec<_>>()
+            .join(" / "),
+    );
+}
+
+/// Pixels per millisecond so the 99th percentile fits the graph with some headroom
+fn graph_scale(percentiles: &[(f32, Duration); 3]) -> f32 {
+    let max = percentiles[2].1.as_secs_f32() * 1000.0;
+    HEIGHT / (max * 1.2).max(1.0)
+}
+
+fn draw_percentiles(painter: &Painter, rect: Rect, scale: f32, percentiles: &[(f32, Duration); 3]) {
+    for (p, duration) in percentiles {
+        let y = rect.max.y - duration.as_secs_f32() * 1000.0 * scale;
+
+        if y < rect.min.y {
+            continue;
+        }
+
+        let color = Color32::from_rgba_unmultiplied(255, 200, 0, 160);
+        painter.hline(rect.x_range(), y, Stroke::new(1.0, color));
+        painter.text(
+            egui::pos2(rect.min.x + 2.0, y - 1.0),
+            Align2::LEFT_BOTTOM,
+            format!("p{}", p),
+            FontId::monospace(10.0),
+            color,
+        );
+    }
+}
+
+/// Stable color per scope or pass name
+fn name_color(name: &str) -> Color32 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+
+    let hue = (hasher.finish() % 360) as f32 / 360.0;
+    Hsva::new(hue, 0.6, 0.85, 1.0).into()
+}
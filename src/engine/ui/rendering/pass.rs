@@ -1,7 +1,5 @@
 use crate::engine::{
-    rendering::{
-        configuration::Configuration, frame::Frame, pass::RenderPass, pipeline::GetPipeline,
-    },
+    rendering::{configuration::Configuration, frame::Frame, pass::RenderPass},
     ui::UiPipeline,
 };
 use egui::Context;
@@ -17,15 +15,14 @@ pub struct UiPass {
 impl RenderPass for UiPass {
     type RequiredPipeline = UiPipeline;
 
-    fn start<C: Configuration + GetPipeline<UiPipeline>>(frame: &Frame<C>) -> Self {
-        let mut ui_state = frame.renderer().configuration().get_pipeline().state();
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let pipeline = frame.renderer().pipeline::<UiPipeline>();
+        let mut ui_state = pipeline.state();
         let raw_input = ui_state.take_egui_input(frame.renderer().window().window());
         ui_state.egui_ctx().begin_pass(raw_input);
 
-        let view = frame
-            .output()
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        // Draw onto the final image
+        frame.resolve();
 
         let mut encoder = frame.renderer().backend().device().create_command_encoder(
             &wgpu::CommandEncoderDescriptor {
@@ -35,10 +32,10 @@ impl RenderPass for UiPass {
 
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
+                view: frame.output_view(),
                 resolve_target: None,
                 ops: egui_wgpu::wgpu::Operations {
-                    load: egui_wgpu::wgpu::LoadOp::Load,
+                    load: frame.color_load_op(),
                     store: StoreOp::Store,
                 },
             })],
@@ -60,30 +57,17 @@ impl RenderPass for UiPass {
         UiPass {
             pass: pass.forget_lifetime(),
             encoder,
-            context: frame
-                .renderer()
-                .configuration()
-                .get_pipeline()
-                .context()
-                .clone(),
+            context: pipeline.context().clone(),
         }
     }
 
-    fn finish<C: Configuration + GetPipeline<UiPipeline>>(mut self, frame: &Frame<C>) {
-        let mut ui_renderer = frame.renderer().configuration().get_pipeline().renderer();
+    fn finish<C: Configuration>(mut self, frame: &Frame<C>) {
+        let pipeline = frame.renderer().pipeline::<UiPipeline>();
+        let mut ui_renderer = pipeline.renderer();
 
-        let full_output = frame
-            .renderer()
-            .configuration()
-            .get_pipeline()
-            .state()
-            .egui_ctx()
-            .end_pass();
+        let full_output = pipeline.state().egui_ctx().end_pass();
 
-        let tris = frame
-            .renderer()
-            .configuration()
-            .get_pipeline()
+        let tris = pipeline
             .state()
             .egui_ctx()
             .tessellate(full_output.shapes, full_output.pixels_per_point);
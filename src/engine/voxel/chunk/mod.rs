@@ -1,6 +1,7 @@
 use super::quad::Quad;
 use ahash::HashMap;
 use axis::Axis;
+use cgmath::{Point3, Vector3};
 use direction::Direction;
 
 pub mod axis;
@@ -9,18 +10,44 @@ pub mod direction;
 pub const CHUNK_SIZE: usize = 32;
 pub const VOXEL_SIZE: f32 = 1.0;
 
+/// Voxel `(x, y, z)` covers `[x, x + 1] × [y, y + 1] × [z - 1, z]` voxel sizes in object space,
+/// as laid out by the quad vertices of `VoxelPipeline`
+pub fn voxel_min(voxel: Vector3<i32>) -> Point3<f32> {
+    Point3::new(voxel.x as f32, voxel.y as f32, (voxel.z - 1) as f32) * VOXEL_SIZE
+}
+
+/// Object space corners of the chunk at `offset`, shifted by one voxel along z like `voxel_min`
+pub fn chunk_bounds(offset: Vector3<i32>) -> (Point3<f32>, Point3<f32>) {
+    let size = CHUNK_SIZE as f32 * VOXEL_SIZE;
+    let min = voxel_min(offset * CHUNK_SIZE as i32);
+
+    (min, min + Vector3::new(size, size, size))
+}
+
 // The chunk coordinates are left handed
 #[derive(Clone)]
 pub struct Chunk {
     voxels: Box<[u32; 32 * 32]>,
+    /// Voxels with a fully opaque color, same layout as `voxels`
+    opaque: Box<[u32; 32 * 32]>,
     colors: HashMap<u16, [u8; 4]>,
+    /// Block texture id and tint flag of textured voxels
+    textures: HashMap<u16, (u8, bool)>,
+}
+
+/// Occupancy and opacity slices along one axis, padded with an empty slice on both ends
+struct Faces<'a> {
+    buffer: &'a [[u32; 32]; 34],
+    opaque: &'a [[u32; 32]; 34],
 }
 
 impl Chunk {
     pub fn empty() -> Chunk {
         Chunk {
             voxels: Box::new([0u32; 32 * 32]),
+            opaque: Box::new([0u32; 32 * 32]),
             colors: HashMap::default(),
+            textures: HashMap::default(),
         }
     }
 
@@ -31,14 +58,35 @@ impl Chunk {
         assert!(y < CHUNK_SIZE);
         assert!(z < CHUNK_SIZE);
 
-        self.colors
-            .insert(((z * 32 * 32) + ((31 - y) * 32) + x) as u16, color);
+        let index = ((z * 32 * 32) + ((31 - y) * 32) + x) as u16;
+
+        self.colors.insert(index, color);
+
+        // Voxels placed here later start without a texture
+        if !state {
+            self.textures.remove(&index);
+        }
 
         if state {
             self.voxels[(z * 32) + (31 - y)] |= 2147483648 >> x;
         } else {
             self.voxels[(z * 32) + (31 - y)] &= u32::MAX ^ (2147483648 >> x);
         }
+
+        if state && color[3] == u8::MAX {
+            self.opaque[(z * 32) + (31 - y)] |= 2147483648 >> x;
+        } else {
+            self.opaque[(z * 32) + (31 - y)] &= u32::MAX ^ (2147483648 >> x);
+        }
+    }
+
+    /// Whether the voxel is occupied and its color has an alpha below 255
+    pub fn get_translucent(&self, x: usize, y: usize, z: usize) -> bool {
+        assert!(x < CHUNK_SIZE);
+        assert!(y < CHUNK_SIZE);
+        assert!(z < CHUNK_SIZE);
+
+        self.get_occupied(x, y, z) && self.opaque[(z * 32) + (31 - y)] & (2147483648 >> x) == 0
     }
 
     /// Gets a voxel state inside a chunk
@@ -61,184 +109,228 @@ impl Chunk {
             .copied()
     }
 
-    pub fn remesh(&mut self, offsets: &mut [u16; 6], out: &mut Vec<Quad>) {
+    /// Assigns a block texture to a voxel, `0` reverts it to its flat color
+    /// With `tint` the texture is multiplied with the voxel color
+    pub fn set_texture(&mut self, x: usize, y: usize, z: usize, texture_id: u8, tint: bool) {
+        assert!(x < CHUNK_SIZE);
+        assert!(y < CHUNK_SIZE);
+        assert!(z < CHUNK_SIZE);
+
+        let index = ((z * 32 * 32) + ((31 - y) * 32) + x) as u16;
+
+        if texture_id == 0 {
+            self.textures.remove(&index);
+        } else {
+            self.textures.insert(index, (texture_id, tint));
+        }
+    }
+
+    pub fn get_texture(&self, x: usize, y: usize, z: usize) -> Option<(u8, bool)> {
+        assert!(x < CHUNK_SIZE);
+        assert!(y < CHUNK_SIZE);
+        assert!(z < CHUNK_SIZE);
+
+        self.textures
+            .get(&(((z * 32 * 32) + ((31 - y) * 32) + x) as u16))
+            .copied()
+    }
+
+    /// Builds the face quads of the chunk
+    /// Opaque faces are culled against opaque neighbours only, translucent faces against
+    /// any opaque neighbour or a translucent neighbour of the same color and texture
+    pub fn remesh(
+        &mut self,
+        offsets: &mut [u16; 6],
+        out: &mut Vec<Quad>,
+        translucent_offsets: &mut [u16; 6],
+        translucent: &mut Vec<Quad>,
+    ) {
         let mut buffer = [[0u32; 32]; 34];
+        let mut opaque = [[0u32; 32]; 34];
 
         // X-Axis
         for n in 0..32 {
             self.slice(Axis::X, n, &mut buffer[n + 1]);
+            Self::slice_layer(&self.opaque, Axis::X, n, &mut opaque[n + 1]);
         }
 
-        for n in 1..33 {
-            // "Vertical"
-            for a in 0..32 {
-                // "Horizontal"
-                for b in 0..32 {
-                    let mid = &buffer[n];
-                    let left = &buffer[n + 1];
-
-                    if left[a] & (2147483648 >> b) == 0 && mid[a] & (2147483648 >> b) != 0 {
-                        out.push(Quad::new(
-                            Direction::Left,
-                            n - 1,
-                            31 - a,
-                            b,
-                            self.get_color(n - 1, 31 - a, b).unwrap(),
-                        ));
-                    }
-                }
-            }
-        }
-
+        let faces = Faces {
+            buffer: &buffer,
+            opaque: &opaque,
+        };
+
+        self.mesh_direction(
+            &faces,
+            Direction::Left,
+            1,
+            |n, a, b| (n, 31 - a, b),
+            out,
+            translucent,
+        );
         offsets[0] = out.len() as u16;
-
-        for n in 1..33 {
-            // "Vertical"
-            for a in 0..32 {
-                // "Horizontal"
-                for b in 0..32 {
-                    let right = &buffer[n - 1];
-                    let mid = &buffer[n];
-
-                    if mid[a] & (2147483648 >> b) != 0 && right[a] & (2147483648 >> b) == 0 {
-                        out.push(Quad::new(
-                            Direction::Right,
-                            n - 1,
-                            31 - a,
-                            b,
-                            self.get_color(n - 1, 31 - a, b).unwrap(),
-                        ));
-                    }
-                }
-            }
-        }
-
+        translucent_offsets[0] = translucent.len() as u16;
+
+        self.mesh_direction(
+            &faces,
+            Direction::Right,
+            -1,
+            |n, a, b| (n, 31 - a, b),
+            out,
+            translucent,
+        );
         offsets[1] = out.len() as u16;
+        translucent_offsets[1] = translucent.len() as u16;
 
         // Y-Axis
         for n in 0..32 {
             self.slice(Axis::Y, n, &mut buffer[n + 1]);
+            Self::slice_layer(&self.opaque, Axis::Y, n, &mut opaque[n + 1]);
         }
 
-        for n in 1..33 {
-            // "Vertical"
-            for a in 0..32 {
-                // "Horizontal"
-                for b in 0..32 {
-                    let up = &buffer[n + 1];
-                    let mid = &buffer[n];
-
-                    if up[a] & (2147483648 >> b) == 0 && mid[a] & (2147483648 >> b) != 0 {
-                        out.push(Quad::new(
-                            Direction::Up,
-                            b,
-                            n - 1,
-                            31 - a,
-                            self.get_color(b, n - 1, 31 - a).unwrap(),
-                        ));
-                    }
-                }
-            }
-        }
-
+        let faces = Faces {
+            buffer: &buffer,
+            opaque: &opaque,
+        };
+
+        self.mesh_direction(
+            &faces,
+            Direction::Up,
+            1,
+            |n, a, b| (b, n, 31 - a),
+            out,
+            translucent,
+        );
         offsets[2] = out.len() as u16;
-
-        for n in 1..33 {
-            // "Vertical"
-            for a in 0..32 {
-                // "Horizontal"
-                for b in 0..32 {
-                    let mid = &buffer[n];
-                    let down = &buffer[n - 1];
-
-                    if mid[a] & (2147483648 >> b) != 0 && down[a] & (2147483648 >> b) == 0 {
-                        out.push(Quad::new(
-                            Direction::Down,
-                            b,
-                            n - 1,
-                            31 - a,
-                            self.get_color(b, n - 1, 31 - a).unwrap(),
-                        ));
-                    }
-                }
-            }
-        }
-
+        translucent_offsets[2] = translucent.len() as u16;
+
+        self.mesh_direction(
+            &faces,
+            Direction::Down,
+            -1,
+            |n, a, b| (b, n, 31 - a),
+            out,
+            translucent,
+        );
         offsets[3] = out.len() as u16;
+        translucent_offsets[3] = translucent.len() as u16;
 
         // Z-Axis
         for n in 0..32 {
             self.slice(Axis::Z, n, &mut buffer[n + 1]);
+            Self::slice_layer(&self.opaque, Axis::Z, n, &mut opaque[n + 1]);
         }
 
-        for n in 1..33 {
-            // "Vertical"
-            for a in 0..32 {
-                // "Horizontal"
-                for b in 0..32 {
-                    let front = &buffer[n - 1];
-                    let mid = &buffer[n];
-
-                    if front[a] & (2147483648 >> b) == 0 && mid[a] & (2147483648 >> b) != 0 {
-                        out.push(Quad::new(
-                            Direction::Front,
-                            b,
-                            31 - a,
-                            n - 1,
-                            self.get_color(b, 31 - a, n - 1).unwrap(),
-                        ));
-                    }
-                }
-            }
-        }
-
+        let faces = Faces {
+            buffer: &buffer,
+            opaque: &opaque,
+        };
+
+        self.mesh_direction(
+            &faces,
+            Direction::Front,
+            -1,
+            |n, a, b| (b, 31 - a, n),
+            out,
+            translucent,
+        );
         offsets[4] = out.len() as u16;
+        translucent_offsets[4] = translucent.len() as u16;
+
+        self.mesh_direction(
+            &faces,
+            Direction::Back,
+            1,
+            |n, a, b| (b, 31 - a, n),
+            out,
+            translucent,
+        );
+        offsets[5] = out.len() as u16;
+        translucent_offsets[5] = translucent.len() as u16;
+    }
 
+    /// Emits the visible faces pointing into `direction`, `step` selects the neighbouring slice
+    fn mesh_direction<F: Fn(usize, usize, usize) -> (usize, usize, usize)>(
+        &self,
+        faces: &Faces,
+        direction: Direction,
+        step: isize,
+        position: F,
+        out: &mut Vec<Quad>,
+        translucent: &mut Vec<Quad>,
+    ) {
         for n in 1..33 {
+            let neighbour = (n as isize + step) as usize;
+
             // "Vertical"
             for a in 0..32 {
                 // "Horizontal"
                 for b in 0..32 {
-                    let mid = &buffer[n];
-                    let back = &buffer[n + 1];
-
-                    if mid[a] & (2147483648 >> b) != 0 && back[a] & (2147483648 >> b) == 0 {
-                        out.push(Quad::new(
-                            Direction::Back,
-                            b,
-                            31 - a,
-                            n - 1,
-                            self.get_color(b, 31 - a, n - 1).unwrap(),
-                        ));
+                    let mask = 2147483648 >> b;
+
+                    if faces.buffer[n][a] & mask == 0 {
+                        continue;
+                    }
+
+                    let (x, y, z) = position(n - 1, a, b);
+
+                    // Colors and textures are only looked up for faces that are emitted
+                    let quad = || {
+                        let color = self.get_color(x, y, z).unwrap();
+                        let mut quad = Quad::new(direction, x, y, z, color);
+
+                        if let Some((id, tint)) = self.get_texture(x, y, z) {
+                            quad.set_texture_id(id);
+                            quad.set_tint(tint);
+                        }
+
+                        quad
+                    };
+
+                    if faces.opaque[n][a] & mask != 0 {
+                        if faces.opaque[neighbour][a] & mask == 0 {
+                            out.push(quad());
+                        }
+                    } else if faces.buffer[neighbour][a] & mask == 0 {
+                        translucent.push(quad());
+                    } else if faces.opaque[neighbour][a] & mask == 0 {
+                        let (nx, ny, nz) = position(neighbour - 1, a, b);
+
+                        if self.get_color(nx, ny, nz) != self.get_color(x, y, z)
+                            || self.get_texture(nx, ny, nz) != self.get_texture(x, y, z)
+                        {
+                            translucent.push(quad());
+                        }
                     }
                 }
             }
         }
-
-        offsets[5] = out.len() as u16;
     }
 
     fn slice(&self, axis: Axis, n: usize, buffer: &mut [u32; 32]) {
+        Self::slice_layer(&self.voxels, axis, n, buffer);
+    }
+
+    fn slice_layer(voxels: &[u32; 32 * 32], axis: Axis, n: usize, buffer: &mut [u32; 32]) {
         match axis {
             Axis::X =>
             {
                 #[allow(clippy::needless_range_loop)]
                 for y in 0..32 {
                     for z in 0..32 {
-                        buffer[y] |= ((self.voxels[z * 32 + y] << n) & 2147483648) >> z;
+                        buffer[y] |= ((voxels[z * 32 + y] << n) & 2147483648) >> z;
                     }
                 }
             }
             Axis::Y => {
                 for z in 0..32 {
-                    buffer[31 - z] = self.voxels[z * 32 + (31 - n)]
+                    buffer[31 - z] = voxels[z * 32 + (31 - n)]
                 }
             }
             Axis::Z =>
             {
                 #[allow(clippy::needless_range_loop)]
                 for y in 0..32 {
-                    buffer[y] = self.voxels[(n * 32) + y];
+                    buffer[y] = voxels[(n * 32) + y];
                 }
             }
         }
@@ -269,6 +361,68 @@ fn test_set_get() {
     }
 }
 
+#[test]
+fn test_clear_texture() {
+    // Clearing a voxel drops its block texture
+    let mut chunk = Chunk::empty();
+    chunk.set(1, 2, 3, true, [0u8; 4]);
+    chunk.set_texture(1, 2, 3, 4, false);
+    chunk.set(1, 2, 3, false, [0u8; 4]);
+    chunk.set(1, 2, 3, true, [0u8; 4]);
+    assert_eq!(chunk.get_texture(1, 2, 3), None);
+}
+
+#[test]
+fn test_remesh_translucent() {
+    let opaque = [255u8, 255, 255, 255];
+    let water = [0u8, 0, 255, 128];
+    let glass = [200u8, 200, 200, 64];
+
+    let mut chunk = Chunk::empty();
+    chunk.set(0, 0, 0, true, opaque);
+    chunk.set(1, 0, 0, true, water);
+    chunk.set(2, 0, 0, true, water);
+    chunk.set(3, 0, 0, true, glass);
+
+    let mut offsets = [0u16; 6];
+    let mut out = Vec::new();
+    let mut translucent_offsets = [0u16; 6];
+    let mut translucent = Vec::new();
+
+    chunk.remesh(
+        &mut offsets,
+        &mut out,
+        &mut translucent_offsets,
+        &mut translucent,
+    );
+
+    // The opaque voxel keeps its face towards the water
+    assert_eq!(out.len(), 6);
+    assert!(out.iter().all(|q| q.x() == 0));
+
+    // Water faces between the two water voxels and towards the opaque voxel are culled,
+    // the faces between water and glass are kept on both sides
+    let water_x: Vec<(u32, Direction)> = translucent
+        .iter()
+        .filter(|q| q.x() == 1 || q.x() == 2)
+        .filter(|q| matches!(q.direction(), Direction::Left | Direction::Right))
+        .map(|q| (q.x(), q.direction()))
+        .collect();
+    assert_eq!(water_x, vec![(2, Direction::Left)]);
+
+    let glass_x: Vec<Direction> = translucent
+        .iter()
+        .filter(|q| q.x() == 3)
+        .filter(|q| matches!(q.direction(), Direction::Left | Direction::Right))
+        .map(|q| q.direction())
+        .collect();
+    assert_eq!(glass_x, vec![Direction::Left, Direction::Right]);
+
+    assert_eq!(translucent_offsets[5] as usize, translucent.len());
+    assert!(chunk.get_translucent(1, 0, 0));
+    assert!(!chunk.get_translucent(0, 0, 0));
+}
+
 #[test]
 fn test_slice() {
     let mut target = [u32::MAX; 32];
@@ -328,3 +482,29 @@ fn test_slice() {
         assert_eq!(buffer, target);
     }
 }
+
+#[test]
+fn test_chunk_bounds() {
+    assert_eq!(
+        chunk_bounds(Vector3::new(1, -2, 3)),
+        (
+            Point3::new(32.0, -64.0, 95.0),
+            Point3::new(64.0, -32.0, 127.0)
+        )
+    );
+
+    let offset = Vector3::new(1, -2, 3);
+    let (min, max) = chunk_bounds(offset);
+    let first = offset * CHUNK_SIZE as i32;
+
+    for local in [Vector3::new(0, 0, 0), Vector3::new(31, 31, 31)] {
+        let voxel = voxel_min(first + local);
+        let corner = voxel + Vector3::new(VOXEL_SIZE, VOXEL_SIZE, VOXEL_SIZE);
+
+        for axis in 0..3 {
+            assert!(min[axis] <= voxel[axis] && corner[axis] <= max[axis]);
+        }
+    }
+
+    assert_eq!(min, voxel_min(first));
+}
@@ -12,6 +12,14 @@ pub struct ChunkMesh {
     buffer: Option<Buffer>,
     /// Indices for face starts, (Left, Right, Up, Down, Front, Back)
     offsets: [u16; 6],
+    /// Quads with an alpha below 255, rendered by the translucent pass
+    translucent_quads: Option<Vec<Quad>>,
+    /// On-Device translucent quad buffer
+    translucent_buffer: Option<Buffer>,
+    /// Indices for translucent face starts, (Left, Right, Up, Down, Front, Back)
+    translucent_offsets: [u16; 6],
+    /// Hidden chunks are skipped by the voxel passes
+    visible: bool,
 }
 
 impl ChunkMesh {
@@ -21,6 +29,10 @@ impl ChunkMesh {
             quads: None,
             buffer: None,
             offsets: [0u16; 6],
+            translucent_quads: None,
+            translucent_buffer: None,
+            translucent_offsets: [0u16; 6],
+            visible: true,
         }
     }
 
@@ -40,11 +52,27 @@ impl ChunkMesh {
         self.quads.as_deref()
     }
 
+    pub fn translucent_offsets(&self) -> &[u16; 6] {
+        &self.translucent_offsets
+    }
+
+    pub fn translucent_quads(&self) -> Option<&[Quad]> {
+        self.translucent_quads.as_deref()
+    }
+
     pub fn remesh(&mut self) {
         let mut quads = Vec::new();
-        self.chunk.remesh(&mut self.offsets, &mut quads);
+        let mut translucent_quads = Vec::new();
+
+        self.chunk.remesh(
+            &mut self.offsets,
+            &mut quads,
+            &mut self.translucent_offsets,
+            &mut translucent_quads,
+        );
 
         self.quads = Some(quads);
+        self.translucent_quads = Some(translucent_quads);
     }
 
     pub fn allocate(&mut self, device: &Device) -> bool {
@@ -55,12 +83,32 @@ impl ChunkMesh {
                 usage: wgpu::BufferUsages::VERTEX,
             }));
 
+            self.translucent_buffer = self
+                .translucent_quads
+                .as_ref()
+                .filter(|quads| !quads.is_empty())
+                .map(|quads| {
+                    device.create_buffer_init(&BufferInitDescriptor {
+                        label: None,
+                        contents: bytemuck::cast_slice(quads),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    })
+                });
+
             return true;
         }
 
         false
     }
 
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
     pub fn into_chunk(self) -> Chunk {
         self.chunk
     }
@@ -69,9 +117,17 @@ impl ChunkMesh {
         if let Some(buffer) = &self.buffer {
             buffer.destroy();
         }
+
+        if let Some(buffer) = &self.translucent_buffer {
+            buffer.destroy();
+        }
     }
 
     pub fn buffer(&self) -> &Option<Buffer> {
         &self.buffer
     }
+
+    pub fn translucent_buffer(&self) -> &Option<Buffer> {
+        &self.translucent_buffer
+    }
 }
@@ -0,0 +1,192 @@
+use super::object::{Object, Voxel};
+use cgmath::{Matrix4, SquareMatrix, Vector3};
+use std::{fmt::Display, path::Path};
+use wgpu::Device;
+
+#[derive(Debug)]
+pub enum ObjectError {
+    Io(std::io::Error),
+    /// Line number and content of a line that isn't a transform or a voxel
+    Syntax(usize, String),
+}
+
+impl Display for ObjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectError::Io(e) => write!(f, "failed to access object: {}", e),
+            ObjectError::Syntax(line, content) => {
+                write!(f, "line {}: unknown transform or voxel '{}'", line, content)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjectError {}
+
+impl Object {
+    /// Parses a `transform` line of 16 column-major values and one line per voxel,
+    /// `voxel <x> <y> <z> <rrggbbaa>` optionally followed by a block texture id and `tint`
+    /// `#` starts a comment, the transform defaults to the identity
+    pub fn parse(device: Device, source: &str) -> Result<Object, ObjectError> {
+        let mut object = Object::new(device, Matrix4::identity());
+
+        for (n, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let syntax = || ObjectError::Syntax(n + 1, line.to_string());
+
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("transform") => {
+                    let values = words
+                        .map(|w| w.parse::<f32>().ok())
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|v| v.len() == 16)
+                        .ok_or_else(syntax)?;
+
+                    let mut columns = [[0f32; 4]; 4];
+                    for (i, value) in values.into_iter().enumerate() {
+                        columns[i / 4][i % 4] = value;
+                    }
+
+                    object.set_transform(Matrix4::from(columns));
+                }
+                Some("voxel") => {
+                    let (position, voxel) = parse_voxel(words).ok_or_else(syntax)?;
+                    object.set_voxel(position, Some(voxel));
+                }
+                _ => return Err(syntax()),
+            }
+        }
+
+        object.update_meshes();
+
+        Ok(object)
+    }
+
+    pub fn load<P: AsRef<Path>>(device: Device, path: P) -> Result<Object, ObjectError> {
+        Self::parse(
+            device,
+            &std::fs::read_to_string(path).map_err(ObjectError::Io)?,
+        )
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ObjectError> {
+        std::fs::write(path, self.to_string()).map_err(ObjectError::Io)
+    }
+}
+
+fn parse_voxel<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<(Vector3<i32>, Voxel)> {
+    let mut coordinate = || words.next()?.parse::<i32>().ok();
+    let position = Vector3::new(coordinate()?, coordinate()?, coordinate()?);
+
+    let color = u32::from_str_radix(words.next()?, 16).ok()?.to_be_bytes();
+
+    let texture = match words.next() {
+        None => None,
+        Some(id) => {
+            let tint = match words.next() {
+                None => false,
+                Some("tint") => true,
+                Some(_) => return None,
+            };
+
+            Some((id.parse::<u8>().ok().filter(|id| *id != 0)?, tint))
+        }
+    };
+
+    if words.next().is_some() {
+        return None;
+    }
+
+    Some((position, Voxel { color, texture }))
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns: &[[f32; 4]; 4] = self.transform().as_ref();
+
+        write!(f, "transform")?;
+        for value in columns.iter().flatten() {
+            write!(f, " {}", value)?;
+        }
+        writeln!(f)?;
+
+        let mut voxels = self.voxels().collect::<Vec<_>>();
+        voxels.sort_by_key(|(p, _)| (p.z, p.y, p.x));
+
+        for (position, voxel) in voxels {
+            write!(
+                f,
+                "voxel {} {} {} {:08x}",
+                position.x,
+                position.y,
+                position.z,
+                u32::from_be_bytes(voxel.color)
+            )?;
+
+            match voxel.texture {
+                Some((id, true)) => writeln!(f, " {} tint", id)?,
+                Some((id, false)) => writeln!(f, " {}", id)?,
+                None => writeln!(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_object_round_trip() {
+    use crate::engine::rendering::test_backend;
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let device = backend.device().clone();
+
+    let mut object = Object::new(
+        device.clone(),
+        Matrix4::from_translation(Vector3::new(1.5, 0.0, -2.0)),
+    );
+    object.set_voxel(Vector3::new(0, 0, 0), Some(Voxel::new([255, 128, 0, 255])));
+    object.set_voxel(
+        Vector3::new(-1, 40, 3),
+        Some(Voxel {
+            color: [10, 20, 30, 128],
+            texture: Some((3, true)),
+        }),
+    );
+    object.update_meshes();
+
+    assert_eq!(object.chunks().len(), 2);
+
+    let source = object.to_string();
+    assert!(source.contains("voxel 0 0 0 ff8000ff\n"));
+    assert!(source.contains("voxel -1 40 3 0a141e80 3 tint\n"));
+
+    let parsed = Object::parse(device.clone(), &source).unwrap();
+    assert_eq!(parsed.transform(), object.transform());
+    assert_eq!(parsed.count(), 2);
+    assert_eq!(
+        parsed.get_voxel(Vector3::new(-1, 40, 3)),
+        object.get_voxel(Vector3::new(-1, 40, 3))
+    );
+
+    // Erasing the last voxel of a chunk drops the chunk
+    let mut parsed = parsed;
+    parsed.set_voxel(Vector3::new(-1, 40, 3), None);
+    parsed.update_meshes();
+    assert_eq!(parsed.chunks().len(), 1);
+
+    assert!(matches!(
+        Object::parse(device, "voxel 0 0 zero ffffffff"),
+        Err(ObjectError::Syntax(1, _))
+    ));
+}
@@ -1,5 +1,7 @@
 pub mod chunk;
 pub mod chunk_mesh;
+pub mod file;
 pub mod object;
 pub mod quad;
 pub mod rendering;
+pub mod texture;
@@ -12,15 +12,35 @@ pub struct Object {
     transform: Matrix4<f32>,
     // Chunks with additional information
     chunks: HashMap<Vector3<i32>, ChunkMesh>,
+    // Chunks changed by `set_voxel` since the last `update_meshes`
+    dirty: HashSet<Vector3<i32>>,
     // Device
     device: Device,
 }
 
+/// Color and block texture of an occupied voxel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Voxel {
+    pub color: [u8; 4],
+    /// Block texture id and tint flag, see `Chunk::set_texture`
+    pub texture: Option<(u8, bool)>,
+}
+
+impl Voxel {
+    pub fn new(color: [u8; 4]) -> Self {
+        Self {
+            color,
+            texture: None,
+        }
+    }
+}
+
 impl Object {
     pub fn new(device: Device, transform: Matrix4<f32>) -> Object {
         Object {
             transform,
             chunks: HashMap::new(),
+            dirty: HashSet::new(),
             device,
         }
     }
@@ -111,7 +131,7 @@ impl Object {
         Object {
             transform,
             chunks,
-
+            dirty: HashSet::new(),
             device,
         }
     }
@@ -147,7 +167,117 @@ impl Object {
         self.chunks.get_mut(&position)
     }
 
-    pub fn chunks(&self) -> Iter<Vector3<i32>, ChunkMesh> {
+    pub fn chunks(&self) -> Iter<'_, Vector3<i32>, ChunkMesh> {
         self.chunks.iter()
     }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Voxel at a position in object voxel coordinates, chunk `offset` starts at
+    /// `offset * CHUNK_SIZE`
+    pub fn get_voxel(&self, position: Vector3<i32>) -> Option<Voxel> {
+        let (offset, [x, y, z]) = split(position);
+        let chunk = self.chunks.get(&offset)?.chunk();
+
+        if !chunk.get_occupied(x, y, z) {
+            return None;
+        }
+
+        Some(Voxel {
+            color: chunk.get_color(x, y, z).unwrap_or_default(),
+            texture: chunk.get_texture(x, y, z),
+        })
+    }
+
+    /// Places or clears a voxel, creating its chunk as needed
+    /// The changes become visible with `update_meshes`
+    pub fn set_voxel(&mut self, position: Vector3<i32>, voxel: Option<Voxel>) {
+        let (offset, [x, y, z]) = split(position);
+
+        let chunk = match (self.chunks.get_mut(&offset), voxel) {
+            (Some(chunk), _) => chunk,
+            (None, Some(_)) => self
+                .chunks
+                .entry(offset)
+                .or_insert_with(|| ChunkMesh::new(Chunk::empty())),
+            (None, None) => return,
+        };
+
+        let chunk = chunk.chunk_mut();
+
+        match voxel {
+            Some(voxel) => {
+                chunk.set(x, y, z, true, voxel.color);
+
+                let (id, tint) = voxel.texture.unwrap_or((0, false));
+                chunk.set_texture(x, y, z, id, tint);
+            }
+            None => chunk.set(x, y, z, false, [0; 4]),
+        }
+
+        self.dirty.insert(offset);
+    }
+
+    /// Remeshes and uploads the chunks changed by `set_voxel`, emptied chunks are removed
+    pub fn update_meshes(&mut self) {
+        for offset in std::mem::take(&mut self.dirty) {
+            let Some(chunk) = self.chunks.get_mut(&offset) else {
+                continue;
+            };
+
+            if chunk.chunk().count() == 0 {
+                self.chunks.remove(&offset);
+                continue;
+            }
+
+            chunk.remesh();
+            chunk.allocate(&self.device);
+        }
+    }
+
+    /// Occupied voxels in object voxel coordinates
+    pub fn voxels(&self) -> impl Iterator<Item = (Vector3<i32>, Voxel)> + '_ {
+        self.chunks.iter().flat_map(|(offset, chunk)| {
+            let chunk = chunk.chunk();
+            let size = CHUNK_SIZE as i32;
+
+            (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE).filter_map(move |n| {
+                let (x, y, z) = (
+                    n % CHUNK_SIZE,
+                    n / CHUNK_SIZE % CHUNK_SIZE,
+                    n / (CHUNK_SIZE * CHUNK_SIZE),
+                );
+
+                if !chunk.get_occupied(x, y, z) {
+                    return None;
+                }
+
+                let voxel = Voxel {
+                    color: chunk.get_color(x, y, z).unwrap_or_default(),
+                    texture: chunk.get_texture(x, y, z),
+                };
+
+                Some((
+                    offset * size + Vector3::new(x as i32, y as i32, z as i32),
+                    voxel,
+                ))
+            })
+        })
+    }
+}
+
+/// Chunk offset and chunk-local coordinates of a voxel
+fn split(position: Vector3<i32>) -> (Vector3<i32>, [usize; 3]) {
+    let size = CHUNK_SIZE as i32;
+
+    (
+        position.map(|c| c.div_euclid(size)),
+        [
+            position.x.rem_euclid(size) as usize,
+            position.y.rem_euclid(size) as usize,
+            position.z.rem_euclid(size) as usize,
+        ],
+    )
 }
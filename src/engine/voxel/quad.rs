@@ -47,12 +47,30 @@ impl Quad {
         self.color.to_le_bytes()
     }
 
+    /// Block texture id, `0` renders the flat color
+    pub fn texture_id(&self) -> u8 {
+        ((self.low >> 21) & 0b01111111) as u8
+    }
+
     pub fn set_texture_id(&mut self, id: u8) {
         // Erst die alten Bits löschen
         self.low &= !(0b01111111 << 21);
         // Dann die neuen Bits setzen
         self.low |= ((0b01111111 & id) as u32) << 21;
     }
+
+    /// Whether a textured quad is multiplied with its color
+    pub fn tint(&self) -> bool {
+        self.low & (1 << 28) != 0
+    }
+
+    pub fn set_tint(&mut self, tint: bool) {
+        if tint {
+            self.low |= 1 << 28;
+        } else {
+            self.low &= !(1 << 28);
+        }
+    }
 }
 
 impl Debug for Quad {
@@ -62,7 +80,9 @@ impl Debug for Quad {
             .field("y", &self.y())
             .field("z", &self.z())
             .field("direction", &self.direction())
-            .field("texture_id", &self.color())
+            .field("color", &self.color())
+            .field("texture_id", &self.texture_id())
+            .field("tint", &self.tint())
             .finish()
     }
 }
@@ -89,8 +109,26 @@ fn test_quad() {
                     assert_eq!(quad.z(), z as u32);
                     assert_eq!(quad.color(), [0u8; 4]);
                     assert_eq!(quad.direction(), *d);
+                    assert_eq!(quad.texture_id(), 0);
                 }
             }
         }
     }
 }
+
+#[test]
+fn test_quad_texture() {
+    let mut quad = Quad::new(Direction::Back, 31, 31, 31, [1, 2, 3, 4]);
+
+    for id in 0..128u8 {
+        quad.set_texture_id(id);
+        quad.set_tint(id % 2 == 0);
+
+        assert_eq!(quad.texture_id(), id);
+        assert_eq!(quad.tint(), id % 2 == 0);
+        assert_eq!(quad.x(), 31);
+        assert_eq!(quad.y(), 31);
+        assert_eq!(quad.z(), 31);
+        assert_eq!(quad.direction(), Direction::Back);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod pass;
 pub mod pipeline;
+pub mod translucent;
+mod uniforms;
@@ -1,50 +1,61 @@
 use super::pipeline::VoxelPipeline;
 use crate::engine::{
+    profiler::Counters,
     rendering::{
-        configuration::Configuration, frame::Frame, pass::RenderPass, pipeline::GetPipeline,
+        configuration::Configuration, frame::Frame, pass::RenderPass,
+        pipeline::MultisampledPipeline,
     },
+    scene::SceneGraph,
     voxel::{chunk_mesh::ChunkMesh, object::Object},
 };
 use cgmath::{Array, Matrix, Matrix4, Vector3};
+use std::sync::Arc;
 use wgpu::CommandEncoder;
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
-struct PushConstant {
+pub(super) struct PushConstant {
     transform: [f32; 4 * 4],
     offset: [i32; 3],
 }
 
-pub struct VoxelPass {
-    encoder: CommandEncoder,
-    pass: wgpu::RenderPass<'static>,
-}
-
-impl VoxelPass {
-    pub fn render_object(&mut self, object: &Object) {
+impl PushConstant {
+    pub(super) fn new(transform: &Matrix4<f32>, offset: Vector3<i32>) -> Self {
         let mut pc = PushConstant {
             transform: [0f32; 4 * 4],
             offset: [0i32; 3],
         };
 
-        let tmp = unsafe { std::slice::from_raw_parts(object.transform().as_ptr(), 4 * 4) };
-
+        let tmp = unsafe { std::slice::from_raw_parts(transform.as_ptr(), 4 * 4) };
         pc.transform[..].copy_from_slice(tmp);
 
+        let tmp = unsafe { std::slice::from_raw_parts(offset.as_ptr(), 3) };
+        pc.offset[..].copy_from_slice(tmp);
+
+        pc
+    }
+}
+
+pub struct VoxelPass {
+    encoder: CommandEncoder,
+    pass: wgpu::RenderPass<'static>,
+    pipeline: Arc<VoxelPipeline>,
+    frame: u64,
+    counters: Counters,
+}
+
+impl VoxelPass {
+    pub fn render_object(&mut self, object: &Object) {
         for (offset, chunk) in object.chunks() {
-            if let Some(buffer) = chunk.buffer() {
-                pc.offset = [offset.x, offset.y, offset.z];
-                self.pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
-                    0,
-                    bytemuck::cast_slice(&[pc]),
-                );
-
-                // Set instance buffer
-                self.pass.set_vertex_buffer(1, buffer.slice(..));
-
-                // Draw chunk
-                self.pass.draw(0..4, 0..chunk.quads().unwrap().len() as u32);
+            self.render_chunk(*object.transform(), *offset, chunk);
+        }
+    }
+
+    /// Renders the objects of `scene` at their world matrices, call `SceneGraph::update` first
+    pub fn render_scene(&mut self, scene: &SceneGraph) {
+        for (transform, object) in scene.objects() {
+            for (offset, chunk) in object.chunks() {
+                self.render_chunk(transform, *offset, chunk);
             }
         }
     }
@@ -55,29 +66,28 @@ impl VoxelPass {
         offset: Vector3<i32>,
         chunk: &ChunkMesh,
     ) {
-        let mut pc = PushConstant {
-            transform: [0f32; 4 * 4],
-            offset: [0i32; 3],
-        };
+        if !chunk.is_visible() {
+            return;
+        }
 
-        let tmp = unsafe { std::slice::from_raw_parts(transform.as_ptr(), 4 * 4) };
-        pc.transform[..].copy_from_slice(tmp);
-
-        let tmp = unsafe { std::slice::from_raw_parts(offset.as_ptr(), 3) };
-        pc.offset[..].copy_from_slice(tmp);
+        let pc = PushConstant::new(&transform, offset);
 
         if let Some(buffer) = chunk.buffer() {
-            self.pass.set_push_constants(
-                wgpu::ShaderStages::VERTEX,
-                0,
-                bytemuck::cast_slice(&[pc]),
-            );
+            self.pipeline
+                .set_push_constant(&mut self.pass, self.frame, &pc);
 
             // Set instance buffer
             self.pass.set_vertex_buffer(1, buffer.slice(..));
 
             // Draw chunk
-            self.pass.draw(0..4, 0..chunk.quads().unwrap().len() as u32);
+            let instances = chunk.quads().unwrap().len() as u32;
+            self.pass.draw(0..4, 0..instances);
+
+            self.counters += Counters {
+                draw_calls: 1,
+                instances: instances as u64,
+                chunks: 1,
+            };
         }
     }
 }
@@ -85,73 +95,148 @@ impl VoxelPass {
 impl RenderPass for VoxelPass {
     type RequiredPipeline = VoxelPipeline;
 
-    fn start<C: Configuration + GetPipeline<VoxelPipeline>>(frame: &Frame<C>) -> Self {
-        let view = frame
-            .output()
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = frame.renderer().backend().device().create_command_encoder(
-            &wgpu::CommandEncoderDescriptor {
-                label: Some("vengine::render_scene_encoder"),
-            },
-        );
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let pipeline = frame.renderer().pipeline::<VoxelPipeline>();
 
-        let depth_view = frame
-            .renderer()
-            .depth_texture()
-            .lock()
-            .unwrap()
-            .view
-            .clone();
-
-        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &depth_view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
-
-        pass.set_pipeline(frame.renderer().configuration().get_pipeline().pipeline());
-        pass.set_bind_group(0, frame.renderer().camera().bind_group(), &[]);
-
-        pass.set_vertex_buffer(
-            0,
-            frame
-                .renderer()
-                .configuration()
-                .get_pipeline()
-                .quad()
-                .slice(..),
-        );
+        let (encoder, pass) = begin(frame, &pipeline.pipeline(), "vengine::render_scene_encoder");
 
         Self {
-            pass: pass.forget_lifetime(),
+            pass,
             encoder,
+            pipeline,
+            frame: frame.index(),
+            counters: Counters::default(),
         }
     }
 
-    fn finish<C: Configuration + GetPipeline<VoxelPipeline>>(self, frame: &Frame<C>) {
+    fn finish<C: Configuration>(self, frame: &Frame<C>) {
+        frame.renderer().profiler().count(self.counters);
         frame.push_encoder(self.encoder);
     }
 }
+
+/// Starts a render pass on the frame output with the camera and quad buffer bound
+pub(super) fn begin<C: Configuration>(
+    frame: &Frame<C>,
+    pipeline: &MultisampledPipeline,
+    label: &str,
+) -> (CommandEncoder, wgpu::RenderPass<'static>) {
+    let mut encoder = frame
+        .renderer()
+        .backend()
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some(label) });
+
+    let (view, resolve_target) = frame.color_target();
+
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: frame.color_load_op(),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: frame.depth_view(),
+            depth_ops: Some(wgpu::Operations {
+                load: frame.depth_load_op(),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    pass.set_pipeline(pipeline.get(frame.sample_count()));
+    pass.set_bind_group(0, frame.renderer().camera().bind_group(), &[]);
+    pass.set_bind_group(
+        1,
+        &*frame
+            .renderer()
+            .pipeline::<VoxelPipeline>()
+            .texture_bind_group(),
+        &[],
+    );
+
+    pass.set_vertex_buffer(
+        0,
+        frame
+            .renderer()
+            .pipeline::<VoxelPipeline>()
+            .quad()
+            .slice(..),
+    );
+
+    let pass = pass.forget_lifetime();
+
+    (encoder, pass)
+}
+
+/// Renders a 3x3x3 cube, with or without push constants
+/// `None` without an adapter or with push constants the adapter doesn't support
+#[cfg(test)]
+fn render_test_cube(push_constants: bool) -> Option<crate::engine::rendering::readback::Image> {
+    use crate::engine::rendering::{test_backend, Renderer};
+    use cgmath::{Point3, SquareMatrix};
+
+    let backend = test_backend(64, 64)?;
+
+    if push_constants && !backend.supports_push_constants() {
+        return None;
+    }
+
+    let renderer = Renderer::new((), backend).unwrap();
+    renderer.pipelines().insert(VoxelPipeline::new(
+        renderer.backend(),
+        renderer.camera(),
+        push_constants,
+    ));
+
+    renderer.camera().set_eye(Point3::new(6.0, 5.0, -4.0));
+    renderer.camera().set_look_at(Point3::new(1.0, 1.0, 1.0));
+
+    let voxels = (0..27)
+        .map(|n| {
+            let position = [n % 3, n / 3 % 3, n / 9];
+            let color = [80 * position[0] as u8, 80 * position[1] as u8, 200, 255];
+
+            (position, color)
+        })
+        .collect();
+
+    let object = Object::from_voxels(
+        renderer.backend().device().clone(),
+        Matrix4::identity(),
+        voxels,
+    );
+
+    let frame = renderer.start_frame().unwrap();
+
+    let mut pass: VoxelPass = frame.start_render_pass().unwrap();
+    pass.render_object(&object);
+    frame.finish_render_pass(pass);
+
+    renderer.finish_frame(frame);
+
+    renderer.read_output()
+}
+
+#[test]
+fn test_voxel_pass_golden() {
+    use crate::engine::rendering::readback;
+
+    let Some(fallback) = render_test_cube(false) else {
+        return;
+    };
+
+    readback::assert_golden(&fallback, "voxel_pass", 2);
+
+    // The push constant path has to match the uniform buffer fallback where it is supported
+    if let Some(image) = render_test_cube(true) {
+        readback::assert_golden(&image, "voxel_pass", 2);
+    }
+}
@@ -1,87 +1,304 @@
+use super::{
+    pass::PushConstant,
+    uniforms::{self, DrawUniforms},
+};
 use crate::engine::{
-    rendering::{backend::Backend, camera::Camera, pipeline::Pipeline, texture::Texture},
-    voxel::quad::Quad,
+    rendering::{
+        backend::Backend,
+        camera::Camera,
+        pipeline::{MultisampledPipeline, Pipeline},
+        shader::{self, ReloadShader, ShaderError},
+        texture::Texture,
+    },
+    voxel::{
+        quad::Quad,
+        texture::{BlockTextures, TextureError},
+    },
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard},
+};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, PipelineLayout, Queue,
+    RenderPipeline, Sampler, ShaderModule,
 };
-use wgpu::{util::DeviceExt, Buffer, RenderPipeline};
 
 pub struct VoxelPipeline {
-    pipeline: RenderPipeline,
+    pipeline: RwLock<MultisampledPipeline>,
+    translucent_pipeline: RwLock<MultisampledPipeline>,
+    layout: PipelineLayout,
     quad: Buffer,
+    texture_bind_group_layout: BindGroupLayout,
+    texture_bind_group: Mutex<BindGroup>,
+    sampler: Sampler,
+    /// Fallback for devices without push constants
+    draw_uniforms: Option<Mutex<DrawUniforms>>,
+    device: Device,
+    queue: Arc<Queue>,
 }
 
 impl VoxelPipeline {
-    pub fn pipeline(&self) -> &RenderPipeline {
-        &self.pipeline
+    pub fn pipeline(&self) -> RwLockReadGuard<'_, MultisampledPipeline> {
+        self.pipeline.read().unwrap()
+    }
+
+    /// Alpha blended variant without depth writes
+    pub fn translucent_pipeline(&self) -> RwLockReadGuard<'_, MultisampledPipeline> {
+        self.translucent_pipeline.read().unwrap()
+    }
+
+    /// Whether draws pass their transform as push constants instead of a uniform buffer
+    pub fn uses_push_constants(&self) -> bool {
+        self.draw_uniforms.is_none()
+    }
+
+    /// Sets transform and chunk offset for the following draws of `pass`
+    pub(super) fn set_push_constant(
+        &self,
+        pass: &mut wgpu::RenderPass<'_>,
+        frame: u64,
+        push_constant: &PushConstant,
+    ) {
+        match &self.draw_uniforms {
+            None => pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::bytes_of(push_constant),
+            ),
+            Some(uniforms) => {
+                let (bind_group, offset) =
+                    uniforms
+                        .lock()
+                        .unwrap()
+                        .push(&self.device, &self.queue, frame, push_constant);
+
+                pass.set_bind_group(2, &bind_group, &[offset]);
+            }
+        }
     }
 
     pub fn quad(&self) -> &Buffer {
         &self.quad
     }
+
+    pub fn texture_bind_group(&self) -> MutexGuard<'_, BindGroup> {
+        self.texture_bind_group.lock().unwrap()
+    }
+
+    /// Uploads the block tiles as a texture array, replacing the previous textures
+    /// Fails without uploading anything when the device can't hold all tiles
+    pub fn set_textures(
+        &self,
+        backend: &Backend,
+        textures: &BlockTextures,
+    ) -> Result<(), TextureError> {
+        textures.check_limits(&backend.device().limits())?;
+
+        let bind_group = Self::create_texture_bind_group(
+            backend.device(),
+            backend.queue(),
+            &self.texture_bind_group_layout,
+            &self.sampler,
+            textures,
+        );
+
+        *self.texture_bind_group() = bind_group;
+
+        Ok(())
+    }
+
+    fn create_texture_bind_group(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        textures: &BlockTextures,
+    ) -> BindGroup {
+        let size = textures.tile_size().max(1);
+        let layers = textures.tiles().len().max(1) as u32;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("vengine::voxel_textures"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, tile) in textures.tiles().iter().enumerate() {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                tile,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size * 4),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("vengine::voxel_textures_view"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let layer_table = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vengine::voxel_layer_table"),
+            contents: bytemuck::cast_slice(&textures.layer_table()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("vengine::voxel_texture_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: layer_table.as_entire_binding(),
+                },
+            ],
+        })
+    }
 }
 
 impl Pipeline for VoxelPipeline {
-    fn initialize(backend: &Backend<'_>, camera: &Camera) -> Self {
-        let render_pipeline_layout =
+    fn initialize(backend: &Backend, camera: &Camera) -> Self {
+        Self::new(backend, camera, backend.supports_push_constants())
+    }
+}
+
+impl VoxelPipeline {
+    /// Without `push_constants` draws use a dynamic-offset uniform buffer at group 2
+    pub fn new(backend: &Backend, camera: &Camera, push_constants: bool) -> Self {
+        let texture_bind_group_layout =
             backend
+                .device()
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("vengine::voxel_texture_bind_group_layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2Array,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::VERTEX,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+
+        // Pixel art tiles, repeated across merged quads
+        let sampler = backend.device().create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("vengine::voxel_texture_sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        // Placeholder until textures are set
+        let texture_bind_group = Self::create_texture_bind_group(
+            backend.device(),
+            backend.queue(),
+            &texture_bind_group_layout,
+            &sampler,
+            &BlockTextures::new(1),
+        );
+
+        let draw_bind_group_layout =
+            (!push_constants).then(|| uniforms::bind_group_layout(backend.device()));
+
+        let render_pipeline_layout = match &draw_bind_group_layout {
+            None => backend
                 .device()
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some("vengine::voxel_pipeline_layout"),
-                    bind_group_layouts: &[camera.bind_group_layout()],
+                    bind_group_layouts: &[camera.bind_group_layout(), &texture_bind_group_layout],
                     push_constant_ranges: &[wgpu::PushConstantRange {
                         stages: wgpu::ShaderStages::VERTEX,
                         range: 0..(size_of::<[f32; 4 * 4]>() + size_of::<[i32; 3]>()) as u32,
                     }],
-                });
+                }),
+            Some(draw_bind_group_layout) => {
+                backend
+                    .device()
+                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: Some("vengine::voxel_pipeline_layout"),
+                        bind_group_layouts: &[
+                            camera.bind_group_layout(),
+                            &texture_bind_group_layout,
+                            draw_bind_group_layout,
+                        ],
+                        push_constant_ranges: &[],
+                    })
+            }
+        };
 
-        let shader = backend
-            .device()
-            .create_shader_module(wgpu::include_wgsl!("shaders/base.wgsl"));
+        let source = concat!(
+            include_str!("../../rendering/shaders/camera.wgsl"),
+            include_str!("shaders/base.wgsl")
+        );
 
-        let pipeline = backend
-            .device()
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("vengine::voxel_pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"),
-                    buffers: &[vertex_desc(), instance_desc()],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: *backend.surface_format(),
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleStrip,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: Texture::DEPTH_FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
-                cache: None,
-            });
+        let (pipeline, translucent_pipeline) = if push_constants {
+            create_pipelines(backend, &render_pipeline_layout, source)
+        } else {
+            create_pipelines(
+                backend,
+                &render_pipeline_layout,
+                &uniforms::uniform_source(source),
+            )
+        };
 
         let quad = backend
             .device()
@@ -96,10 +313,150 @@ impl Pipeline for VoxelPipeline {
                 usage: wgpu::BufferUsages::VERTEX,
             });
 
-        Self { pipeline, quad }
+        Self {
+            pipeline: RwLock::new(pipeline),
+            translucent_pipeline: RwLock::new(translucent_pipeline),
+            layout: render_pipeline_layout,
+            quad,
+            texture_bind_group_layout,
+            texture_bind_group: Mutex::new(texture_bind_group),
+            sampler,
+            draw_uniforms: draw_bind_group_layout
+                .map(|layout| Mutex::new(DrawUniforms::new(backend.device(), layout))),
+            device: backend.device().clone(),
+            queue: backend.queue().clone(),
+        }
+    }
+}
+
+impl ReloadShader for VoxelPipeline {
+    fn shader_files() -> Vec<PathBuf> {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/engine");
+
+        vec![
+            root.join("rendering/shaders/camera.wgsl"),
+            root.join("voxel/rendering/shaders/base.wgsl"),
+        ]
+    }
+
+    fn reload_shader(&self, backend: &Backend, source: &str) -> Result<(), ShaderError> {
+        let source = if self.uses_push_constants() {
+            source.to_string()
+        } else {
+            uniforms::uniform_source(source)
+        };
+
+        shader::validate_wgsl("vengine::voxel_shader", &source, &["vs_main", "fs_main"])?;
+
+        // Naga can't tell whether the shader matches the layout and vertex buffers
+        let (pipeline, translucent_pipeline) =
+            shader::capture_validation(backend, "vengine::voxel_shader", || {
+                create_pipelines(backend, &self.layout, &source)
+            })?;
+
+        *self.pipeline.write().unwrap() = pipeline;
+        *self.translucent_pipeline.write().unwrap() = translucent_pipeline;
+
+        Ok(())
     }
 }
 
+/// Opaque and translucent pipelines sharing one shader module
+fn create_pipelines(
+    backend: &Backend,
+    layout: &PipelineLayout,
+    source: &str,
+) -> (MultisampledPipeline, MultisampledPipeline) {
+    let shader = backend
+        .device()
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vengine::voxel_shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+    let pipeline = MultisampledPipeline::new(backend, |sample_count| {
+        create_pipeline(
+            backend,
+            layout,
+            &shader,
+            "vengine::voxel_pipeline",
+            wgpu::BlendState::REPLACE,
+            true,
+            sample_count,
+        )
+    });
+
+    let translucent_pipeline = MultisampledPipeline::new(backend, |sample_count| {
+        create_pipeline(
+            backend,
+            layout,
+            &shader,
+            "vengine::voxel_translucent_pipeline",
+            wgpu::BlendState::ALPHA_BLENDING,
+            false,
+            sample_count,
+        )
+    });
+
+    (pipeline, translucent_pipeline)
+}
+
+fn create_pipeline(
+    backend: &Backend,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    label: &str,
+    blend: wgpu::BlendState,
+    depth_write_enabled: bool,
+    sample_count: u32,
+) -> RenderPipeline {
+    backend
+        .device()
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[vertex_desc(), instance_desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_FORMAT,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+}
+
 fn vertex_desc() -> wgpu::VertexBufferLayout<'static> {
     wgpu::VertexBufferLayout {
         array_stride: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
@@ -130,3 +487,44 @@ pub fn instance_desc() -> wgpu::VertexBufferLayout<'static> {
         ],
     }
 }
+
+#[test]
+fn test_reload_shader() {
+    use crate::engine::rendering::{test_backend, Renderer};
+
+    let Some(backend) = test_backend(8, 8) else {
+        return;
+    };
+
+    let renderer = Renderer::new((), backend).unwrap();
+    let pipeline = renderer.register_pipeline::<VoxelPipeline>();
+
+    let source = VoxelPipeline::shader_files()
+        .iter()
+        .map(std::fs::read_to_string)
+        .collect::<std::io::Result<String>>()
+        .unwrap();
+
+    assert!(pipeline.reload_shader(renderer.backend(), &source).is_ok());
+
+    let broken = source.replace("fn fs_main", "fn fs_main_renamed");
+    let error = pipeline
+        .reload_shader(renderer.backend(), &broken)
+        .unwrap_err();
+
+    assert!(error.message.contains("fs_main"));
+
+    // Valid WGSL whose bindings don't match the pipeline layout
+    let mismatched = source.replace("@group(1) @binding(0)", "@group(3) @binding(0)");
+    assert!(shader::validate_wgsl("test", &mismatched, &[]).is_ok());
+    let error = pipeline
+        .reload_shader(renderer.backend(), &mismatched)
+        .unwrap_err();
+    assert!(error.message.contains("create_render_pipeline"));
+
+    // The previous pipelines are kept and still render
+    let frame = renderer.start_frame().unwrap();
+    let pass: super::pass::VoxelPass = frame.start_render_pass().unwrap();
+    frame.finish_render_pass(pass);
+    renderer.finish_frame(frame);
+}
@@ -1,9 +1,4 @@
-// Camera
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-};
-@group(0) @binding(0)
-var<uniform> camera: CameraUniform;
+// Camera and sky bindings are prepended from rendering/shaders/camera.wgsl
 
 struct PushConstant {
     transform: mat4x4<f32>,
@@ -12,6 +7,14 @@ struct PushConstant {
 
 var<push_constant> pc: PushConstant;
 
+// Block textures
+@group(1) @binding(0)
+var textures: texture_2d_array<f32>;
+@group(1) @binding(1)
+var textures_sampler: sampler;
+// Array layer per (texture id * 6 + direction), packed into vec4s
+@group(1) @binding(2)
+var<uniform> layers: array<vec4<u32>, 192>;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -20,6 +23,12 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) @interpolate(flat) layer: u32,
+    // 0 = color, 1 = texture, 2 = tinted texture
+    @location(4) @interpolate(flat) mode: u32,
+    @location(5) shade: f32,
 }
 
 struct InstanceInput {
@@ -41,6 +50,8 @@ fn vs_main(
     var position_y: u32 = (instance.low >> 6u) & 63u;
     var position_z: u32 = (instance.low >> 12u) & 63u;
     var direction: u32 = (instance.low >> 18u) & 7u;
+    var texture_id: u32 = (instance.low >> 21u) & 127u;
+    var tint: bool = ((instance.low >> 28u) & 1u) == 1u;
 
     var position: vec3<f32> = model.position;
 
@@ -72,42 +83,74 @@ fn vs_main(
         default: {}
     }
 
-    position += vec3(f32(position_x), f32(position_y), f32(position_z)) + (vec3(f32(pc.offset.x), f32(pc.offset.y), f32(pc.offset.z)) * CHUNK_SIZE * VOXEL_SIZE);
+    position += vec3(f32(position_x), f32(position_y), f32(position_z));
+
+    // Texture coordinates follow the chunk-local position so merged quads tile the texture
+    switch direction {
+        // Left, Right
+        case 0u, 1u: {
+            out.uv = vec2(position.z, -position.y);
+        }
+        // Up, Down
+        case 2u, 3u: {
+            out.uv = vec2(position.x, position.z);
+        }
+        // Front, Back
+        default: {
+            out.uv = vec2(position.x, -position.y);
+        }
+    }
+
+    let index = texture_id * 6u + direction;
+    out.layer = layers[index / 4u][index % 4u];
+
+    if texture_id == 0u {
+        out.mode = 0u;
+    } else if tint {
+        out.mode = 2u;
+    } else {
+        out.mode = 1u;
+    }
+
+    position += vec3(f32(pc.offset.x), f32(pc.offset.y), f32(pc.offset.z)) * CHUNK_SIZE * VOXEL_SIZE;
 
     let pos4 = pc.transform * vec4<f32>(position, 1.0);
     position = (pos4.xyz / pos4.w);
 
-    out.color = unpack_color(instance.color);
-
     // Apply "shading"
     switch direction {
         // Left
         case 0u: {
-            out.color = darken_color(out.color, 0.3);
+            out.shade = 1.0 - 0.3;
         }
         // Right
         case 1u: {
-            out.color = darken_color(out.color, 0.325);
+            out.shade = 1.0 - 0.325;
         }
         // Up
         case 2u: {
-            out.color = darken_color(out.color, 0.15);
+            out.shade = 1.0 - 0.15;
         }
         // Down
         case 3u: {
-            out.color = darken_color(out.color, 0.4);
-        } 
+            out.shade = 1.0 - 0.4;
+        }
         // Front
         case 4u: {
-            out.color = darken_color(out.color, 0.35);
+            out.shade = 1.0 - 0.35;
         }
         // Back
         case 5u: {
-            out.color = darken_color(out.color, 0.375);
+            out.shade = 1.0 - 0.375;
+        }
+        default: {
+            out.shade = 1.0;
         }
-        default: {}
     }
 
+    out.color = darken_color(unpack_color(instance.color), 1.0 - out.shade);
+
+    out.world_position = position;
     out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
 
     return out;
@@ -129,5 +172,20 @@ fn darken_color(color: vec4<f32>, factor: f32) -> vec4<f32> {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    // Sampled unconditionally to stay in uniform control flow
+    let texel = textureSample(textures, textures_sampler, in.uv, in.layer);
+
+    var color = in.color;
+
+    switch in.mode {
+        case 1u: {
+            color = vec4<f32>(texel.rgb * in.shade, texel.a * in.color.a);
+        }
+        case 2u: {
+            color = texel * in.color;
+        }
+        default: {}
+    }
+
+    return apply_fog(color, in.world_position);
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use super::{
+    pass::{begin, PushConstant},
+    pipeline::VoxelPipeline,
+};
+use crate::engine::{
+    profiler::Counters,
+    rendering::{configuration::Configuration, frame::Frame, pass::RenderPass},
+    scene::SceneGraph,
+    voxel::{chunk::chunk_bounds, chunk_mesh::ChunkMesh, object::Object},
+};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, Transform, Vector3};
+use std::sync::Arc;
+use wgpu::{Buffer, CommandEncoder};
+
+struct TranslucentChunk {
+    push_constant: PushConstant,
+    buffer: Buffer,
+    instances: u32,
+    distance: f32,
+}
+
+/// Renders the translucent quads of chunks with alpha blending
+/// Start it after every opaque `VoxelPass`, chunks are drawn back-to-front on finish
+pub struct TranslucentVoxelPass {
+    encoder: CommandEncoder,
+    pass: wgpu::RenderPass<'static>,
+    eye: Point3<f32>,
+    chunks: Vec<TranslucentChunk>,
+    pipeline: Arc<VoxelPipeline>,
+    frame: u64,
+}
+
+impl TranslucentVoxelPass {
+    pub fn render_object(&mut self, object: &Object) {
+        for (offset, chunk) in object.chunks() {
+            self.render_chunk(*object.transform(), *offset, chunk);
+        }
+    }
+
+    /// Renders the objects of `scene` at their world matrices, call `SceneGraph::update` first
+    pub fn render_scene(&mut self, scene: &SceneGraph) {
+        for (transform, object) in scene.objects() {
+            for (offset, chunk) in object.chunks() {
+                self.render_chunk(transform, *offset, chunk);
+            }
+        }
+    }
+
+    pub fn render_chunk(
+        &mut self,
+        transform: Matrix4<f32>,
+        offset: Vector3<i32>,
+        chunk: &ChunkMesh,
+    ) {
+        let (Some(buffer), Some(quads)) = (chunk.translucent_buffer(), chunk.translucent_quads())
+        else {
+            return;
+        };
+
+        if !chunk.is_visible() {
+            return;
+        }
+
+        let (min, max) = chunk_bounds(offset);
+        let center = transform.transform_point(min.midpoint(max));
+
+        self.chunks.push(TranslucentChunk {
+            push_constant: PushConstant::new(&transform, offset),
+            buffer: buffer.clone(),
+            instances: quads.len() as u32,
+            distance: (center.to_vec() - self.eye.to_vec()).magnitude2(),
+        });
+    }
+}
+
+impl RenderPass for TranslucentVoxelPass {
+    type RequiredPipeline = VoxelPipeline;
+
+    fn start<C: Configuration>(frame: &Frame<C>) -> Self {
+        let pipeline = frame.renderer().pipeline::<VoxelPipeline>();
+
+        let (encoder, pass) = begin(
+            frame,
+            &pipeline.translucent_pipeline(),
+            "vengine::render_translucent_encoder",
+        );
+
+        Self {
+            encoder,
+            pass,
+            eye: frame.renderer().camera().get_eye(),
+            chunks: Vec::new(),
+            pipeline,
+            frame: frame.index(),
+        }
+    }
+
+    fn finish<C: Configuration>(mut self, frame: &Frame<C>) {
+        // Back-to-front
+        self.chunks
+            .sort_by(|a, b| b.distance.total_cmp(&a.distance));
+
+        let mut counters = Counters::default();
+
+        for chunk in &self.chunks {
+            self.pipeline
+                .set_push_constant(&mut self.pass, self.frame, &chunk.push_constant);
+
+            self.pass.set_vertex_buffer(1, chunk.buffer.slice(..));
+            self.pass.draw(0..4, 0..chunk.instances);
+
+            counters += Counters {
+                draw_calls: 1,
+                instances: chunk.instances as u64,
+                chunks: 1,
+            };
+        }
+
+        drop(self.pass);
+
+        frame.renderer().profiler().count(counters);
+
+        frame.push_encoder(self.encoder);
+    }
+}
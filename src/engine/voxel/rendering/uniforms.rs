@@ -0,0 +1,127 @@
+use super::pass::PushConstant;
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue};
+
+/// `PushConstant` padded to the size of the WGSL struct
+const DRAW_UNIFORM_SIZE: u64 = 80;
+
+/// Replaces the push constant declaration of `base.wgsl` for devices without push constants
+pub(super) fn uniform_source(source: &str) -> String {
+    source.replace(
+        "var<push_constant> pc: PushConstant;",
+        "@group(2) @binding(0)\nvar<uniform> pc: PushConstant;",
+    )
+}
+
+pub(super) fn bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("vengine::voxel_draw_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(DRAW_UNIFORM_SIZE),
+            },
+            count: None,
+        }],
+    })
+}
+
+/// Transform and chunk offset of every draw in a frame when push constants are unavailable
+/// Each draw writes its own slot and binds it with a dynamic offset
+pub(super) struct DrawUniforms {
+    layout: BindGroupLayout,
+    buffer: Buffer,
+    bind_group: BindGroup,
+    stride: u64,
+    capacity: u64,
+    next: u64,
+    frame: u64,
+}
+
+impl DrawUniforms {
+    pub(super) fn new(device: &Device, layout: BindGroupLayout) -> Self {
+        let stride = DRAW_UNIFORM_SIZE
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+
+        let (buffer, bind_group) = Self::allocate(device, &layout, stride, 256);
+
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            stride,
+            capacity: 256,
+            next: 0,
+            frame: 0,
+        }
+    }
+
+    fn allocate(
+        device: &Device,
+        layout: &BindGroupLayout,
+        stride: u64,
+        capacity: u64,
+    ) -> (Buffer, BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vengine::voxel_draw_buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("vengine::voxel_draw_bind_group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(DRAW_UNIFORM_SIZE),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Writes the next free slot of `frame`, returns the bind group and its dynamic offset
+    pub(super) fn push(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        frame: u64,
+        push_constant: &PushConstant,
+    ) -> (BindGroup, u32) {
+        if frame != self.frame {
+            self.frame = frame;
+            self.next = 0;
+        }
+
+        // Draws recorded earlier keep using the old buffer, their writes still land there
+        if self.next == self.capacity {
+            self.capacity *= 2;
+            self.next = 0;
+
+            (self.buffer, self.bind_group) =
+                Self::allocate(device, &self.layout, self.stride, self.capacity);
+        }
+
+        let offset = self.next * self.stride;
+        self.next += 1;
+
+        queue.write_buffer(&self.buffer, offset, bytemuck::bytes_of(push_constant));
+
+        (self.bind_group.clone(), offset as u32)
+    }
+}
+
+#[test]
+fn test_uniform_source() {
+    let source = uniform_source(include_str!("shaders/base.wgsl"));
+
+    assert!(!source.contains("var<push_constant>"));
+    assert!(source.contains("var<uniform> pc: PushConstant;"));
+}
@@ -0,0 +1,327 @@
+use ahash::HashMap;
+use std::{fmt::Display, path::Path};
+
+/// Texture ids are stored in 7 bits of a `Quad`, `0` means untextured
+pub const MAX_BLOCK_TEXTURES: usize = 127;
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(std::io::Error),
+    Decode(png::DecodingError),
+    TileSize {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    /// Pixel data length doesn't match the tile size
+    DataSize {
+        expected: usize,
+        found: usize,
+    },
+    UnknownTile(String),
+    TooManyBlocks,
+    /// More tiles than array layers the device supports
+    TooManyLayers {
+        max: u32,
+        found: u32,
+    },
+}
+
+impl Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureError::Io(e) => write!(f, "failed to read tile: {}", e),
+            TextureError::Decode(e) => write!(f, "failed to decode tile: {}", e),
+            TextureError::TileSize { expected, found } => write!(
+                f,
+                "tile is {}x{}, expected {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            TextureError::DataSize { expected, found } => {
+                write!(f, "tile data is {} bytes, expected {}", found, expected)
+            }
+            TextureError::UnknownTile(name) => write!(f, "unknown tile '{}'", name),
+            TextureError::TooManyBlocks => {
+                write!(f, "more than {} block textures", MAX_BLOCK_TEXTURES)
+            }
+            TextureError::TooManyLayers { max, found } => write!(
+                f,
+                "{} tiles exceed the {} texture array layers of the device",
+                found, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+impl From<std::io::Error> for TextureError {
+    fn from(e: std::io::Error) -> Self {
+        TextureError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for TextureError {
+    fn from(e: png::DecodingError) -> Self {
+        TextureError::Decode(e)
+    }
+}
+
+/// Tile names per face of a block
+#[derive(Clone, Copy, Debug)]
+pub enum BlockFaces<'a> {
+    All(&'a str),
+    /// Top, bottom and the four sides
+    Sides {
+        top: &'a str,
+        bottom: &'a str,
+        side: &'a str,
+    },
+    /// One tile per `Direction` (Left, Right, Up, Down, Front, Back)
+    Each([&'a str; 6]),
+}
+
+impl<'a> BlockFaces<'a> {
+    fn names(&self) -> [&'a str; 6] {
+        match *self {
+            BlockFaces::All(name) => [name; 6],
+            BlockFaces::Sides { top, bottom, side } => [side, side, top, bottom, side, side],
+            BlockFaces::Each(names) => names,
+        }
+    }
+}
+
+/// Square RGBA tiles and the block types built from them
+/// Upload it with `VoxelPipeline::set_textures` as a texture array
+pub struct BlockTextures {
+    tile_size: u32,
+    tiles: Vec<Vec<u8>>,
+    names: HashMap<String, u32>,
+    /// Array layer per direction for every block texture id - 1
+    blocks: Vec<[u32; 6]>,
+}
+
+impl BlockTextures {
+    pub fn new(tile_size: u32) -> Self {
+        Self {
+            tile_size,
+            tiles: Vec::new(),
+            names: HashMap::default(),
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Adds a tile from tightly packed RGBA8 (sRGB) pixels, returns its array layer
+    pub fn add_tile(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> Result<u32, TextureError> {
+        if width != self.tile_size || height != self.tile_size {
+            return Err(TextureError::TileSize {
+                expected: (self.tile_size, self.tile_size),
+                found: (width, height),
+            });
+        }
+
+        let expected = (width * height * 4) as usize;
+
+        if rgba.len() != expected {
+            return Err(TextureError::DataSize {
+                expected,
+                found: rgba.len(),
+            });
+        }
+
+        if let Some(layer) = self.names.get(name) {
+            self.tiles[*layer as usize] = rgba;
+            return Ok(*layer);
+        }
+
+        let layer = self.tiles.len() as u32;
+
+        self.tiles.push(rgba);
+        self.names.insert(name.to_string(), layer);
+
+        Ok(layer)
+    }
+
+    pub fn add_tile_png(&mut self, name: &str, bytes: &[u8]) -> Result<u32, TextureError> {
+        let (width, height, rgba) = decode_png(bytes)?;
+
+        self.add_tile(name, width, height, rgba)
+    }
+
+    pub fn load_tile<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<u32, TextureError> {
+        let bytes = std::fs::read(path)?;
+
+        self.add_tile_png(name, &bytes)
+    }
+
+    /// Registers a block type, returns the texture id for `Chunk::set_texture`
+    pub fn add_block(&mut self, faces: BlockFaces) -> Result<u8, TextureError> {
+        if self.blocks.len() >= MAX_BLOCK_TEXTURES {
+            return Err(TextureError::TooManyBlocks);
+        }
+
+        let mut layers = [0u32; 6];
+
+        for (layer, name) in layers.iter_mut().zip(faces.names()) {
+            *layer = *self
+                .names
+                .get(name)
+                .ok_or_else(|| TextureError::UnknownTile(name.to_string()))?;
+        }
+
+        self.blocks.push(layers);
+
+        Ok(self.blocks.len() as u8)
+    }
+
+    /// Checks that the tiles fit into one texture array of a device with `limits`
+    pub fn check_limits(&self, limits: &wgpu::Limits) -> Result<(), TextureError> {
+        let layers = self.tiles.len() as u32;
+
+        if layers > limits.max_texture_array_layers {
+            return Err(TextureError::TooManyLayers {
+                max: limits.max_texture_array_layers,
+                found: layers,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    pub fn tiles(&self) -> &[Vec<u8>] {
+        &self.tiles
+    }
+
+    pub fn blocks(&self) -> &[[u32; 6]] {
+        &self.blocks
+    }
+
+    /// Layer lookup table indexed by `texture_id * 6 + direction`
+    pub fn layer_table(&self) -> [u32; (MAX_BLOCK_TEXTURES + 1) * 6] {
+        let mut table = [0u32; (MAX_BLOCK_TEXTURES + 1) * 6];
+
+        for (n, layers) in self.blocks.iter().enumerate() {
+            let start = (n + 1) * 6;
+            table[start..start + 6].copy_from_slice(layers);
+        }
+
+        table
+    }
+}
+
+/// Decodes a PNG into RGBA8 regardless of its color type and bit depth
+pub fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), TextureError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let buffer = &buffer[..info.buffer_size()];
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer.to_vec(),
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|p| [*p, *p, *p, u8::MAX]).collect(),
+        // Expanded by `normalize_to_color8`
+        png::ColorType::Indexed => unreachable!(),
+    };
+
+    Ok((info.width, info.height, rgba))
+}
+
+#[test]
+fn test_block_textures() {
+    let mut textures = BlockTextures::new(2);
+
+    assert!(matches!(
+        textures.add_tile("wrong", 4, 4, vec![0; 64]),
+        Err(TextureError::TileSize { .. })
+    ));
+
+    assert_eq!(textures.add_tile("grass", 2, 2, vec![0; 16]).unwrap(), 0);
+    assert_eq!(textures.add_tile("dirt", 2, 2, vec![0; 16]).unwrap(), 1);
+    assert_eq!(textures.add_tile("side", 2, 2, vec![0; 16]).unwrap(), 2);
+    assert!(matches!(
+        textures.add_tile("short", 2, 2, vec![0; 12]),
+        Err(TextureError::DataSize {
+            expected: 16,
+            found: 12
+        })
+    ));
+
+    let dirt = textures.add_block(BlockFaces::All("dirt")).unwrap();
+    let grass = textures
+        .add_block(BlockFaces::Sides {
+            top: "grass",
+            bottom: "dirt",
+            side: "side",
+        })
+        .unwrap();
+
+    assert_eq!(dirt, 1);
+    assert_eq!(grass, 2);
+    assert!(matches!(
+        textures.add_block(BlockFaces::All("stone")),
+        Err(TextureError::UnknownTile(_))
+    ));
+
+    let table = textures.layer_table();
+    assert_eq!(&table[6..12], &[1, 1, 1, 1, 1, 1]);
+    assert_eq!(&table[12..18], &[2, 2, 0, 1, 2, 2]);
+}
+
+#[test]
+fn test_check_limits() {
+    let mut textures = BlockTextures::new(1);
+
+    for name in ["a", "b", "c"] {
+        textures.add_tile(name, 1, 1, vec![0; 4]).unwrap();
+    }
+
+    let limits = wgpu::Limits {
+        max_texture_array_layers: 2,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        textures.check_limits(&limits),
+        Err(TextureError::TooManyLayers { max: 2, found: 3 })
+    ));
+    assert!(textures.check_limits(&wgpu::Limits::default()).is_ok());
+}
+
+#[test]
+fn test_decode_png() {
+    let mut bytes = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 255, 0]).unwrap();
+    }
+
+    let (width, height, rgba) = decode_png(&bytes).unwrap();
+
+    assert_eq!((width, height), (2, 1));
+    assert_eq!(rgba, vec![255, 0, 0, 255, 0, 255, 0, 255]);
+}